@@ -0,0 +1,270 @@
+use crate::de::{check_limit, Limits};
+use crate::lazy::{FrameScanner, Next};
+use crate::ser::{self, HEADER_MAX_LEN};
+use crate::{LimitKind, RESPKind, RESPType, Result};
+
+use std::convert::TryFrom;
+
+use std::future;
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+// Bytes read between two chances given to the executor to run other tasks.
+const YIELD_INTERVAL: usize = 64 * 1024;
+// Payloads at least this long are written straight from the value instead of being copied into a buffer.
+const DIRECT_WRITE_MIN: usize = 8 * 1024;
+
+// Buffered async reading, the same for the readers of tokio and futures-io, which wrap them in `Tokio` and `Futures`.
+pub(crate) trait PollBufRead {
+    fn poll_fill_buf(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>>;
+    fn consume(&mut self, n: usize);
+}
+
+// Async writing, the same for the writers of tokio and futures-io.
+pub(crate) trait PollWrite {
+    fn poll_write(&mut self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>>;
+}
+
+// A reader or writer with tokio's traits.
+#[cfg(feature = "tokio")]
+pub(crate) struct Tokio<T>(pub(crate) T);
+
+#[cfg(feature = "tokio")]
+impl<R: tokio::io::AsyncBufRead + Unpin> PollBufRead for Tokio<R> {
+    fn poll_fill_buf(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        Pin::new(&mut self.0).poll_fill_buf(cx)
+    }
+
+    fn consume(&mut self, n: usize) {
+        Pin::new(&mut self.0).consume(n)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<W: tokio::io::AsyncWrite + Unpin> PollWrite for Tokio<W> {
+    fn poll_write(&mut self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+}
+
+// A reader or writer with the traits of futures-io.
+#[cfg(feature = "futures-io")]
+pub(crate) struct Futures<T>(pub(crate) T);
+
+#[cfg(feature = "futures-io")]
+impl<R: futures_io::AsyncBufRead + Unpin> PollBufRead for Futures<R> {
+    fn poll_fill_buf(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        Pin::new(&mut self.0).poll_fill_buf(cx)
+    }
+
+    fn consume(&mut self, n: usize) {
+        Pin::new(&mut self.0).consume(n)
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<W: futures_io::AsyncWrite + Unpin> PollWrite for Futures<W> {
+    fn poll_write(&mut self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+}
+
+// Copy one complete frame from `reader` to the end of `out`, checking its structure, and return its type.
+// Like `lazy::read_frame`, yielding to the executor every YIELD_INTERVAL bytes so huge frames that are
+// already buffered do not hold up the other tasks of the worker.
+// Like `lazy::FrameSplitter`, anything over `limits` fails as soon as it is seen, rather than once it is read.
+pub(crate) async fn read_frame<R: PollBufRead>(
+    reader: &mut R,
+    out: &mut Vec<u8>,
+    limits: Limits,
+) -> Result<RESPKind> {
+    let mut scanner = FrameScanner::new(limits);
+    let mut next = Next::Line;
+    let frame_start = out.len();
+    let mut yielded_at = out.len();
+    loop {
+        let start = out.len();
+        next = match next {
+            Next::Line => {
+                read_line(reader, out, limits.max_line_len).await?;
+                if out.len() == frame_start {
+                    return Err(crate::Error::CleanEof);
+                }
+                let next = scanner.line(&out[start..])?;
+                if let Next::Payload(len) = next {
+                    let len = usize::try_from(len - 2).unwrap_or(usize::MAX);
+                    check_limit(LimitKind::BulkLength, limits.max_bulk_len, len)?;
+                }
+                next
+            }
+            Next::Payload(len) => {
+                let mut left = len;
+                while left > 0 {
+                    let chunk = left.min(YIELD_INTERVAL as u64);
+                    read_exact(reader, chunk as usize, out).await?;
+                    left -= chunk;
+                    if out.len() - yielded_at >= YIELD_INTERVAL {
+                        yield_now().await;
+                        yielded_at = out.len();
+                    }
+                }
+                scanner.payload(&out[start..])?
+            }
+            Next::Done(kind) => return Ok(kind),
+        };
+        if out.len() - yielded_at >= YIELD_INTERVAL {
+            yield_now().await;
+            yielded_at = out.len();
+        }
+    }
+}
+
+// Write `value` to `writer` as `ser::to_writer` would. Everything but long payloads is encoded into a buffer
+// first, and long payloads are written in between from the value itself.
+pub(crate) async fn write_value<W: PollWrite>(value: &RESPType, writer: &mut W) -> Result<()> {
+    let mut buf = Vec::new();
+    // Long payloads, with where they go in `buf`.
+    let mut payloads = Vec::new();
+    let mut stack = vec![value];
+    while let Some(value) = stack.pop() {
+        encode(value, &mut buf, &mut payloads)?;
+        let len = stack.len();
+        stack.extend(value.children());
+        stack[len..].reverse();
+    }
+    let mut written = 0;
+    for (offset, payload) in payloads {
+        write_all(writer, &buf[written..offset]).await?;
+        write_all(writer, payload).await?;
+        written = offset;
+    }
+    write_all(writer, &buf[written..]).await?;
+    Ok(())
+}
+
+// Append bytes from `reader` to `out` up to and including LF, or until the end of input,
+// giving up once the line is longer than `max` before its LF.
+async fn read_line<R: PollBufRead>(reader: &mut R, out: &mut Vec<u8>, max: usize) -> Result<()> {
+    let start = out.len();
+    future::poll_fn(|cx| loop {
+        let available = ready!(reader.poll_fill_buf(cx))?;
+        let (n, done) = match available.iter().position(|&b| b == b'\n') {
+            Some(i) => (i + 1, true),
+            None => (available.len(), available.is_empty()),
+        };
+        out.extend_from_slice(&available[..n]);
+        reader.consume(n);
+        // Without its LF, or the CR that may come before it
+        let len = (out.len() - start).saturating_sub(1);
+        check_limit(LimitKind::LineLength, max, len)?;
+        if done {
+            return Poll::Ready(Ok(()));
+        }
+    })
+    .await
+}
+
+// Append exactly `n` bytes from `reader` to `out`, failing with UnexpectedEof if the input ends before.
+async fn read_exact<R: PollBufRead>(
+    reader: &mut R,
+    mut n: usize,
+    out: &mut Vec<u8>,
+) -> io::Result<()> {
+    future::poll_fn(|cx| {
+        while n > 0 {
+            let available = ready!(reader.poll_fill_buf(cx))?;
+            if available.is_empty() {
+                return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
+            }
+            let read = available.len().min(n);
+            out.extend_from_slice(&available[..read]);
+            reader.consume(read);
+            n -= read;
+        }
+        Poll::Ready(Ok(()))
+    })
+    .await
+}
+
+async fn write_all<W: PollWrite>(writer: &mut W, mut buf: &[u8]) -> io::Result<()> {
+    future::poll_fn(|cx| {
+        while !buf.is_empty() {
+            match ready!(writer.poll_write(cx, buf))? {
+                0 => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                n => buf = &buf[n..],
+            }
+        }
+        Poll::Ready(Ok(()))
+    })
+    .await
+}
+
+// Encode `value`, without the values nested in it, to the end of `buf`, keeping its payload aside if it is long.
+fn encode<'a>(
+    value: &'a RESPType,
+    buf: &mut Vec<u8>,
+    payloads: &mut Vec<(usize, &'a [u8])>,
+) -> Result<()> {
+    let mut header = [0; HEADER_MAX_LEN];
+    let (kind, len) = match value {
+        RESPType::Array(Some(arr)) => (RESPKind::Array, arr.len()),
+        RESPType::Push(arr) => (RESPKind::Push, arr.len()),
+        RESPType::Set(arr) => (RESPKind::Set, arr.len()),
+        RESPType::Map(pairs) => (RESPKind::Map, pairs.len()),
+        RESPType::Attribute { attributes, .. } => (RESPKind::Attribute, attributes.len()),
+        RESPType::BulkString(Some(b)) if b.len() >= DIRECT_WRITE_MIN => {
+            buf.extend_from_slice(ser::encode_header(&mut header, b'$', b.len()));
+            payloads.push((buf.len(), &b[..]));
+            buf.extend_from_slice(b"\r\n");
+            return Ok(());
+        }
+        RESPType::Verbatim { format, data }
+            if format.len() == 3 && data.len() >= DIRECT_WRITE_MIN =>
+        {
+            let len = format.len() + 1 + data.len();
+            buf.extend_from_slice(ser::encode_header(&mut header, b'=', len));
+            buf.extend_from_slice(format.as_bytes());
+            buf.push(b':');
+            payloads.push((buf.len(), &data[..]));
+            buf.extend_from_slice(b"\r\n");
+            return Ok(());
+        }
+        value => return ser::to_writer(value, buf),
+    };
+    buf.extend_from_slice(ser::encode_header(&mut header, kind.type_byte(), len));
+    Ok(())
+}
+
+// Run `task` until it completes or `cancelled` does, whichever comes first.
+#[cfg(feature = "tokio")]
+pub(crate) async fn cancellable<T, F, C>(task: F, cancelled: C) -> Result<T>
+where
+    F: future::Future<Output = Result<T>>,
+    C: future::Future<Output = ()>,
+{
+    let mut task = std::pin::pin!(task);
+    let mut cancelled = std::pin::pin!(cancelled);
+    future::poll_fn(|cx| {
+        if cancelled.as_mut().poll(cx).is_ready() {
+            let e = io::Error::new(io::ErrorKind::Interrupted, "cancelled");
+            return Poll::Ready(Err(crate::Error::from(e)));
+        }
+        task.as_mut().poll(cx)
+    })
+    .await
+}
+
+// Let the executor run other tasks before resuming, without depending on a particular runtime.
+async fn yield_now() {
+    let mut yielded = false;
+    future::poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    })
+    .await
+}
@@ -0,0 +1,321 @@
+//! Decode RESP captures into readable text, JSON or literals, and encode JSON or literals back into RESP.
+//!
+//! Build with `--features cli`.
+//!
+//! ```text
+//! resp-cli decode [--json | --literal] [FILE]
+//! resp-cli encode [--literal] [FILE]
+//! ```
+//!
+//! Input is read from `FILE`, or stdin if omitted. `decode` prints every value in the input as soon as
+//! it is decoded, in the style of `redis-cli`, as one JSON document per line with `--json`,
+//! or as one literal per line with `--literal`. It stops at the first value that fails to decode,
+//! after printing those before it.
+//!
+//! Literals are the wire format escaped into a single line, as [text](serde_resp::text) does,
+//! such as `*1\r\n$4\r\nPING\r\n`. `encode --literal` reads one per line, skipping empty lines,
+//! and writes them as RESP.
+//!
+//! `encode` reads JSON documents in the same form as `decode --json` prints and writes them as RESP:
+//! - simple strings are `{"simple": "OK"}` and errors are `{"error": "ERR"}`,
+//! - integers are numbers,
+//! - bulk strings are strings, or `{"bytes": [...]}` if they are not valid utf-8,
+//! - arrays are arrays,
+//! - null bulk strings are `null` and null arrays are `{"array": null}`,
+//! - RESP3 doubles are `{"double": 1.5}`, or `{"double": "inf"}` for infinities and NaN,
+//! - RESP3 booleans are `true` and `false`,
+//! - RESP3 big numbers are `{"big_number": "12345678901234567890"}`,
+//! - RESP3 verbatim strings are `{"verbatim": "txt:Some string"}`, the format and the data,
+//! - RESP3 nulls are `{"null": null}`,
+//! - RESP3 pushes are `{"push": [...]}`,
+//! - RESP3 attributes are `{"attribute": {"attributes": [[key, value], ...], "value": ...}}`,
+//! - RESP3 maps are `{"map": [[key, value], ...]}`,
+//! - RESP3 sets are `{"set": [...]}`.
+
+use serde_json::{json, Value};
+use serde_resp::{de, ser, text, RESP};
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::{env, process};
+
+const USAGE: &str =
+    "usage: resp-cli decode [--json | --literal] [FILE]\n       resp-cli encode [--literal] [FILE]";
+
+// How values are shown or given.
+#[derive(Clone, Copy, PartialEq)]
+enum Form {
+    Pretty,
+    Json,
+    Literal,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        let mut message = e.to_string();
+        let mut source = e.source();
+        while let Some(error) = source {
+            message = format!("{}: {}", message, error);
+            source = error.source();
+        }
+        eprintln!("{}", message);
+        process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let (command, rest) = args.split_first().ok_or(USAGE)?;
+    let mut form = Form::Pretty;
+    let mut paths = Vec::new();
+    for arg in rest {
+        match arg.as_str() {
+            "--json" if form == Form::Pretty && command == "decode" => form = Form::Json,
+            "--literal" if form == Form::Pretty => form = Form::Literal,
+            flag if flag.starts_with("--") => return Err(USAGE.into()),
+            path => paths.push(path),
+        }
+    }
+    if paths.len() > 1 {
+        return Err(USAGE.into());
+    }
+    let input: Box<dyn BufRead> = match paths.first() {
+        Some(path) => Box::new(BufReader::new(File::open(path)?)),
+        None => Box::new(BufReader::new(io::stdin())),
+    };
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let done = match command.as_str() {
+        "decode" => decode(input, &mut out, form),
+        "encode" => encode(input, &mut out, form),
+        _ => return Err(USAGE.into()),
+    };
+    // Whatever was written before an error is still shown.
+    out.flush()?;
+    done
+}
+
+// Print every value read from `input`, one after the other, until the end of input or the first error.
+fn decode(input: impl BufRead, out: &mut impl Write, form: Form) -> Result<(), Box<dyn Error>> {
+    let mut deserializer = de::Deserializer::from_buf_reader(input);
+    for value in deserializer.iter::<RESP>() {
+        let value = value?;
+        match form {
+            Form::Pretty => {
+                for line in pretty(&value) {
+                    writeln!(out, "{}", line)?;
+                }
+            }
+            Form::Json => writeln!(out, "{}", to_json(&value))?,
+            Form::Literal => writeln!(out, "{}", text::to_text(&value)?)?,
+        }
+    }
+    Ok(())
+}
+
+// Write the values given in `input` as RESP, as JSON documents or as one literal per line.
+fn encode(input: impl BufRead, out: &mut impl Write, form: Form) -> Result<(), Box<dyn Error>> {
+    if form == Form::Literal {
+        for (i, line) in input.lines().enumerate() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let value: RESP =
+                text::from_text(&line).map_err(|e| format!("line {}: {:#}", i + 1, e))?;
+            ser::to_writer(&value, out)?;
+        }
+        return Ok(());
+    }
+    for value in serde_json::Deserializer::from_reader(input).into_iter() {
+        ser::to_writer(&from_json(value?)?, out)?;
+    }
+    Ok(())
+}
+
+// lines of a value as redis-cli shows it.
+fn pretty(value: &RESP) -> Vec<String> {
+    match value {
+        RESP::SimpleString(s) => vec![s.clone()],
+        RESP::Error(s) => vec![format!("(error) {}", s)],
+        RESP::Integer(i) => vec![format!("(integer) {}", i)],
+        RESP::BulkString(Some(b)) => vec![format!("\"{}\"", b.escape_ascii())],
+        RESP::BulkString(None) | RESP::Array(None) | RESP::Null => vec!["(nil)".to_owned()],
+        RESP::Double(v) => vec![format!("(double) {}", v)],
+        RESP::Boolean(v) => vec![format!("({})", v)],
+        RESP::BigNumber(s) => vec![format!("(big number) {}", s)],
+        // Shown as is, like redis-cli does for the art of LOLWUT.
+        RESP::Verbatim { data, .. } => String::from_utf8_lossy(data)
+            .split('\n')
+            .map(str::to_owned)
+            .collect(),
+        RESP::Array(Some(arr)) | RESP::Push(arr) if arr.is_empty() => {
+            vec!["(empty array)".to_owned()]
+        }
+        RESP::Array(Some(arr)) | RESP::Push(arr) => numbered(arr, ')'),
+        RESP::Set(arr) if arr.is_empty() => vec!["(empty set)".to_owned()],
+        RESP::Set(arr) => numbered(arr, '~'),
+        RESP::Map(pairs) if pairs.is_empty() => vec!["(empty hash)".to_owned()],
+        // Numbered with `#`, each key followed by `=>` and its value.
+        RESP::Map(pairs) => {
+            let width = pairs.len().to_string().len();
+            let mut lines = Vec::new();
+            for (i, (k, v)) in pairs.iter().enumerate() {
+                let key = pretty(k).join(" ");
+                for (j, line) in pretty(v).into_iter().enumerate() {
+                    lines.push(if j == 0 {
+                        format!("{:>width$}# {} => {}", i + 1, key, line, width = width)
+                    } else {
+                        format!("{:width$}  {}", "", line, width = width)
+                    });
+                }
+            }
+            lines
+        }
+        // The attributes first, each prefixed with `# `, then the value.
+        RESP::Attribute { attributes, value } => {
+            let mut lines = Vec::new();
+            for (k, v) in attributes {
+                let key = pretty(k).join(" ");
+                for (j, line) in pretty(v).into_iter().enumerate() {
+                    lines.push(if j == 0 {
+                        format!("# {} => {}", key, line)
+                    } else {
+                        format!("#   {}", line)
+                    });
+                }
+            }
+            lines.extend(pretty(value));
+            lines
+        }
+        // Types added after this binary was written.
+        other => vec![format!("{:?}", other)],
+    }
+}
+
+// lines of the elements of an array or a set, numbered like `1) ` with `mark` after the number.
+fn numbered(elements: &[RESP], mark: char) -> Vec<String> {
+    let width = elements.len().to_string().len();
+    let mut lines = Vec::new();
+    for (i, element) in elements.iter().enumerate() {
+        for (j, line) in pretty(element).into_iter().enumerate() {
+            lines.push(if j == 0 {
+                format!("{:>width$}{} {}", i + 1, mark, line, width = width)
+            } else {
+                format!("{:width$}  {}", "", line, width = width)
+            });
+        }
+    }
+    lines
+}
+
+fn to_json(value: &RESP) -> Value {
+    match value {
+        RESP::SimpleString(s) => json!({ "simple": s }),
+        RESP::Error(s) => json!({ "error": s }),
+        RESP::Integer(i) => json!(i),
+        RESP::BulkString(Some(b)) => match std::str::from_utf8(b) {
+            Ok(s) => json!(s),
+            Err(_) => json!({ "bytes": b }),
+        },
+        RESP::BulkString(None) => Value::Null,
+        RESP::Array(Some(arr)) => Value::Array(arr.iter().map(to_json).collect()),
+        RESP::Array(None) => json!({ "array": null }),
+        RESP::Null => json!({ "null": null }),
+        RESP::Push(arr) => json!({ "push": arr.iter().map(to_json).collect::<Vec<_>>() }),
+        RESP::Attribute { attributes, value } => json!({
+            "attribute": {
+                "attributes": pairs_to_json(attributes),
+                "value": to_json(value),
+            }
+        }),
+        RESP::Map(pairs) => json!({ "map": pairs_to_json(pairs) }),
+        RESP::Set(arr) => json!({ "set": arr.iter().map(to_json).collect::<Vec<_>>() }),
+        RESP::Double(v) if v.is_finite() => json!({ "double": v }),
+        RESP::Double(v) => json!({ "double": v.to_string() }),
+        RESP::Boolean(v) => json!(v),
+        RESP::BigNumber(s) => json!({ "big_number": s }),
+        RESP::Verbatim { format, data } => {
+            json!({ "verbatim": format!("{}:{}", format, String::from_utf8_lossy(data)) })
+        }
+        other => json!({ "unknown": format!("{:?}", other) }),
+    }
+}
+
+fn pairs_to_json(pairs: &[(RESP, RESP)]) -> Vec<Value> {
+    pairs
+        .iter()
+        .map(|(k, v)| json!([to_json(k), to_json(v)]))
+        .collect()
+}
+
+fn from_json(value: Value) -> Result<RESP, Box<dyn Error>> {
+    Ok(match value {
+        Value::Null => RESP::BulkString(None),
+        Value::Number(n) => RESP::Integer(n.as_i64().ok_or_else(|| not_resp(&n))?),
+        Value::String(s) => RESP::BulkString(Some(s.into_bytes())),
+        Value::Bool(v) => RESP::Boolean(v),
+        Value::Array(arr) => RESP::Array(Some(
+            arr.into_iter().map(from_json).collect::<Result<_, _>>()?,
+        )),
+        Value::Object(object) if object.len() == 1 => {
+            let (key, value) = object.into_iter().next().unwrap();
+            match (key.as_str(), value) {
+                ("simple", Value::String(s)) => RESP::SimpleString(s),
+                ("error", Value::String(s)) => RESP::Error(s),
+                ("bytes", bytes) => RESP::BulkString(Some(serde_json::from_value(bytes)?)),
+                ("array", Value::Null) => RESP::Array(None),
+                ("null", Value::Null) => RESP::Null,
+                ("push", Value::Array(arr)) => {
+                    RESP::Push(arr.into_iter().map(from_json).collect::<Result<_, _>>()?)
+                }
+                ("attribute", Value::Object(mut object)) => {
+                    let attributes = match object.remove("attributes") {
+                        Some(Value::Array(pairs)) => pairs_from_json(pairs)?,
+                        _ => return Err(not_resp(&Value::Object(object))),
+                    };
+                    match object.remove("value") {
+                        Some(value) => RESP::attribute(attributes, from_json(value)?),
+                        None => return Err(not_resp(&Value::Object(object))),
+                    }
+                }
+                ("map", Value::Array(pairs)) => RESP::Map(pairs_from_json(pairs)?),
+                ("set", Value::Array(arr)) => {
+                    RESP::Set(arr.into_iter().map(from_json).collect::<Result<_, _>>()?)
+                }
+                ("double", Value::Number(n)) => {
+                    RESP::Double(n.as_f64().ok_or_else(|| not_resp(&n))?)
+                }
+                ("double", Value::String(s)) => RESP::Double(s.parse()?),
+                ("big_number", Value::String(s)) => RESP::BigNumber(s),
+                ("verbatim", Value::String(s)) => match s.split_once(':') {
+                    Some((format, data)) if format.len() == 3 => RESP::Verbatim {
+                        format: format.to_owned(),
+                        data: data.as_bytes().to_vec(),
+                    },
+                    _ => return Err(not_resp(&s)),
+                },
+                (key, value) => return Err(not_resp(&json!({ key: value }))),
+            }
+        }
+        value => return Err(not_resp(&value)),
+    })
+}
+
+fn pairs_from_json(pairs: Vec<Value>) -> Result<Vec<(RESP, RESP)>, Box<dyn Error>> {
+    pairs
+        .into_iter()
+        .map(|pair| match pair {
+            Value::Array(pair) if pair.len() == 2 => {
+                let mut pair = pair.into_iter();
+                let k = from_json(pair.next().unwrap())?;
+                Ok((k, from_json(pair.next().unwrap())?))
+            }
+            other => Err(not_resp(&other)),
+        })
+        .collect()
+}
+
+fn not_resp(value: &dyn std::fmt::Display) -> Box<dyn Error> {
+    format!("cannot encode {} as RESP", value).into()
+}
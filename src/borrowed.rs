@@ -0,0 +1,752 @@
+use crate::de::{is_big_number, is_verbatim};
+use crate::ser::{
+    AttributeContent, MapContent, VerbatimContent, ATTRIBUTE_TOKEN, BIG_NUMBER_TOKEN,
+    BOOLEAN_TOKEN, DOUBLE_TOKEN, ERROR_TOKEN, MAP_TOKEN, NULL_TOKEN, PUSH_TOKEN, SET_TOKEN,
+    SIMPLE_STRING_TOKEN, VERBATIM_TOKEN,
+};
+use crate::value::{flatten, EnumDeserializer, SeqDeserializer};
+use crate::{Error, RESPKind, RESPType, Result};
+
+use serde::de::{self, IntoDeserializer, Visitor};
+use serde::{ser, Serialize, Serializer};
+use std::str::{self, FromStr};
+
+/// A RESP value borrowing its strings from the buffer it was decoded from, returned by
+/// [de::from_slice](crate::de::from_slice).
+///
+/// Decoding one copies no payload: bulk strings, simple strings, errors and the other strings are
+/// slices of the buffer, which saves allocating a `Vec<u8>` for every bulk string. Use
+/// [into_owned](RESPRef::into_owned) to get a [RESPType](RESPType) that outlives the buffer.
+///
+/// Rust types are deserialized from it the same way as from a [RESPType](RESPType),
+/// see [value](crate::value). Fields of type `&str`, `&[u8]` and, with `#[serde(borrow)]`,
+/// `Cow<str>` and `Cow<[u8]>` borrow from the buffer too. Serializing it writes the value it stands for.
+///
+/// # Examples
+/// ```
+/// use serde::Deserialize;
+/// use serde_resp::{array, bulk, de, RESPRef, RESP};
+/// use std::borrow::Cow;
+///
+/// let buf = b"*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n";
+/// let command = de::from_slice(buf).unwrap();
+/// match &command {
+///     RESPRef::Array(Some(args)) => assert_eq!(RESPRef::BulkString(Some(&b"GET"[..])), args[0]),
+///     _ => panic!("not an array"),
+/// }
+/// assert_eq!(array![bulk!(b"GET".to_vec()), bulk!(b"key".to_vec())], command.into_owned());
+///
+/// #[derive(Deserialize)]
+/// struct User<'a> {
+///     #[serde(borrow)]
+///     name: Cow<'a, str>,
+///     age: u8,
+/// }
+///
+/// let reply = b"*4\r\n$4\r\nname\r\n$5\r\nAlice\r\n$3\r\nage\r\n$2\r\n42\r\n";
+/// let user = User::deserialize(de::from_slice(reply).unwrap()).unwrap();
+/// assert!(matches!(user.name, Cow::Borrowed("Alice")));
+/// assert_eq!(42, user.age);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum RESPRef<'a> {
+    /// [RESPType::SimpleString](RESPType::SimpleString)
+    SimpleString(&'a str),
+    /// [RESPType::Error](RESPType::Error)
+    Error(&'a str),
+    /// [RESPType::Integer](RESPType::Integer)
+    Integer(i64),
+    /// [RESPType::BulkString](RESPType::BulkString)
+    BulkString(Option<&'a [u8]>),
+    /// [RESPType::Array](RESPType::Array)
+    Array(Option<Vec<RESPRef<'a>>>),
+    /// [RESPType::Double](RESPType::Double)
+    Double(f64),
+    /// [RESPType::Boolean](RESPType::Boolean)
+    Boolean(bool),
+    /// [RESPType::BigNumber](RESPType::BigNumber)
+    BigNumber(&'a str),
+    /// [RESPType::Verbatim](RESPType::Verbatim)
+    Verbatim {
+        /// Format of the data, such as `txt`.
+        format: &'a str,
+        /// The data.
+        data: &'a [u8],
+    },
+    /// [RESPType::Null](RESPType::Null)
+    Null,
+    /// [RESPType::Push](RESPType::Push)
+    Push(Vec<RESPRef<'a>>),
+    /// [RESPType::Attribute](RESPType::Attribute)
+    Attribute {
+        /// The attributes, as key-value pairs.
+        attributes: Vec<(RESPRef<'a>, RESPRef<'a>)>,
+        /// The value the attributes are about.
+        value: Box<RESPRef<'a>>,
+    },
+    /// [RESPType::Map](RESPType::Map)
+    Map(Vec<(RESPRef<'a>, RESPRef<'a>)>),
+    /// [RESPType::Set](RESPType::Set)
+    Set(Vec<RESPRef<'a>>),
+}
+
+impl<'a> RESPRef<'a> {
+    /// Type of the value.
+    pub fn kind(&self) -> RESPKind {
+        match self {
+            RESPRef::SimpleString(_) => RESPKind::SimpleString,
+            RESPRef::Error(_) => RESPKind::Error,
+            RESPRef::Integer(_) => RESPKind::Integer,
+            RESPRef::BulkString(_) => RESPKind::BulkString,
+            RESPRef::Array(_) => RESPKind::Array,
+            RESPRef::Double(_) => RESPKind::Double,
+            RESPRef::Boolean(_) => RESPKind::Boolean,
+            RESPRef::BigNumber(_) => RESPKind::BigNumber,
+            RESPRef::Verbatim { .. } => RESPKind::Verbatim,
+            RESPRef::Null => RESPKind::Null,
+            RESPRef::Push(_) => RESPKind::Push,
+            RESPRef::Attribute { .. } => RESPKind::Attribute,
+            RESPRef::Map(_) => RESPKind::Map,
+            RESPRef::Set(_) => RESPKind::Set,
+        }
+    }
+
+    /// Copy the value into a [RESPType](RESPType), which does not borrow from the buffer.
+    pub fn into_owned(self) -> RESPType {
+        match self {
+            RESPRef::SimpleString(s) => RESPType::SimpleString(s.to_owned()),
+            RESPRef::Error(s) => RESPType::Error(s.to_owned()),
+            RESPRef::Integer(i) => RESPType::Integer(i),
+            RESPRef::BulkString(b) => RESPType::BulkString(b.map(<[u8]>::to_vec)),
+            RESPRef::Array(arr) => RESPType::Array(arr.map(owned_elements)),
+            RESPRef::Double(v) => RESPType::Double(v),
+            RESPRef::Boolean(v) => RESPType::Boolean(v),
+            RESPRef::BigNumber(digits) => RESPType::BigNumber(digits.to_owned()),
+            RESPRef::Verbatim { format, data } => RESPType::Verbatim {
+                format: format.to_owned(),
+                data: data.to_vec(),
+            },
+            RESPRef::Null => RESPType::Null,
+            RESPRef::Push(elements) => RESPType::Push(owned_elements(elements)),
+            RESPRef::Attribute { attributes, value } => RESPType::Attribute {
+                attributes: owned_pairs(attributes),
+                value: Box::new(value.into_owned()),
+            },
+            RESPRef::Map(pairs) => RESPType::Map(owned_pairs(pairs)),
+            RESPRef::Set(elements) => RESPType::Set(owned_elements(elements)),
+        }
+    }
+
+    // Text of a simple string, error, big number or bulk string.
+    fn text(&self) -> Option<&'a [u8]> {
+        match *self {
+            RESPRef::SimpleString(s) | RESPRef::Error(s) | RESPRef::BigNumber(s) => {
+                Some(s.as_bytes())
+            }
+            RESPRef::BulkString(Some(b)) => Some(b),
+            _ => None,
+        }
+    }
+
+    fn invalid_type<E: de::Error>(&self, exp: &dyn de::Expected) -> E {
+        let unexp = match self {
+            RESPRef::SimpleString(s) => de::Unexpected::Str(s),
+            RESPRef::Error(s) => de::Unexpected::Other(s),
+            RESPRef::Integer(i) => de::Unexpected::Signed(*i),
+            RESPRef::BulkString(Some(b)) => de::Unexpected::Bytes(b),
+            RESPRef::BulkString(None) => de::Unexpected::Other("null bulk string"),
+            RESPRef::Array(None) => de::Unexpected::Other("null array"),
+            RESPRef::Array(Some(_)) | RESPRef::Push(_) | RESPRef::Set(_) => de::Unexpected::Seq,
+            RESPRef::Double(v) => de::Unexpected::Float(*v),
+            RESPRef::Boolean(v) => de::Unexpected::Bool(*v),
+            RESPRef::BigNumber(_) => de::Unexpected::Other("big number"),
+            RESPRef::Verbatim { data, .. } => de::Unexpected::Bytes(data),
+            RESPRef::Null => de::Unexpected::Other("null"),
+            RESPRef::Attribute { .. } => de::Unexpected::Other("attribute"),
+            RESPRef::Map(_) => de::Unexpected::Map,
+        };
+        de::Error::invalid_type(unexp, exp)
+    }
+
+    // Integer, or text parsed as a number.
+    fn parse<T: FromStr>(&self, exp: &dyn de::Expected) -> Result<T> {
+        self.text()
+            .and_then(|text| str::from_utf8(text).ok())
+            .and_then(|text| text.parse().ok())
+            .ok_or_else(|| self.invalid_type(exp))
+    }
+}
+
+fn owned_elements(elements: Vec<RESPRef>) -> Vec<RESPType> {
+    elements.into_iter().map(RESPRef::into_owned).collect()
+}
+
+fn owned_pairs(pairs: Vec<(RESPRef, RESPRef)>) -> Vec<(RESPType, RESPType)> {
+    pairs
+        .into_iter()
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect()
+}
+
+// Same as for RESPType.
+impl<'a> Serialize for RESPRef<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            RESPRef::SimpleString(str) => s.serialize_newtype_struct(SIMPLE_STRING_TOKEN, str),
+            RESPRef::Error(str) => s.serialize_newtype_struct(ERROR_TOKEN, str),
+            RESPRef::Integer(i) => s.serialize_i64(*i),
+            RESPRef::BulkString(None) => s.serialize_none(),
+            RESPRef::BulkString(Some(b)) => s.serialize_bytes(b),
+            RESPRef::Array(None) => s.serialize_unit(),
+            RESPRef::Array(Some(elements)) => s.collect_seq(elements),
+            RESPRef::Double(v) => s.serialize_newtype_struct(DOUBLE_TOKEN, v),
+            RESPRef::Boolean(v) => s.serialize_newtype_struct(BOOLEAN_TOKEN, v),
+            RESPRef::BigNumber(digits) => s.serialize_newtype_struct(BIG_NUMBER_TOKEN, digits),
+            RESPRef::Verbatim { format, data } => {
+                if format.len() != 3 {
+                    return Err(ser::Error::custom(format!(
+                        "verbatim string format {:?} is not 3 bytes long",
+                        format
+                    )));
+                }
+                s.serialize_newtype_struct(VERBATIM_TOKEN, &VerbatimContent { format, data })
+            }
+            RESPRef::Null => s.serialize_unit_struct(NULL_TOKEN),
+            RESPRef::Push(elements) => s.serialize_newtype_struct(PUSH_TOKEN, elements),
+            RESPRef::Attribute { attributes, value } => {
+                s.serialize_newtype_struct(ATTRIBUTE_TOKEN, &AttributeContent { attributes, value })
+            }
+            RESPRef::Map(pairs) => s.serialize_newtype_struct(MAP_TOKEN, &MapContent(pairs)),
+            RESPRef::Set(elements) => s.serialize_newtype_struct(SET_TOKEN, elements),
+        }
+    }
+}
+
+macro_rules! deserialize_number {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                match self {
+                    RESPRef::Integer(i) => visitor.visit_i64(i),
+                    RESPRef::Double(v) => visitor.visit_f64(v),
+                    value => {
+                        let v = value.parse(&visitor)?;
+                        visitor.$visit(v)
+                    }
+                }
+            }
+        )*
+    };
+}
+
+// Same as for RESPType, giving out borrowed strings.
+impl<'de> de::Deserializer<'de> for RESPRef<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            RESPRef::SimpleString(s) => visitor.visit_borrowed_str(s),
+            // Owned, which is what tells errors apart from simple strings.
+            RESPRef::Error(s) => visitor.visit_string(s.to_owned()),
+            RESPRef::Integer(i) => visitor.visit_i64(i),
+            RESPRef::BulkString(Some(b)) => visitor.visit_borrowed_bytes(b),
+            RESPRef::BulkString(None) => visitor.visit_none(),
+            RESPRef::Array(Some(arr)) => visitor.visit_seq(SeqDeserializer::new(arr)),
+            RESPRef::Array(None) => visitor.visit_unit(),
+            RESPRef::Double(v) => visitor.visit_f64(v),
+            RESPRef::Boolean(v) => visitor.visit_bool(v),
+            RESPRef::BigNumber(digits) => visitor.visit_enum(EnumDeserializer {
+                variant: tag(BIG_NUMBER_TOKEN),
+                content: Some(RESPRef::SimpleString(digits)),
+            }),
+            // The payload `format:data` is not a single slice once split, so it is copied.
+            RESPRef::Verbatim { format, data } => {
+                let mut payload = Vec::with_capacity(format.len() + 1 + data.len());
+                payload.extend_from_slice(format.as_bytes());
+                payload.push(b':');
+                payload.extend_from_slice(data);
+                visitor.visit_enum(EnumDeserializer {
+                    variant: RESPType::bulk(VERBATIM_TOKEN),
+                    content: Some(RESPType::BulkString(Some(payload))),
+                })
+            }
+            RESPRef::Null => visitor.visit_enum(EnumDeserializer {
+                variant: tag(NULL_TOKEN),
+                content: None,
+            }),
+            RESPRef::Push(elements) => visitor.visit_enum(EnumDeserializer {
+                variant: tag(PUSH_TOKEN),
+                content: Some(RESPRef::Array(Some(elements))),
+            }),
+            RESPRef::Attribute { attributes, value } => {
+                let mut elements = flatten(attributes);
+                elements.push(*value);
+                visitor.visit_enum(EnumDeserializer {
+                    variant: tag(ATTRIBUTE_TOKEN),
+                    content: Some(RESPRef::Array(Some(elements))),
+                })
+            }
+            RESPRef::Map(pairs) => visitor.visit_enum(EnumDeserializer {
+                variant: tag(MAP_TOKEN),
+                content: Some(RESPRef::Array(Some(flatten(pairs)))),
+            }),
+            RESPRef::Set(elements) => visitor.visit_enum(EnumDeserializer {
+                variant: tag(SET_TOKEN),
+                content: Some(RESPRef::Array(Some(elements))),
+            }),
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            RESPRef::Integer(i) => visitor.visit_bool(i != 0),
+            RESPRef::Boolean(v) => visitor.visit_bool(v),
+            value => Err(value.invalid_type(&visitor)),
+        }
+    }
+
+    deserialize_number! {
+        deserialize_i8 => visit_i64,
+        deserialize_i16 => visit_i64,
+        deserialize_i32 => visit_i64,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u64,
+        deserialize_u16 => visit_u64,
+        deserialize_u32 => visit_u64,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f64,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            RESPRef::SimpleString(s) | RESPRef::Error(s) | RESPRef::BigNumber(s) => {
+                visitor.visit_borrowed_str(s)
+            }
+            RESPRef::BulkString(Some(b)) | RESPRef::Verbatim { data: b, .. } => {
+                match str::from_utf8(b) {
+                    Ok(s) => visitor.visit_borrowed_str(s),
+                    Err(_) => visitor.visit_borrowed_bytes(b),
+                }
+            }
+            value => Err(value.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            RESPRef::SimpleString(s) | RESPRef::Error(s) => {
+                visitor.visit_borrowed_bytes(s.as_bytes())
+            }
+            RESPRef::BulkString(Some(b)) | RESPRef::Verbatim { data: b, .. } => {
+                visitor.visit_borrowed_bytes(b)
+            }
+            value => Err(value.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            RESPRef::BulkString(None) | RESPRef::Array(None) | RESPRef::Null => {
+                visitor.visit_none()
+            }
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            RESPRef::BulkString(None) | RESPRef::Array(None) | RESPRef::Null => {
+                visitor.visit_unit()
+            }
+            value => Err(value.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            RESPRef::Array(Some(arr)) | RESPRef::Push(arr) | RESPRef::Set(arr) => {
+                visitor.visit_seq(SeqDeserializer::new(arr))
+            }
+            // Vec<u8> and friends
+            RESPRef::BulkString(Some(b)) => visitor.visit_seq(
+                de::value::SeqDeserializer::<_, Error>::new(b.iter().copied()),
+            ),
+            value => Err(value.invalid_type(&visitor)),
+        }
+    }
+
+    // Tuples and fixed-size arrays, which need exactly `len` elements.
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let actual = match &self {
+            RESPRef::Array(Some(arr)) | RESPRef::Push(arr) | RESPRef::Set(arr) => arr.len(),
+            RESPRef::BulkString(Some(b)) => b.len(),
+            _ => len,
+        };
+        if actual != len {
+            return Err(de::Error::invalid_length(actual, &visitor));
+        }
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            RESPRef::Array(Some(arr)) => {
+                if arr.len() % 2 != 0 {
+                    return Err(de::Error::invalid_length(
+                        arr.len(),
+                        &"an even number of keys and values",
+                    ));
+                }
+                visitor.visit_map(SeqDeserializer::new(arr))
+            }
+            RESPRef::Map(pairs) => visitor.visit_map(SeqDeserializer::new(flatten(pairs))),
+            value => Err(value.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            RESPRef::Array(Some(mut arr)) => {
+                if arr.len() != 2 {
+                    return Err(de::Error::invalid_length(
+                        arr.len(),
+                        &"a variant name and its content",
+                    ));
+                }
+                let content = arr.pop();
+                let variant = arr.pop().unwrap_or(RESPRef::Array(None));
+                visitor.visit_enum(EnumDeserializer { variant, content })
+            }
+            variant => visitor.visit_enum(EnumDeserializer {
+                variant,
+                content: None,
+            }),
+        }
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for RESPRef<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+// Name of a tagged type, as given to visitors.
+fn tag(token: &'static str) -> RESPRef<'static> {
+    RESPRef::BulkString(Some(token.as_bytes()))
+}
+
+// Decode the frame at the start of `bytes`, returning it and its length.
+pub(crate) fn parse(bytes: &[u8]) -> Result<(RESPRef<'_>, usize)> {
+    let mut parser = Parser { bytes, pos: 0 };
+    let value = parser.value().map_err(|e| match e {
+        Error::Eof if parser.pos == 0 => Error::CleanEof.at(0),
+        e => e.at(parser.pos),
+    })?;
+    Ok((value, parser.pos))
+}
+
+// Decoder of frames in a slice, checking them like the wire format Deserializer does.
+struct Parser<'a> {
+    bytes: &'a [u8],
+    // Position of the next byte to decode.
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn value(&mut self) -> Result<RESPRef<'a>> {
+        let value = match self.byte()? {
+            b'+' => RESPRef::SimpleString(self.line()?),
+            b'-' => RESPRef::Error(self.line()?),
+            b':' => RESPRef::Integer(self.number()?),
+            b'$' => match self.len()? {
+                None => {
+                    return Err(Error::Message(
+                        "streamed bulk strings cannot be borrowed".to_owned(),
+                    ))
+                }
+                Some(len) if len < 0 => RESPRef::BulkString(None),
+                Some(len) => RESPRef::BulkString(Some(self.payload(len as usize)?)),
+            },
+            b'*' => match self.len()? {
+                Some(len) if len < 0 => RESPRef::Array(None),
+                len => RESPRef::Array(Some(self.elements(len)?)),
+            },
+            b',' => RESPRef::Double(self.number()?),
+            b'#' => match self.line()? {
+                "t" => RESPRef::Boolean(true),
+                "f" => RESPRef::Boolean(false),
+                _ => return Err(Error::Syntax),
+            },
+            b'(' => match self.line()? {
+                digits if is_big_number(digits) => RESPRef::BigNumber(digits),
+                _ => return Err(Error::Syntax),
+            },
+            b'=' => {
+                let len = self.number::<isize>()?;
+                if len < 0 {
+                    return Err(Error::Syntax);
+                }
+                let payload = self.payload(len as usize)?;
+                if !is_verbatim(payload) {
+                    return Err(Error::Syntax);
+                }
+                RESPRef::Verbatim {
+                    format: str::from_utf8(&payload[..3]).expect("checked by is_verbatim"),
+                    data: &payload[4..],
+                }
+            }
+            b'_' => match self.line()? {
+                "" => RESPRef::Null,
+                _ => return Err(Error::Syntax),
+            },
+            b'>' => match self.number::<isize>()? {
+                len if len < 0 => return Err(Error::Syntax),
+                len => RESPRef::Push(self.elements(Some(len))?),
+            },
+            b'|' => match self.number::<isize>()? {
+                pairs if pairs < 0 => return Err(Error::Syntax),
+                pairs => RESPRef::Attribute {
+                    attributes: self.pairs(Some(pairs))?,
+                    value: Box::new(self.value()?),
+                },
+            },
+            b'%' => match self.len()? {
+                Some(pairs) if pairs < 0 => return Err(Error::Syntax),
+                pairs => RESPRef::Map(self.pairs(pairs)?),
+            },
+            b'~' => match self.len()? {
+                Some(len) if len < 0 => return Err(Error::Syntax),
+                len => RESPRef::Set(self.elements(len)?),
+            },
+            byte => return Err(Error::unexpected(byte, self.pos)),
+        };
+        Ok(value)
+    }
+
+    // The `len` elements of an aggregate, or those up to the end of a streamed one.
+    fn elements(&mut self, len: Option<isize>) -> Result<Vec<RESPRef<'a>>> {
+        let mut elements = Vec::new();
+        match len {
+            Some(len) => {
+                // The length is untrusted, but every element takes at least 3 bytes.
+                elements.reserve((len as usize).min(self.remaining() / 3));
+                for _ in 0..len {
+                    elements.push(self.value()?);
+                }
+            }
+            None => {
+                while !self.end_of_stream()? {
+                    elements.push(self.value()?);
+                }
+            }
+        }
+        Ok(elements)
+    }
+
+    // The `len` key-value pairs of a map or an attribute, or those up to the end of a streamed map.
+    fn pairs(&mut self, len: Option<isize>) -> Result<Vec<(RESPRef<'a>, RESPRef<'a>)>> {
+        let mut pairs = Vec::new();
+        match len {
+            Some(len) => {
+                pairs.reserve((len as usize).min(self.remaining() / 6));
+                for _ in 0..len {
+                    pairs.push((self.value()?, self.value()?));
+                }
+            }
+            None => {
+                while !self.end_of_stream()? {
+                    let k = self.value()?;
+                    if self.end_of_stream()? {
+                        return Err(de::Error::invalid_value(
+                            de::Unexpected::Other("malformed map"),
+                            &"keys and values",
+                        ));
+                    }
+                    pairs.push((k, self.value()?));
+                }
+            }
+        }
+        Ok(pairs)
+    }
+
+    // Whether the next byte ends a streamed aggregate, skipping the end `.\r\n` if so.
+    fn end_of_stream(&mut self) -> Result<bool> {
+        match self.bytes.get(self.pos) {
+            None => Err(Error::Eof),
+            Some(b'.') => {
+                self.pos += 1;
+                match self.line()? {
+                    "" => Ok(true),
+                    _ => Err(Error::Syntax),
+                }
+            }
+            Some(_) => Ok(false),
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        let b = *self.bytes.get(self.pos).ok_or(Error::Eof)?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    // The rest of the line, without its line ending.
+    fn line(&mut self) -> Result<&'a str> {
+        let rest = &self.bytes[self.pos..];
+        let end = rest.iter().position(|&b| b == b'\n').ok_or(Error::Eof)?;
+        self.pos += end + 1;
+        let line = &rest[..end];
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        str::from_utf8(line).map_err(|e| Error::FromUtf8 {
+            valid_up_to: e.valid_up_to(),
+            error_len: e.error_len(),
+        })
+    }
+
+    fn number<T: FromStr>(&mut self) -> Result<T> {
+        self.line()?.trim_end().parse().map_err(|_| Error::Syntax)
+    }
+
+    // The length in the header of a bulk string or an aggregate, None for `?`, the length of streamed ones.
+    fn len(&mut self) -> Result<Option<isize>> {
+        match self.line()? {
+            "?" => Ok(None),
+            line => line.trim_end().parse().map(Some).map_err(|_| Error::Syntax),
+        }
+    }
+
+    // A payload of `len` bytes and its CRLF.
+    fn payload(&mut self, len: usize) -> Result<&'a [u8]> {
+        let rest = &self.bytes[self.pos..];
+        if rest.len() < len + 2 {
+            return Err(Error::Eof);
+        }
+        if rest[len..len + 2] != *b"\r\n" {
+            return Err(Error::Syntax);
+        }
+        self.pos += len + 2;
+        Ok(&rest[..len])
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+}
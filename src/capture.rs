@@ -0,0 +1,426 @@
+//! Capture raw traffic into a log for later replay and debugging.
+//!
+//! A [Capture](Capture) wraps readers and writers so that every chunk of bytes passing through them
+//! is also recorded into a sink, such as a file.
+//!
+//! ## Format
+//! The log is itself RESP: one array per chunk, containing
+//! - the direction as a simple string, `in` for bytes read and `out` for bytes written,
+//! - the time of the chunk as an integer, in microseconds since the UNIX epoch,
+//! - the bytes as a bulk string.
+//!
+//! Chunks are whatever a single `read` or `write` call transferred, so a frame may span several of them.
+//! Concatenating the chunks of one direction gives back the original byte stream.
+//! [Replay](Replay) does that to get back the frames of a log.
+//!
+//! Failing to record never fails the reads and writes being captured, as their bytes were already
+//! transferred. The error is kept instead, see [CaptureReader::take_error](CaptureReader::take_error).
+//!
+//! Not available on `wasm32-unknown-unknown`, which has no system clock.
+//!
+//! # Examples
+//! ```
+//! use serde_resp::capture::Capture;
+//! use serde_resp::{bulk, de, simple, RESP};
+//!
+//! let capture = Capture::new(Vec::new());
+//!
+//! let mut socket = capture.reader("+PONG\r\n".as_bytes());
+//! let reply: RESP = de::from_reader(&mut socket).unwrap();
+//! assert_eq!(simple!("PONG".to_owned()), reply);
+//! drop(socket);
+//!
+//! let log = capture.into_inner().unwrap();
+//! let records: Vec<RESP> = de::read_many(&mut &log[..]).unwrap();
+//! match &records[0] {
+//!     RESP::Array(Some(record)) => {
+//!         assert_eq!(simple!("in".to_owned()), record[0]);
+//!         assert_eq!(bulk!(b"+PONG\r\n".to_vec()), record[2]);
+//!     }
+//!     _ => unreachable!(),
+//! }
+//! ```
+
+use crate::{de, ser, Error, RESPType, Result};
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Direction of a captured chunk.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Direction {
+    /// Bytes read, recorded as `in`.
+    In,
+    /// Bytes written, recorded as `out`.
+    Out,
+}
+
+impl Direction {
+    /// Name of the direction in the log.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::In => "in",
+            Direction::Out => "out",
+        }
+    }
+
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "in" => Some(Direction::In),
+            "out" => Some(Direction::Out),
+            _ => None,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Direction::In => 0,
+            Direction::Out => 1,
+        }
+    }
+}
+
+/// Handle to a capture sink, shared by all readers and writers it wraps. Cloning it is cheap.
+#[derive(Debug)]
+pub struct Capture<S: Write> {
+    sink: Arc<Mutex<S>>,
+}
+
+impl<S: Write> Clone for Capture<S> {
+    fn clone(&self) -> Self {
+        Capture {
+            sink: self.sink.clone(),
+        }
+    }
+}
+
+impl<S: Write> Capture<S> {
+    /// Record into `sink`.
+    pub fn new(sink: S) -> Self {
+        Capture {
+            sink: Arc::new(Mutex::new(sink)),
+        }
+    }
+
+    /// Wrap `reader`, recording everything read from it.
+    pub fn reader<R: Read>(&self, reader: R) -> CaptureReader<R, S> {
+        CaptureReader {
+            inner: reader,
+            capture: self.clone(),
+            error: None,
+        }
+    }
+
+    /// Wrap `writer`, recording everything written to it.
+    pub fn writer<W: Write>(&self, writer: W) -> CaptureWriter<W, S> {
+        CaptureWriter {
+            inner: writer,
+            capture: self.clone(),
+            error: None,
+        }
+    }
+
+    /// Record a chunk of bytes by hand.
+    pub fn record(&self, direction: Direction, bytes: &[u8]) -> io::Result<()> {
+        let micros = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_micros() as i64)
+            .unwrap_or_default();
+        let mut sink = self.lock()?;
+        write!(
+            sink,
+            "*3\r\n+{}\r\n:{}\r\n${}\r\n",
+            direction.as_str(),
+            micros,
+            bytes.len()
+        )?;
+        sink.write_all(bytes)?;
+        sink.write_all(b"\r\n")
+    }
+
+    /// Flush the sink.
+    pub fn flush(&self) -> io::Result<()> {
+        self.lock()?.flush()
+    }
+
+    /// Get the sink back, if this is the last handle to it.
+    pub fn into_inner(self) -> Option<S> {
+        Arc::try_unwrap(self.sink)
+            .ok()
+            .map(|sink| sink.into_inner().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn lock(&self) -> io::Result<std::sync::MutexGuard<'_, S>> {
+        self.sink
+            .lock()
+            .map_err(|_| io::Error::other("capture sink poisoned"))
+    }
+}
+
+/// Reader recording everything read through it. Refer to [Capture::reader](Capture::reader).
+#[derive(Debug)]
+pub struct CaptureReader<R: Read, S: Write> {
+    inner: R,
+    capture: Capture<S>,
+    error: Option<io::Error>,
+}
+
+impl<R: Read, S: Write> CaptureReader<R, S> {
+    /// Get the wrapped reader back.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Take the first error the sink failed with since the last call, if any.
+    /// Chunks read after the sink failed may be missing from the log.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::capture::Capture;
+    /// use serde_resp::{de, simple, RESP};
+    /// use std::io::{self, Write};
+    ///
+    /// struct FullDisk;
+    ///
+    /// impl Write for FullDisk {
+    ///     fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+    ///         Err(io::Error::other("disk full"))
+    ///     }
+    ///
+    ///     fn flush(&mut self) -> io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let capture = Capture::new(FullDisk);
+    /// let mut socket = capture.reader("+PONG\r\n".as_bytes());
+    /// let reply: RESP = de::from_reader(&mut socket).unwrap();
+    /// assert_eq!(simple!("PONG".to_owned()), reply);
+    /// assert_eq!("disk full", socket.take_error().unwrap().to_string());
+    /// assert!(socket.take_error().is_none());
+    /// ```
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+}
+
+impl<R: Read, S: Write> Read for CaptureReader<R, S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            keep_error(
+                &mut self.error,
+                self.capture.record(Direction::In, &buf[..n]),
+            );
+        }
+        Ok(n)
+    }
+}
+
+/// Writer recording everything written through it. Refer to [Capture::writer](Capture::writer).
+#[derive(Debug)]
+pub struct CaptureWriter<W: Write, S: Write> {
+    inner: W,
+    capture: Capture<S>,
+    error: Option<io::Error>,
+}
+
+impl<W: Write, S: Write> CaptureWriter<W, S> {
+    /// Get the wrapped writer back.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Take the first error the sink failed with since the last call, if any.
+    /// Refer to [CaptureReader::take_error](CaptureReader::take_error).
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+}
+
+impl<W: Write, S: Write> Write for CaptureWriter<W, S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        if n > 0 {
+            keep_error(
+                &mut self.error,
+                self.capture.record(Direction::Out, &buf[..n]),
+            );
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        keep_error(&mut self.error, self.capture.flush());
+        Ok(())
+    }
+}
+
+// Keep the first error of the sink, so as not to fail the transfer it was recording.
+fn keep_error(error: &mut Option<io::Error>, recorded: io::Result<()>) {
+    if let Err(e) = recorded {
+        error.get_or_insert(e);
+    }
+}
+
+/// Frame reassembled from a capture log by [Replay](Replay).
+#[derive(Clone, Debug, PartialEq)]
+pub struct CapturedFrame {
+    /// Direction of the frame.
+    pub direction: Direction,
+    /// Time of the chunk that completed the frame, in microseconds since the UNIX epoch.
+    pub micros: i64,
+    /// The frame.
+    pub value: RESPType,
+}
+
+/// Iterator over the frames in a capture log, in the order they were completed.
+///
+/// Chunks are reassembled into frames separately for each direction.
+/// A frame that fails to decode is yielded as an error, and the rest of the bytes buffered for its direction are dropped.
+/// A frame cut off by the end of the log is yielded as an [Eof](Error::Eof) error.
+///
+/// # Examples
+/// ```
+/// use serde_resp::capture::{Capture, Direction, Replay};
+/// use serde_resp::{ser, simple, RESP};
+/// use std::io::Read;
+///
+/// let capture = Capture::new(Vec::new());
+/// let mut socket = capture.reader("+PING\r\n".as_bytes());
+/// // Read in chunks of 4 bytes, so the frame spans two of them.
+/// let mut buf = [0; 4];
+/// while socket.read(&mut buf).unwrap() > 0 {}
+/// ser::to_writer(&simple!("PONG".to_owned()), &mut capture.writer(Vec::new())).unwrap();
+/// drop(socket);
+/// let log = capture.into_inner().unwrap();
+///
+/// let mut replay = Replay::new(&log[..]);
+/// let frame = replay.next().unwrap().unwrap();
+/// assert_eq!((Direction::In, simple!("PING".to_owned())), (frame.direction, frame.value));
+/// let frame = replay.next().unwrap().unwrap();
+/// assert_eq!((Direction::Out, simple!("PONG".to_owned())), (frame.direction, frame.value));
+/// assert!(replay.next().is_none());
+///
+/// // Replay the requests, e.g. against a new server.
+/// let mut requests = Vec::new();
+/// assert_eq!(1, Replay::new(&log[..]).write_to(Direction::In, &mut requests).unwrap());
+/// assert_eq!(b"+PING\r\n", &requests[..]);
+/// ```
+#[derive(Debug)]
+pub struct Replay<R: BufRead> {
+    log: R,
+    pending: [Vec<u8>; 2],
+    ready: VecDeque<Result<CapturedFrame>>,
+    done: bool,
+}
+
+impl<R: BufRead> Replay<R> {
+    /// Replay the capture log read from `log`.
+    pub fn new(log: R) -> Self {
+        Replay {
+            log,
+            pending: [Vec::new(), Vec::new()],
+            ready: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Serialize the frames in `direction` to `writer`, without their original timing,
+    /// and return how many were written. Stops at the first error.
+    pub fn write_to<W: Write>(self, direction: Direction, writer: &mut W) -> Result<usize> {
+        let mut written = 0;
+        for frame in self {
+            let frame = frame?;
+            if frame.direction == direction {
+                ser::to_writer(&frame.value, writer)?;
+                written += 1;
+            }
+        }
+        Ok(written)
+    }
+
+    // Read the next chunk of the log, or None at its end.
+    fn read_record(&mut self) -> Result<Option<(Direction, i64, Vec<u8>)>> {
+        if self.log.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        match de::from_buf_reader(&mut self.log)? {
+            RESPType::Array(Some(record)) => match &record[..] {
+                [RESPType::SimpleString(direction), RESPType::Integer(micros), RESPType::BulkString(Some(bytes))] => {
+                    match Direction::from_str(direction) {
+                        Some(direction) => Ok(Some((direction, *micros, bytes.clone()))),
+                        None => Err(invalid_record()),
+                    }
+                }
+                _ => Err(invalid_record()),
+            },
+            _ => Err(invalid_record()),
+        }
+    }
+
+    // Decode all frames completed by the latest chunk in `direction`.
+    fn decode(&mut self, direction: Direction, micros: i64) {
+        let pending = &mut self.pending[direction.index()];
+        while !pending.is_empty() {
+            let mut rest = &pending[..];
+            match de::from_buf_reader(&mut rest) {
+                Ok(value) => {
+                    let consumed = pending.len() - rest.len();
+                    pending.drain(..consumed);
+                    self.ready.push_back(Ok(CapturedFrame {
+                        direction,
+                        micros,
+                        value,
+                    }));
+                }
+                Err(e) if e.is_eof() => break,
+                Err(e) => {
+                    pending.clear();
+                    self.ready.push_back(Err(e));
+                }
+            }
+        }
+    }
+}
+
+fn invalid_record() -> Error {
+    Error::Message("invalid capture record".to_owned())
+}
+
+impl<R: BufRead> Iterator for Replay<R> {
+    type Item = Result<CapturedFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(frame) = self.ready.pop_front() {
+                return Some(frame);
+            }
+            if self.done {
+                return None;
+            }
+            match self.read_record() {
+                Ok(Some((direction, micros, bytes))) => {
+                    self.pending[direction.index()].extend_from_slice(&bytes);
+                    self.decode(direction, micros);
+                }
+                Ok(None) => {
+                    self.done = true;
+                    for pending in &mut self.pending {
+                        if !pending.is_empty() {
+                            pending.clear();
+                            self.ready.push_back(Err(Error::Eof));
+                        }
+                    }
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! Mapping keys to the hash slots of Redis Cluster.
+//!
+//! A cluster splits keys into [SLOT_COUNT](SLOT_COUNT) hash slots by the CRC16 of the key.
+//! If a key contains a hash tag, a non-empty part between the first `{` and the next `}`,
+//! only the tag is hashed, so keys sharing a tag always land on the same slot.
+//! To find which node serves a slot, refer to [replies::cluster_slots](crate::replies::cluster_slots).
+//!
+//! # Examples
+//! ```
+//! use serde_resp::cluster;
+//!
+//! assert_eq!(12182, cluster::key_hash_slot(b"foo"));
+//! assert_eq!(
+//!     cluster::key_hash_slot(b"{user1000}.following"),
+//!     cluster::key_hash_slot(b"{user1000}.followers")
+//! );
+//! ```
+
+/// Number of hash slots in a cluster.
+pub const SLOT_COUNT: u16 = 16384;
+
+/// Hash slot of `key`, hashing only its hash tag if it has one.
+///
+/// # Examples
+/// ```
+/// use serde_resp::cluster::key_hash_slot;
+///
+/// assert_eq!(3443, key_hash_slot(b"user1000"));
+/// assert_eq!(3443, key_hash_slot(b"{user1000}.following"));
+/// // Empty tags are not tags, and only the first `{` counts.
+/// assert_eq!(9500, key_hash_slot(b"{}foo"));
+/// assert_eq!(8363, key_hash_slot(b"foo{}{bar}"));
+/// ```
+pub fn key_hash_slot(key: &[u8]) -> u16 {
+    crc16(hash_tag(key).unwrap_or(key)) % SLOT_COUNT
+}
+
+/// The hash tag of `key`: the part between the first `{` and the next `}`, if it is not empty.
+pub fn hash_tag(key: &[u8]) -> Option<&[u8]> {
+    let open = key.iter().position(|&b| b == b'{')?;
+    let len = key[open + 1..].iter().position(|&b| b == b'}')?;
+    match len {
+        0 => None,
+        len => Some(&key[open + 1..open + 1 + len]),
+    }
+}
+
+// CRC16-CCITT (XModem), as used by Redis Cluster.
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &b in bytes {
+        crc ^= (b as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
@@ -0,0 +1,123 @@
+//! [tokio-util](https://docs.rs/tokio-util) codec, to read and write frames through `Framed`. Requires feature `codec`.
+//!
+//! [RespCodec](RespCodec) decodes [RESPType](crate::RESPType)s from a `BytesMut`, waiting for more input while
+//! the frame at its start is incomplete, and encodes anything that implements `Serialize`.
+//!
+//! # Examples
+//! ```
+//! use bytes::BytesMut;
+//! use serde_resp::codec::RespCodec;
+//! use serde_resp::{array, bulk, int, RESP};
+//! use tokio_util::codec::{Decoder, Encoder};
+//!
+//! let mut codec = RespCodec::new();
+//! let mut buf = BytesMut::from(&b"*2\r\n$3\r\nGET\r\n$1"[..]);
+//! assert_eq!(None, codec.decode(&mut buf).unwrap());
+//! buf.extend_from_slice(b"\r\na\r\n:1\r\n");
+//! assert_eq!(Some(array![bulk!(b"GET".to_vec()), bulk!(b"a".to_vec())]), codec.decode(&mut buf).unwrap());
+//! assert_eq!(Some(int!(1)), codec.decode(&mut buf).unwrap());
+//! assert_eq!(None, codec.decode(&mut buf).unwrap());
+//!
+//! codec.encode(int!(2), &mut buf).unwrap();
+//! assert_eq!(b":2\r\n", &buf[..]);
+//! ```
+
+use crate::de::{Deserializer, Limits};
+use crate::lazy::FrameSplitter;
+use crate::ser::{Protocol, Serializer};
+use crate::{Error, RESPType, Result};
+
+use bytes::{Buf, BufMut, BytesMut};
+use serde::{Deserialize, Serialize};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Codec of RESP frames. Refer to [codec](self).
+#[derive(Clone, Debug, Default)]
+pub struct RespCodec {
+    limits: Limits,
+    protocol: Option<Protocol>,
+    // Where the frame at the start of the buffer is scanned up to.
+    splitter: FrameSplitter,
+}
+
+impl RespCodec {
+    /// Decode and encode frames without limits, for any protocol.
+    pub fn new() -> Self {
+        RespCodec::default()
+    }
+
+    /// Set the limits on frames decoded. Refer to [Limits](Limits).
+    ///
+    /// Lines, bulk strings, arrays and nesting over the limits fail as soon as they are received, without waiting
+    /// for the rest of the frame.
+    ///
+    /// # Examples
+    /// ```
+    /// use bytes::BytesMut;
+    /// use serde_resp::codec::RespCodec;
+    /// use serde_resp::de::Limits;
+    /// use serde_resp::LimitKind;
+    /// use tokio_util::codec::Decoder;
+    ///
+    /// let limits = Limits { max_array_len: 2, max_depth: 2, ..Limits::default() };
+    ///
+    /// // Only the header of the array was received
+    /// let mut buf = BytesMut::from(&b"*1000000\r\n:1\r\n"[..]);
+    /// let err = RespCodec::new().with_limits(limits).decode(&mut buf).unwrap_err();
+    /// assert_eq!(Some((LimitKind::ArrayLength, 1000000)), err.limit());
+    ///
+    /// let mut buf = BytesMut::from(&b"*1\r\n*1\r\n*1\r\n"[..]);
+    /// let err = RespCodec::new().with_limits(limits).decode(&mut buf).unwrap_err();
+    /// assert_eq!(Some((LimitKind::Depth, 3)), err.limit());
+    /// ```
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self.splitter = FrameSplitter::new(limits);
+        self
+    }
+
+    /// Encode frames for `protocol`. Refer to [Serializer::with_protocol](Serializer::with_protocol).
+    pub fn with_protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    /// Switch the protocol frames are encoded for, such as after a client sent `HELLO 3`.
+    pub fn set_protocol(&mut self, protocol: Protocol) {
+        self.protocol = Some(protocol);
+    }
+}
+
+impl Decoder for RespCodec {
+    type Item = RESPType;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<RESPType>> {
+        let len = match self.splitter.split(src)? {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+        let mut deserializer = Deserializer::from_buf_reader(&src[..len]).with_limits(self.limits);
+        let frame = RESPType::deserialize(&mut deserializer)?;
+        src.advance(len);
+        Ok(Some(frame))
+    }
+}
+
+impl<T: Serialize> Encoder<T> for RespCodec {
+    type Error = Error;
+
+    /// Encode `item` at the end of `dst`. If it fails to serialize, `dst` is left as it was.
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<()> {
+        let len = dst.len();
+        let mut serializer = Serializer::new(dst.writer());
+        let encoded = match self.protocol {
+            Some(protocol) => item.serialize(&mut serializer.with_protocol(protocol)),
+            None => item.serialize(&mut serializer),
+        };
+        if encoded.is_err() {
+            dst.truncate(len);
+        }
+        encoded
+    }
+}
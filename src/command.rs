@@ -0,0 +1,172 @@
+//! Recognizing the commands of requests.
+//!
+//! Command names are case-insensitive, so a server would otherwise allocate an uppercased copy of the name
+//! of every request to find its command. [CommandTable](CommandTable) compares names ignoring case without
+//! allocating, and gives back a value chosen by the server for each command, such as a `&'static str`
+//! or a variant of its own enum, to match on.
+//!
+//! # Examples
+//! ```
+//! use serde_resp::command::CommandTable;
+//! use serde_resp::{array, bulk, RESP};
+//!
+//! #[derive(Clone, Copy, Debug, PartialEq)]
+//! enum Command {
+//!     Get,
+//!     Set,
+//! }
+//!
+//! let table = CommandTable::new()
+//!     .with("GET", Command::Get)
+//!     .with("SET", Command::Set);
+//! let request = array![bulk!(b"get".to_vec()), bulk!(b"key".to_vec())];
+//! assert_eq!(Some(Command::Get), table.command(&request));
+//! assert_eq!(None, table.get(b"DEL"));
+//! ```
+
+use crate::{Error, RESPType, Result};
+
+use std::cmp::Ordering;
+
+/// Table of command names, ignoring case. Refer to [command](self).
+#[derive(Clone, Debug)]
+pub struct CommandTable<T = &'static str> {
+    // Lowercase names, sorted.
+    entries: Vec<(Vec<u8>, T)>,
+}
+
+impl CommandTable {
+    /// A table mapping each of `names` to itself, to match on `&'static str`s.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::command::CommandTable;
+    ///
+    /// let table = CommandTable::from_names(&["GET", "SET"]);
+    /// match table.get(b"sEt") {
+    ///     Some("GET") => unreachable!(),
+    ///     Some("SET") => {}
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn from_names(names: &[&'static str]) -> Self {
+        names
+            .iter()
+            .fold(CommandTable::new(), |table, &name| table.with(name, name))
+    }
+}
+
+impl<T: Copy> CommandTable<T> {
+    /// An empty table.
+    pub fn new() -> Self {
+        CommandTable {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Add command `name`, recognized as `command`, when building a table.
+    pub fn with(mut self, name: &str, command: T) -> Self {
+        self.insert(name, command);
+        self
+    }
+
+    /// Add command `name`, recognized as `command`. Returns the command it replaces, if any.
+    pub fn insert(&mut self, name: &str, command: T) -> Option<T> {
+        let name = name.to_ascii_lowercase().into_bytes();
+        match self.search(&name) {
+            Ok(index) => Some(std::mem::replace(&mut self.entries[index].1, command)),
+            Err(index) => {
+                self.entries.insert(index, (name, command));
+                None
+            }
+        }
+    }
+
+    /// Rename command `name` to `new_name` like the `rename-command` directive of Redis:
+    /// the command is no longer recognized by its old name, and an empty new name disables it.
+    ///
+    /// # Errors
+    /// Fails if there is no command `name`, or if `new_name` already names another command.
+    /// The table is left unchanged then.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::command::CommandTable;
+    ///
+    /// let mut table = CommandTable::from_names(&["CONFIG", "FLUSHALL"]);
+    /// table.rename("CONFIG", "b840fc02d524045429941cc15f59e41cb7be6c52").unwrap();
+    /// table.rename("flushall", "").unwrap();
+    /// assert_eq!(None, table.get(b"CONFIG"));
+    /// assert_eq!(Some("CONFIG"), table.get(b"B840FC02D524045429941CC15F59E41CB7BE6C52"));
+    /// assert_eq!(None, table.get(b"FLUSHALL"));
+    /// assert!(table.rename("FLUSHALL", "FA").is_err());
+    ///
+    /// let mut table = CommandTable::from_names(&["GET", "SET"]);
+    /// assert!(table.rename("GET", "set").is_err());
+    /// assert_eq!(Some("GET"), table.get(b"GET"));
+    /// assert_eq!(Some("SET"), table.get(b"SET"));
+    /// ```
+    pub fn rename(&mut self, name: &str, new_name: &str) -> Result<()> {
+        let index = self
+            .search(name.as_bytes())
+            .map_err(|_| Error::Message(format!("unknown command {:?}", name)))?;
+        if let Ok(existing) = self.search(new_name.as_bytes()) {
+            if existing != index {
+                return Err(Error::Message(format!(
+                    "command {:?} already exists",
+                    new_name
+                )));
+            }
+        }
+        let (_, command) = self.entries.remove(index);
+        if !new_name.is_empty() {
+            self.insert(new_name, command);
+        }
+        Ok(())
+    }
+
+    /// The command named `name`, ignoring case.
+    pub fn get(&self, name: &[u8]) -> Option<T> {
+        self.search(name).ok().map(|index| self.entries[index].1)
+    }
+
+    /// The command of `request`, an array starting with the name of the command as a bulk string.
+    pub fn command(&self, request: &RESPType) -> Option<T> {
+        match request {
+            RESPType::Array(Some(arr)) => match arr.first()? {
+                RESPType::BulkString(Some(name)) => self.get(name),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Number of commands.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there is no command.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn search(&self, name: &[u8]) -> std::result::Result<usize, usize> {
+        self.entries
+            .binary_search_by(|(entry, _)| compare_ignoring_case(entry, name))
+    }
+}
+
+impl<T: Copy> Default for CommandTable<T> {
+    fn default() -> Self {
+        CommandTable::new()
+    }
+}
+
+// Compare lowercase `entry` with `name` as if `name` was lowercase too.
+fn compare_ignoring_case(entry: &[u8], name: &[u8]) -> Ordering {
+    entry
+        .iter()
+        .copied()
+        .cmp(name.iter().map(u8::to_ascii_lowercase))
+}
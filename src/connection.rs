@@ -0,0 +1,279 @@
+//! Sending and receiving frames over any transport.
+//!
+//! [Connection](Connection) bundles a buffered [Deserializer](crate::de::Deserializer) over the reading half
+//! of a transport with a buffered writer over its writing half, such as the two halves of a `TcpStream`,
+//! a Unix socket or a TLS stream. It sits between the raw (de)serializers and full client or server
+//! frameworks: it sends commands, receives replies, pipelines batches of commands with a single flush,
+//! and keeps out-of-band frames such as pub/sub messages aside.
+//!
+//! # Examples
+//! ```
+//! use serde_resp::connection::Connection;
+//! use serde_resp::{array, bulk, int, simple, RESP};
+//!
+//! // The replies the server would send
+//! let replies = "+OK\r\n:1\r\n:2\r\n".as_bytes();
+//! let mut connection = Connection::new(replies, Vec::new());
+//!
+//! let reply = connection
+//!     .request(&array![bulk!(b"SET".to_vec()), bulk!(b"a".to_vec()), bulk!(b"0".to_vec())])
+//!     .unwrap();
+//! assert_eq!(simple!("OK".to_owned()), reply);
+//!
+//! let incr = array![bulk!(b"INCR".to_vec()), bulk!(b"a".to_vec())];
+//! let replies = connection.pipeline(&[incr.clone(), incr]).unwrap();
+//! assert_eq!(vec![int!(1), int!(2)], replies);
+//! assert!(connection.writer().starts_with(b"*3\r\n$3\r\nSET\r\n"));
+//! ```
+
+use crate::de::{Deserializer, Limits};
+use crate::ser::{Protocol, Serializer};
+use crate::{RESPType, Result};
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+/// A reader and a writer of frames, for both ends of a connection. Refer to [connection](self).
+pub struct Connection<R: Read, W: Write> {
+    reader: Deserializer<BufReader<R>>,
+    writer: BufWriter<W>,
+    pushes: VecDeque<RESPType>,
+    is_push: Box<dyn Fn(&RESPType) -> bool + Send + Sync>,
+    protocol: Option<Protocol>,
+}
+
+impl<R: Read, W: Write> Connection<R, W> {
+    /// Read frames from `reader` and write frames to `writer`, both buffered.
+    /// RESP3 [pushes](RESPType::Push) received are kept aside, see [next_push](Connection::next_push).
+    pub fn new(reader: R, writer: W) -> Self {
+        Connection {
+            reader: Deserializer::from_buf_reader(BufReader::new(reader)),
+            writer: BufWriter::new(writer),
+            pushes: VecDeque::new(),
+            is_push: Box::new(|frame| matches!(frame, RESPType::Push(_))),
+            protocol: None,
+        }
+    }
+
+    /// Set the limits on frames received. Refer to [Limits](Limits).
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.reader = self.reader.with_limits(limits);
+        self
+    }
+
+    /// Keep frames received for which `is_push` returns true aside, instead of returning them as replies.
+    /// Get them with [next_push](Connection::next_push). Like [Correlator::with_push_filter](crate::correlator::Correlator::with_push_filter),
+    /// this replaces the default of keeping RESP3 pushes aside.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::connection::Connection;
+    /// use serde_resp::{array, bulk, simple, RESP};
+    ///
+    /// let replies = "*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$2\r\nhi\r\n+PONG\r\n".as_bytes();
+    /// let mut connection = Connection::new(replies, Vec::new()).with_push_filter(|frame| match frame {
+    ///     RESP::Array(Some(arr)) => arr.first() == Some(&bulk!(b"message".to_vec())),
+    ///     _ => false,
+    /// });
+    /// let pong = connection.request(&array![bulk!(b"PING".to_vec())]).unwrap();
+    /// assert_eq!(simple!("PONG".to_owned()), pong);
+    /// assert_eq!(Some(bulk!(b"hi".to_vec())), connection.next_push().map(|message| match message {
+    ///     RESP::Array(Some(mut arr)) => arr.remove(2),
+    ///     _ => unreachable!(),
+    /// }));
+    /// ```
+    pub fn with_push_filter<F>(mut self, is_push: F) -> Self
+    where
+        F: Fn(&RESPType) -> bool + Send + Sync + 'static,
+    {
+        self.is_push = Box::new(is_push);
+        self
+    }
+
+    /// Write frames for `protocol`. Refer to [Serializer::with_protocol](Serializer::with_protocol).
+    pub fn with_protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    /// Switch the protocol frames are written for, such as after a client sent `HELLO 3`.
+    ///
+    /// Clients need not call it: a successful `HELLO 2` or `HELLO 3` sent with [request](Connection::request)
+    /// or [pipeline](Connection::pipeline) switches the protocol on its own.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::connection::Connection;
+    /// use serde_resp::ser::Protocol;
+    /// use serde_resp::{array, bulk, RESP};
+    ///
+    /// let requests = "*2\r\n$5\r\nHELLO\r\n$1\r\n3\r\n".as_bytes();
+    /// let mut connection = Connection::new(requests, Vec::new());
+    /// let hello = connection.recv_any().unwrap();
+    /// assert_eq!(array![bulk!(b"HELLO".to_vec()), bulk!(b"3".to_vec())], hello);
+    /// connection.set_protocol(Protocol::Resp3);
+    /// connection.send(&RESP::Null).unwrap();
+    /// connection.set_protocol(Protocol::Resp2);
+    /// connection.send(&RESP::Null).unwrap();
+    /// assert_eq!(b"_\r\n$-1\r\n", &connection.writer()[..]);
+    /// ```
+    pub fn set_protocol(&mut self, protocol: Protocol) {
+        self.protocol = Some(protocol);
+    }
+
+    /// The protocol frames are written for, if one was set.
+    pub fn protocol(&self) -> Option<Protocol> {
+        self.protocol
+    }
+
+    /// Write `frame` to the write buffer, to be sent by the next [flush](Connection::flush).
+    ///
+    /// # Errors
+    /// Fails if `frame` cannot be serialized, in which case nothing is written, or if writing fails.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::connection::Connection;
+    /// use serde_resp::{array, int, simple, RESP};
+    ///
+    /// let mut connection = Connection::new(&b""[..], Vec::new());
+    /// assert!(connection.send(&array![int!(1), simple!("a\r\nb".to_owned())]).is_err());
+    /// connection.send(&int!(7)).unwrap();
+    /// assert_eq!(b":7\r\n", &connection.writer()[..]);
+    /// ```
+    pub fn queue(&mut self, frame: &RESPType) -> Result<()> {
+        let mut buf = Vec::new();
+        self.serialize(frame, &mut buf)?;
+        Ok(self.writer.write_all(&buf)?)
+    }
+
+    // Append `frame` to `buf`, which is left with part of it on failure.
+    fn serialize(&self, frame: &RESPType, buf: &mut Vec<u8>) -> Result<()> {
+        let mut serializer = Serializer::new(buf);
+        match self.protocol {
+            Some(protocol) => frame.serialize(&mut serializer.with_protocol(protocol)),
+            None => frame.serialize(&mut serializer),
+        }
+    }
+
+    /// Send the frames in the write buffer.
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    /// Send `frame` now, along with the frames queued before it.
+    pub fn send(&mut self, frame: &RESPType) -> Result<()> {
+        self.queue(frame)?;
+        self.flush()
+    }
+
+    /// Receive the next frame that is not a push, keeping the pushes before it aside.
+    ///
+    /// # Errors
+    /// Fails like [de::from_buf_reader](crate::de::from_buf_reader). After a failure other than
+    /// running out of input, the connection is out of sync and should be closed.
+    pub fn recv(&mut self) -> Result<RESPType> {
+        loop {
+            let frame = RESPType::deserialize(&mut self.reader)?;
+            if !(self.is_push)(&frame) {
+                return Ok(frame);
+            }
+            self.pushes.push_back(frame);
+        }
+    }
+
+    /// Receive the next frame, which may be a push. For servers reading requests, which are never pushes.
+    pub fn recv_any(&mut self) -> Result<RESPType> {
+        RESPType::deserialize(&mut self.reader)
+    }
+
+    /// Send `command` and receive its reply.
+    pub fn request(&mut self, command: &RESPType) -> Result<RESPType> {
+        self.send(command)?;
+        let reply = self.recv()?;
+        self.track_hello(command, &reply);
+        Ok(reply)
+    }
+
+    /// Send all `commands` with a single flush, then receive their replies, in order.
+    ///
+    /// # Errors
+    /// Fails if sending fails, or like [recv](Connection::recv). Error replies are returned as replies.
+    /// If any command cannot be serialized, none of them are written nor sent, and the frames queued
+    /// before stay in the write buffer.
+    pub fn pipeline(&mut self, commands: &[RESPType]) -> Result<Vec<RESPType>> {
+        let mut buf = Vec::new();
+        for command in commands {
+            self.serialize(command, &mut buf)?;
+        }
+        self.writer.write_all(&buf)?;
+        self.flush()?;
+        commands
+            .iter()
+            .map(|command| {
+                let reply = self.recv()?;
+                self.track_hello(command, &reply);
+                Ok(reply)
+            })
+            .collect()
+    }
+
+    // Switch protocol once the server accepted `HELLO 2` or `HELLO 3`.
+    fn track_hello(&mut self, command: &RESPType, reply: &RESPType) {
+        if let RESPType::Error(_) = reply {
+            return;
+        }
+        if let RESPType::Array(Some(args)) = command {
+            match args.get(..2) {
+                Some([RESPType::BulkString(Some(name)), RESPType::BulkString(Some(version))])
+                    if name.eq_ignore_ascii_case(b"HELLO") =>
+                {
+                    match &version[..] {
+                        b"2" => self.protocol = Some(Protocol::Resp2),
+                        b"3" => self.protocol = Some(Protocol::Resp3),
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The oldest push received and not taken yet.
+    pub fn next_push(&mut self) -> Option<RESPType> {
+        self.pushes.pop_front()
+    }
+
+    /// Get a reference to the deserializer reading frames, e.g. to check [last_frame_len](Deserializer::last_frame_len).
+    pub fn reader(&self) -> &Deserializer<BufReader<R>> {
+        &self.reader
+    }
+
+    /// Get a reference to the writer. Frames still in the write buffer are not in it yet.
+    pub fn writer(&self) -> &W {
+        self.writer.get_ref()
+    }
+
+    /// Get the reader and the writer back, after flushing the write buffer.
+    /// Bytes received but not read yet are lost.
+    ///
+    /// # Errors
+    /// Fails if flushing fails.
+    pub fn into_parts(self) -> Result<(R, W)> {
+        let writer = self.writer.into_inner().map_err(|e| e.into_error())?;
+        Ok((self.reader.into_inner().into_inner(), writer))
+    }
+}
+
+impl<R: Read, W: Write> fmt::Debug for Connection<R, W> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("Connection")
+            .field("buffered", &self.writer.buffer().len())
+            .field("pushes", &self.pushes)
+            .field("protocol", &self.protocol)
+            .finish()
+    }
+}
@@ -0,0 +1,243 @@
+//! Pairing pipelined commands with their replies.
+//!
+//! Redis answers commands on a connection in the order they were sent. [Correlator](Correlator) keeps the
+//! commands waiting to be written and the requests waiting for a reply, and hands each reply to the oldest
+//! waiting request through a [PendingReply](PendingReply), a future that needs no particular async runtime.
+//! Out-of-band frames that answer no request, such as pub/sub messages, are recognized by a filter
+//! and kept aside. By default, the filter recognizes RESP3 pushes.
+//!
+//! A client typically shares the correlator between the task writing to the connection,
+//! which takes commands with [next_command](Correlator::next_command), and the task reading from it,
+//! which passes every frame to [handle_frame](Correlator::handle_frame).
+//!
+//! # Examples
+//! ```
+//! use serde_resp::correlator::Correlator;
+//! use serde_resp::{array, bulk, int, simple, RESP};
+//!
+//! let mut correlator = Correlator::new();
+//! let set = correlator.send(array![bulk!(b"SET".to_vec()), bulk!(b"a".to_vec()), bulk!(b"1".to_vec())]);
+//! let incr = correlator.send(array![bulk!(b"INCR".to_vec()), bulk!(b"a".to_vec())]);
+//!
+//! // Writer side
+//! let mut written = 0;
+//! while let Some(command) = correlator.next_command() {
+//!     written += 1; // serialize `command` to the connection here
+//! }
+//! assert_eq!(2, written);
+//!
+//! // Reader side
+//! correlator.handle_frame(simple!("OK".to_owned())).unwrap();
+//! correlator.handle_frame(int!(2)).unwrap();
+//!
+//! // `set` and `incr` can be awaited, or checked without blocking.
+//! assert_eq!(simple!("OK".to_owned()), set.try_take().unwrap().unwrap());
+//! assert_eq!(int!(2), incr.try_take().unwrap().unwrap());
+//! assert!(correlator.handle_frame(int!(3)).is_err());
+//! ```
+
+use crate::{Error, RESPType, Result};
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// Queue of pipelined commands and the requests waiting for their replies. Refer to [correlator](self).
+pub struct Correlator {
+    commands: VecDeque<RESPType>,
+    waiting: VecDeque<Arc<Mutex<Slot>>>,
+    pushes: VecDeque<RESPType>,
+    is_push: Box<dyn Fn(&RESPType) -> bool + Send + Sync>,
+}
+
+impl Correlator {
+    /// A correlator that keeps RESP3 [pushes](RESPType::Push) aside, and treats every other frame as a reply.
+    pub fn new() -> Self {
+        Correlator {
+            commands: VecDeque::new(),
+            waiting: VecDeque::new(),
+            pushes: VecDeque::new(),
+            is_push: Box::new(|frame| matches!(frame, RESPType::Push(_))),
+        }
+    }
+
+    /// Keep frames for which `is_push` returns true aside, instead of treating them as replies.
+    /// Get them with [next_push](Correlator::next_push). This replaces the default, so return true for
+    /// RESP3 pushes too if the server may send them.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::correlator::Correlator;
+    /// use serde_resp::{array, bulk, simple, RESP};
+    ///
+    /// // RESP2 pub/sub messages are arrays starting with "message".
+    /// let mut correlator = Correlator::new().with_push_filter(|frame| match frame {
+    ///     RESP::Array(Some(arr)) => arr.first() == Some(&bulk!(b"message".to_vec())),
+    ///     _ => false,
+    /// });
+    /// let ping = correlator.send(array![bulk!(b"PING".to_vec())]);
+    /// let message = array![bulk!(b"message".to_vec()), bulk!(b"news".to_vec()), bulk!(b"hi".to_vec())];
+    /// correlator.handle_frame(message.clone()).unwrap();
+    /// correlator.handle_frame(simple!("PONG".to_owned())).unwrap();
+    /// assert_eq!(Some(message), correlator.next_push());
+    /// assert_eq!(simple!("PONG".to_owned()), ping.try_take().unwrap().unwrap());
+    /// ```
+    pub fn with_push_filter<F>(mut self, is_push: F) -> Self
+    where
+        F: Fn(&RESPType) -> bool + Send + Sync + 'static,
+    {
+        self.is_push = Box::new(is_push);
+        self
+    }
+
+    /// Queue `command` to be written, and get its reply once it arrives.
+    pub fn send(&mut self, command: RESPType) -> PendingReply {
+        self.commands.push_back(command);
+        self.expect_reply()
+    }
+
+    /// Get the reply to a command written without [send](Correlator::send), once it arrives.
+    /// Replies are matched in order, so call it in the order the commands are written.
+    pub fn expect_reply(&mut self) -> PendingReply {
+        let slot = Arc::new(Mutex::new(Slot::default()));
+        self.waiting.push_back(slot.clone());
+        PendingReply { slot }
+    }
+
+    /// The next command to write to the connection.
+    pub fn next_command(&mut self) -> Option<RESPType> {
+        self.commands.pop_front()
+    }
+
+    /// Pass a frame read from the connection, to be kept aside if it is a push, or given to the oldest waiting request.
+    ///
+    /// # Errors
+    /// Fails if the frame is a reply but no request is waiting, meaning the connection is out of sync.
+    pub fn handle_frame(&mut self, frame: RESPType) -> Result<()> {
+        if (self.is_push)(&frame) {
+            self.pushes.push_back(frame);
+            return Ok(());
+        }
+        match self.waiting.pop_front() {
+            Some(slot) => {
+                fill(&slot, Ok(frame));
+                Ok(())
+            }
+            None => Err(Error::Message(
+                "reply received with no request waiting".to_owned(),
+            )),
+        }
+    }
+
+    /// The oldest push frame not taken yet.
+    pub fn next_push(&mut self) -> Option<RESPType> {
+        self.pushes.pop_front()
+    }
+
+    /// Number of requests waiting for a reply.
+    pub fn waiting(&self) -> usize {
+        self.waiting.len()
+    }
+
+    /// Fail every waiting request with `error`, e.g. when the connection is lost, and drop the queued commands.
+    pub fn fail_all(&mut self, error: Error) {
+        self.commands.clear();
+        for slot in self.waiting.drain(..) {
+            fill(&slot, Err(error.clone()));
+        }
+    }
+}
+
+impl Default for Correlator {
+    fn default() -> Self {
+        Correlator::new()
+    }
+}
+
+impl fmt::Debug for Correlator {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("Correlator")
+            .field("commands", &self.commands)
+            .field("waiting", &self.waiting.len())
+            .field("pushes", &self.pushes)
+            .finish()
+    }
+}
+
+/// Requests still waiting fail with [Error::Eof](Error::Eof).
+impl Drop for Correlator {
+    fn drop(&mut self) {
+        self.fail_all(Error::Eof);
+    }
+}
+
+#[derive(Debug, Default)]
+struct Slot {
+    reply: Option<Result<RESPType>>,
+    waker: Option<Waker>,
+}
+
+fn fill(slot: &Mutex<Slot>, reply: Result<RESPType>) {
+    let mut slot = slot.lock().unwrap_or_else(|e| e.into_inner());
+    slot.reply = Some(reply);
+    if let Some(waker) = slot.waker.take() {
+        waker.wake();
+    }
+}
+
+/// The reply to a request, returned by [Correlator::send](Correlator::send).
+///
+/// Await it, or check it without blocking with [try_take](PendingReply::try_take).
+/// It resolves to an error if the correlator fails or is dropped before the reply arrives.
+/// Dropping it discards the reply, which still takes its turn.
+///
+/// # Examples
+/// ```
+/// use serde_resp::correlator::Correlator;
+/// use serde_resp::{array, bulk, Error, RESP};
+/// use std::future::Future;
+/// use std::pin::Pin;
+/// use std::task::{Context, Poll, Waker};
+///
+/// let mut correlator = Correlator::new();
+/// let mut reply = correlator.send(array![bulk!(b"PING".to_vec())]);
+/// let mut cx = Context::from_waker(Waker::noop());
+/// assert!(Pin::new(&mut reply).poll(&mut cx).is_pending());
+/// drop(correlator); // e.g. the connection is lost
+/// let polled = Pin::new(&mut reply).poll(&mut cx);
+/// assert!(matches!(polled, Poll::Ready(Err(Error::Eof))));
+/// ```
+#[derive(Debug)]
+pub struct PendingReply {
+    slot: Arc<Mutex<Slot>>,
+}
+
+impl PendingReply {
+    /// Take the reply if it has arrived.
+    pub fn try_take(&self) -> Option<Result<RESPType>> {
+        self.slot
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .reply
+            .take()
+    }
+}
+
+impl Future for PendingReply {
+    type Output = Result<RESPType>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.slot.lock().unwrap_or_else(|e| e.into_inner());
+        match slot.reply.take() {
+            Some(reply) => Poll::Ready(reply),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -1,58 +1,747 @@
-use crate::{Error, RESPType, Result};
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+use crate::async_io;
+use crate::metrics::Metrics;
+use crate::ser::{
+    ATTRIBUTE_TOKEN, BIG_NUMBER_TOKEN, MAP_TOKEN, NULL_TOKEN, PUSH_TOKEN, SET_TOKEN, VERBATIM_TOKEN,
+};
+use crate::{borrowed, lazy};
+use crate::{Error, ErrorKind, LimitKind, RESPKind, RESPRef, RESPType, Result};
 
-use serde::de::{self, DeserializeOwned, DeserializeSeed, SeqAccess, Visitor};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, SeqAccess, VariantAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
 use serde::Deserialize;
 
 use std::fmt;
-use std::io::{BufRead, BufReader, Cursor, Read};
+use std::io::{self, BufRead, BufReader, Cursor, Read};
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::str::{self, FromStr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 /// Deserializer for RESP format
-pub struct Deserializer<'de, R: BufRead> {
-    reader: &'de mut R,
+///
+/// It can be wrapped by [serde_path_to_error](https://docs.rs/serde_path_to_error) to find out
+/// which element of an array failed to deserialize.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{de::Deserializer, RESP};
+///
+/// let mut reader = "*2\r\n:1\r\n*2\r\n+OK\r\n:x\r\n".as_bytes();
+/// let mut deserializer = Deserializer::from_buf_reader(&mut reader);
+/// let err = serde_path_to_error::deserialize::<_, RESP>(&mut deserializer).unwrap_err();
+/// assert_eq!("[1][1]", err.path().to_string());
+/// assert!(err.inner().is_protocol());
+/// ```
+///
+/// The reader may also be a trait object, `&mut dyn BufRead` or `Box<dyn BufRead>`,
+/// so the transport type can be erased.
+/// ```
+/// use serde::Deserialize;
+/// use serde_resp::{de::{self, Deserializer}, int, simple, RESP};
+/// use std::io::BufRead;
+///
+/// let mut reader: Box<dyn BufRead> = Box::new("+OK\r\n:1\r\n".as_bytes());
+/// let dyn_reader: &mut dyn BufRead = &mut reader;
+/// assert_eq!(simple!("OK".to_owned()), de::from_buf_reader::<RESP, _>(dyn_reader).unwrap());
+///
+/// let mut deserializer: Deserializer<Box<dyn BufRead>> = Deserializer::from_buf_reader(reader);
+/// assert_eq!(int!(1), RESP::deserialize(&mut deserializer).unwrap());
+/// ```
+pub struct Deserializer<R: BufRead, M: Metrics = ()> {
+    reader: R,
+    limits: Limits,
+    metrics: M,
+    // Bytes consumed from reader so far.
+    pos: usize,
+    // Arrays currently being deserialized.
+    depth: usize,
+    // Largest part of a bulk string allocated before it is read.
+    bulk_chunk_size: usize,
+    // Length of the last top-level frame read.
+    last_frame_len: Option<usize>,
+    budget: Option<Budget>,
+    // Buffers given back to be reused for bulk strings.
+    spare: Vec<Vec<u8>>,
 }
 
-impl<'de, R: BufRead> Deserializer<'de, R> {
+impl<R: BufRead> Deserializer<R> {
     /// Method for building Deserializer
-    pub fn from_buf_reader(reader: &'de mut R) -> Deserializer<'de, R> {
-        Deserializer { reader }
+    ///
+    /// The Deserializer owns `reader`. To keep using a reader afterwards, pass it by `&mut`,
+    /// or get it back with [into_inner](Deserializer::into_inner).
+    pub fn from_buf_reader(reader: R) -> Deserializer<R> {
+        Deserializer {
+            reader,
+            limits: Limits::default(),
+            metrics: (),
+            pos: 0,
+            depth: 0,
+            bulk_chunk_size: usize::MAX,
+            last_frame_len: None,
+            budget: None,
+            spare: Vec::new(),
+        }
+    }
+}
+
+impl<R: BufRead, M: Metrics> Deserializer<R, M> {
+    /// Report bytes and frames read to `metrics`. Refer to [Metrics](Metrics).
+    pub fn with_metrics<N: Metrics>(self, metrics: N) -> Deserializer<R, N> {
+        Deserializer {
+            reader: self.reader,
+            limits: self.limits,
+            metrics,
+            pos: self.pos,
+            depth: self.depth,
+            bulk_chunk_size: self.bulk_chunk_size,
+            last_frame_len: self.last_frame_len,
+            budget: self.budget,
+            spare: self.spare,
+        }
+    }
+
+    /// Set the limits on values this Deserializer accepts.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_resp::de::{Deserializer, Limits};
+    /// use serde_resp::{LimitKind, RESP};
+    ///
+    /// let limits = Limits {
+    ///     max_bulk_len: 4,
+    ///     ..Limits::default()
+    /// };
+    /// let mut reader = "$6\r\nfoobar\r\n".as_bytes();
+    /// let mut deserializer = Deserializer::from_buf_reader(&mut reader).with_limits(limits);
+    /// let err = RESP::deserialize(&mut deserializer).unwrap_err();
+    /// assert!(err.is_limit());
+    /// assert_eq!(Some((LimitKind::BulkLength, 6)), err.limit());
+    /// ```
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Read bulk strings in chunks of at most `size` bytes, growing their buffer as data arrives.
+    ///
+    /// By default, the whole buffer of a bulk string is allocated as soon as its length is read.
+    /// A smaller chunk size keeps a peer that announces a large bulk string but sends little data
+    /// from making this Deserializer allocate much, at the cost of more copies for large ones.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_resp::de::Deserializer;
+    /// use serde_resp::RESP;
+    ///
+    /// // Claims 1 GiB, but sends 3 bytes.
+    /// let mut reader = "$1073741824\r\nfoo".as_bytes();
+    /// let mut deserializer = Deserializer::from_buf_reader(&mut reader).with_bulk_chunk_size(64 * 1024);
+    /// assert!(RESP::deserialize(&mut deserializer).unwrap_err().is_eof());
+    /// ```
+    pub fn with_bulk_chunk_size(mut self, size: usize) -> Self {
+        self.bulk_chunk_size = size.max(1);
+        self
+    }
+
+    /// Charge every byte read to `budget`, failing with [Error::LimitExceeded](Error::LimitExceeded)
+    /// once it runs out. Refer to [Budget](Budget).
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Deserialize every value until the reader is exhausted, like [read_many](read_many),
+    /// with the limits, budget and metrics of this Deserializer.
+    ///
+    /// # Errors
+    /// Failures are wrapped in [Error::Batch](Error::Batch), where `consumed` counts from where this
+    /// Deserializer was when called.
+    pub fn read_many<T: DeserializeOwned>(&mut self) -> Result<Vec<T>> {
+        read_all(self).map_err(|(error, _)| error)
+    }
+
+    /// Replace the values in `values` with every value until the reader is exhausted, like [decode_into](decode_into),
+    /// reusing the buffers of this Deserializer as well as those of the old values.
+    ///
+    /// # Errors
+    /// Like [read_many](Deserializer::read_many). The values read before the failure are left in `values`.
+    pub fn decode_into(&mut self, values: &mut Vec<RESPType>) -> Result<()> {
+        for value in values.drain(..) {
+            self.recycle(value);
+        }
+        read_all_into(self, values).map_err(|(error, _)| error)
+    }
+
+    /// Iterate over the values until the reader is exhausted, deserializing each one when it is reached,
+    /// such as to handle the commands of a connection one at a time.
+    ///
+    /// The iterator ends at the end of the reader, or after the first failure. Failures are wrapped like with
+    /// [read_many](Deserializer::read_many).
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{array, bulk, de::Deserializer, int, RESP};
+    ///
+    /// let reader = "*1\r\n$4\r\nPING\r\n:1\r\n:x\r\n:2\r\n".as_bytes();
+    /// let mut deserializer = Deserializer::from_buf_reader(reader);
+    /// let mut values = deserializer.iter::<RESP>();
+    /// assert_eq!(array![bulk!(b"PING".to_vec())], values.next().unwrap().unwrap());
+    /// assert_eq!(int!(1), values.next().unwrap().unwrap());
+    /// let err = values.next().unwrap().unwrap_err();
+    /// assert_eq!(Some(2), err.batch_index());
+    /// assert!(err.is_protocol());
+    /// assert!(values.next().is_none());
+    /// ```
+    pub fn iter<T: DeserializeOwned>(&mut self) -> Values<'_, T, R, M> {
+        Values {
+            start: self.pos,
+            deserializer: self,
+            index: 0,
+            done: false,
+            marker: PhantomData,
+        }
+    }
+
+    /// Check that the reader is exhausted, such as after the last value expected, so stray bytes
+    /// are not silently ignored.
+    ///
+    /// # Errors
+    /// Fails with [Error::TrailingBytes](Error::TrailingBytes) if the reader has more bytes,
+    /// or if reading fails.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_resp::de::Deserializer;
+    /// use serde_resp::{simple, RESP};
+    ///
+    /// let mut deserializer = Deserializer::from_buf_reader("+OK\r\ngarbage".as_bytes());
+    /// assert_eq!(simple!("OK".to_owned()), RESP::deserialize(&mut deserializer).unwrap());
+    /// let err = deserializer.end().unwrap_err();
+    /// assert!(err.is_protocol());
+    /// assert_eq!(Some(5), err.offset());
+    /// ```
+    pub fn end(&mut self) -> Result<()> {
+        let exhausted = loop {
+            match self.reader.fill_buf() {
+                Ok(available) => break available.is_empty(),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(Error::from(e).at(self.pos)),
+            }
+        };
+        if !exhausted {
+            return Err(Error::TrailingBytes.at(self.pos));
+        }
+        Ok(())
+    }
+
+    /// Keep the buffers of the bulk strings in `value`, including those nested in arrays,
+    /// to be reused for bulk strings read later instead of allocating new ones.
+    pub fn recycle(&mut self, value: RESPType) {
+        let mut stack = vec![value];
+        while let Some(value) = stack.pop() {
+            match value {
+                RESPType::BulkString(Some(buffer)) => self.recycle_buffer(buffer),
+                RESPType::Array(Some(elements))
+                | RESPType::Push(elements)
+                | RESPType::Set(elements) => stack.extend(elements),
+                RESPType::Map(pairs) => {
+                    for (k, v) in pairs {
+                        stack.push(k);
+                        stack.push(v);
+                    }
+                }
+                RESPType::Attribute { attributes, value } => {
+                    for (k, v) in attributes {
+                        stack.push(k);
+                        stack.push(v);
+                    }
+                    stack.push(*value);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Keep `buffer` to be reused for a bulk string read later, such as one taken out of a value
+    /// decoded before. Its content is discarded, only its allocation is reused.
+    pub fn recycle_buffer(&mut self, buffer: Vec<u8>) {
+        if buffer.capacity() > 0 {
+            self.spare.push(buffer);
+        }
+    }
+
+    /// Deserialize an array of integers, doubles or big numbers, or of numbers written as bulk strings, straight into a `Vec`,
+    /// without building a [RESPType](RESPType) for each element. A null array gives `None`.
+    ///
+    /// This suits large numeric replies, such as those of `BITFIELD`, `OBJECT FREQ` or scripts,
+    /// decoded into `i64`, `f64` or any other type parsed with [FromStr](std::str::FromStr).
+    ///
+    /// # Errors
+    /// Fails if the value is not an array, or if an element is not an integer, a double, a big number or
+    /// a bulk string holding a number of type `T`.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::de::Deserializer;
+    ///
+    /// let mut reader = "*3\r\n:1\r\n:-2\r\n$2\r\n30\r\n*2\r\n$3\r\n0.5\r\n,2\r\n*-1\r\n".as_bytes();
+    /// let mut deserializer = Deserializer::from_buf_reader(&mut reader);
+    /// assert_eq!(Some(vec![1i64, -2, 30]), deserializer.read_numbers().unwrap());
+    /// assert_eq!(Some(vec![0.5f64, 2.0]), deserializer.read_numbers().unwrap());
+    /// assert_eq!(None, deserializer.read_numbers::<i64>().unwrap());
+    ///
+    /// let mut deserializer = Deserializer::from_buf_reader("*2\r\n:1\r\n$3\r\nabc\r\n".as_bytes());
+    /// let err = deserializer.read_numbers::<i64>().unwrap_err();
+    /// assert_eq!(
+    ///     "decoding failed after 17 bytes: array element 1 is not a number: \"abc\"",
+    ///     format!("{:#}", err)
+    /// );
+    /// ```
+    pub fn read_numbers<T: FromStr>(&mut self) -> Result<Option<Vec<T>>> {
+        let start = self.pos;
+        let mut buf = [0u8; 1];
+        let value = match self.charge(1).and_then(|()| self.read_exact(&mut buf)) {
+            Err(e) => Err(e),
+            Ok(()) if buf[0] != b'*' => Err(Error::unexpected(buf[0], self.pos)),
+            Ok(()) => self.read_number_array(),
+        };
+        self.frame_read(buf[0], start, value)
+    }
+
+    /// Read a request the way a Redis server does on its listening socket: either a RESP array,
+    /// or an inline command such as `SET key value`, typed in by hand with telnet or `nc`.
+    ///
+    /// Inline commands are split on spaces and tabs, with arguments in double quotes allowing escapes
+    /// like `\n` and `\x41`, and arguments in single quotes allowing `\'`. Either way the request is
+    /// returned as an array of bulk strings. Empty lines are skipped, as Redis does.
+    ///
+    /// # Errors
+    /// Fails like [from_buf_reader](from_buf_reader), or if the quotes of an inline command are unbalanced.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::de::Deserializer;
+    /// use serde_resp::{array, bulk, RESP};
+    ///
+    /// let mut reader = "*1\r\n$4\r\nPING\r\n\r\nSET greeting \"hello world\\n\"\r\n".as_bytes();
+    /// let mut deserializer = Deserializer::from_buf_reader(&mut reader);
+    /// assert_eq!(array![bulk!(b"PING".to_vec())], deserializer.read_request().unwrap());
+    /// assert_eq!(
+    ///     array![
+    ///         bulk!(b"SET".to_vec()),
+    ///         bulk!(b"greeting".to_vec()),
+    ///         bulk!(b"hello world\n".to_vec())
+    ///     ],
+    ///     deserializer.read_request().unwrap()
+    /// );
+    ///
+    /// let mut deserializer = Deserializer::from_buf_reader("GET \"key\r\n".as_bytes());
+    /// assert!(deserializer.read_request().unwrap_err().is_protocol());
+    /// ```
+    pub fn read_request(&mut self) -> Result<RESPType> {
+        loop {
+            let first = match self.reader.fill_buf() {
+                Ok([]) => return Err(Error::CleanEof.at(self.pos)),
+                Ok(available) => available[0],
+                Err(e) => return Err(Error::from(e).at(self.pos)),
+            };
+            if first == b'*' {
+                return RESPType::deserialize(&mut *self);
+            }
+            let start = self.pos;
+            let mut line = Vec::new();
+            let request = self.read_line_into(&mut line).and_then(|()| {
+                split_inline(&line)
+                    .map(|args| RESPType::array(args.into_iter().map(RESPType::bulk)))
+            });
+            match request {
+                Ok(RESPType::Array(Some(args))) if args.is_empty() => continue,
+                request => return self.frame_read(b'*', start, request),
+            }
+        }
+    }
+
+    /// Length in bytes of the last complete top-level frame read, or `None` if none was read yet.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_resp::de::Deserializer;
+    /// use serde_resp::RESP;
+    ///
+    /// let mut deserializer = Deserializer::from_buf_reader("*1\r\n$3\r\nfoo\r\n:1\r\n".as_bytes());
+    /// assert_eq!(None, deserializer.last_frame_len());
+    /// RESP::deserialize(&mut deserializer).unwrap();
+    /// assert_eq!(Some(13), deserializer.last_frame_len());
+    /// RESP::deserialize(&mut deserializer).unwrap();
+    /// assert_eq!(Some(4), deserializer.last_frame_len());
+    /// ```
+    pub fn last_frame_len(&self) -> Option<usize> {
+        self.last_frame_len
+    }
+
+    /// Get the reader back. Data it buffered but this Deserializer did not consume stays in it.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_resp::de::Deserializer;
+    /// use serde_resp::{int, simple, RESP};
+    /// use std::io::BufReader;
+    ///
+    /// struct Connection {
+    ///     deserializer: Deserializer<BufReader<&'static [u8]>>,
+    /// }
+    ///
+    /// let mut connection = Connection {
+    ///     deserializer: Deserializer::from_buf_reader(BufReader::new(&b"+OK\r\n:1\r\n"[..])),
+    /// };
+    /// assert_eq!(simple!("OK".to_owned()), RESP::deserialize(&mut connection.deserializer).unwrap());
+    /// let mut reader = connection.deserializer.into_inner();
+    /// assert_eq!(int!(1), serde_resp::de::from_buf_reader(&mut reader).unwrap());
+    /// ```
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Get a reference to the reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Get a mutable reference to the reader. Reading from it directly may leave the Deserializer in the middle of a frame.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+}
+
+/// Limits on the size of values accepted by a [Deserializer](Deserializer).
+///
+/// Exceeding any of them fails with [Error::LimitExceeded](Error::LimitExceeded).
+/// By default, nothing is limited.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Limits {
+    /// Maximum length of a bulk string.
+    pub max_bulk_len: usize,
+    /// Maximum number of elements in an array. The elements of a map are its keys and its values,
+    /// and those of an attribute are followed by the value it is about.
+    pub max_array_len: usize,
+    /// Maximum nesting depth of arrays. A top-level array has depth 1.
+    pub max_depth: usize,
+    /// Maximum length of a line (simple string, error, integer or length header) after its type byte, including CRLF.
+    pub max_line_len: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_bulk_len: usize::MAX,
+            max_array_len: usize::MAX,
+            max_depth: usize::MAX,
+            max_line_len: usize::MAX,
+        }
+    }
+}
+
+/// Memory budget shared by any number of [Deserializer](Deserializer)s, such as those of all connections of a server.
+///
+/// Every byte a Deserializer with the budget reads is charged to it, bulk strings as soon as their length
+/// is known and before anything is allocated for them. Arrays are not allocated ahead of their elements,
+/// which are charged as they are read. Once a read would exceed the budget, it fails with
+/// [Error::LimitExceeded](Error::LimitExceeded) for [LimitKind::Budget](LimitKind::Budget).
+/// Per-frame [Limits](Limits) alone do not stop a pipelined burst of many frames of the maximum size.
+///
+/// Charged bytes are not given back automatically, as decoded values outlive the Deserializer.
+/// [release](Budget::release) them once the values are processed. Clones share the same budget.
+///
+/// # Examples
+/// ```
+/// use serde_resp::de::{Budget, Deserializer};
+/// use serde_resp::{LimitKind, RESP};
+///
+/// let budget = Budget::new(32);
+/// let mut deserializer = Deserializer::from_buf_reader("$10\r\n0123456789\r\n".as_bytes())
+///     .with_budget(budget.clone());
+/// let values: Vec<RESP> = deserializer.read_many().unwrap();
+/// assert_eq!(17, budget.used());
+///
+/// // Another connection sharing the budget
+/// let mut deserializer = Deserializer::from_buf_reader("$10\r\n0123456789\r\n".as_bytes())
+///     .with_budget(budget.clone());
+/// let err = deserializer.read_many::<RESP>().unwrap_err();
+/// assert_eq!(Some((LimitKind::Budget, 34)), err.limit());
+/// // The header read before the failure stays charged.
+/// assert_eq!(22, budget.used());
+///
+/// drop(values);
+/// budget.release(22);
+/// assert_eq!(0, budget.used());
+///
+/// // An array claiming 100 billion elements
+/// let budget = Budget::new(64);
+/// let input = format!("*100000000000\r\n{}", ":1\r\n".repeat(100));
+/// let mut deserializer = Deserializer::from_buf_reader(input.as_bytes()).with_budget(budget);
+/// let err = deserializer.read_many::<RESP>().unwrap_err();
+/// assert_eq!(Some(LimitKind::Budget), err.limit().map(|(limit, _)| limit));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Budget {
+    inner: Arc<BudgetInner>,
+}
+
+#[derive(Debug)]
+struct BudgetInner {
+    max: usize,
+    used: AtomicUsize,
+}
+
+impl Budget {
+    /// A budget of `max` bytes.
+    pub fn new(max: usize) -> Self {
+        Budget {
+            inner: Arc::new(BudgetInner {
+                max,
+                used: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Bytes charged and not released yet.
+    pub fn used(&self) -> usize {
+        self.inner.used.load(Ordering::Relaxed)
+    }
+
+    /// Bytes that can still be charged.
+    pub fn remaining(&self) -> usize {
+        self.inner.max.saturating_sub(self.used())
+    }
+
+    /// Give `n` charged bytes back, e.g. once the values decoded from them are dropped.
+    pub fn release(&self, n: usize) {
+        let _ = self
+            .inner
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                Some(used.saturating_sub(n))
+            });
+    }
+
+    // Charge `n` bytes, failing without charging anything if they do not fit.
+    fn charge(&self, n: usize) -> Result<()> {
+        let max = self.inner.max;
+        self.inner
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                used.checked_add(n).filter(|&total| total <= max)
+            })
+            .map(|_| ())
+            .map_err(|used| Error::LimitExceeded {
+                limit: LimitKind::Budget,
+                actual: used as u64 + n as u64,
+            })
+    }
+}
+
+// Split an inline command into its arguments, with quotes and escapes like redis-cli and the Redis server.
+fn split_inline(line: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let mut args = Vec::new();
+    let mut bytes = line.iter().copied().peekable();
+    loop {
+        while bytes.next_if(|b| b.is_ascii_whitespace()).is_some() {}
+        let quote = match bytes.peek() {
+            None => return Ok(args),
+            Some(&b) if b == b'"' || b == b'\'' => bytes.next(),
+            Some(_) => None,
+        };
+        let mut arg = Vec::new();
+        loop {
+            let b = match (bytes.next(), quote) {
+                (None, None) => break,
+                (None, Some(_)) => return Err(Error::Syntax), // unbalanced quotes
+                (Some(b), None) if b.is_ascii_whitespace() => break,
+                (Some(b), Some(q)) if b == q => {
+                    // A closing quote must end the argument.
+                    if bytes.peek().is_some_and(|b| !b.is_ascii_whitespace()) {
+                        return Err(Error::Syntax);
+                    }
+                    break;
+                }
+                (Some(b'\\'), Some(b'"')) => match bytes.next() {
+                    Some(b'x') => {
+                        let hex = [bytes.next(), bytes.next()];
+                        let digits = match hex {
+                            [Some(high), Some(low)] => [high, low],
+                            _ => return Err(Error::Syntax),
+                        };
+                        match str::from_utf8(&digits)
+                            .ok()
+                            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                        {
+                            Some(b) => b,
+                            None => return Err(Error::Syntax),
+                        }
+                    }
+                    Some(b'n') => b'\n',
+                    Some(b'r') => b'\r',
+                    Some(b't') => b'\t',
+                    Some(b'b') => 0x08,
+                    Some(b'a') => 0x07,
+                    Some(b) => b,
+                    None => return Err(Error::Syntax),
+                },
+                (Some(b'\\'), Some(b'\'')) if bytes.peek() == Some(&b'\'') => {
+                    bytes.next();
+                    b'\''
+                }
+                (Some(b), _) => b,
+            };
+            arg.push(b);
+        }
+        args.push(arg);
+    }
+}
+
+fn not_a_number(index: usize, found: &str) -> Error {
+    Error::Message(format!(
+        "array element {} is not a number: {}",
+        index, found
+    ))
+}
+
+// Fail with Error::LimitExceeded if actual > max.
+pub(crate) fn check_limit(limit: LimitKind, max: usize, actual: usize) -> Result<()> {
+    if actual > max {
+        return Err(Error::LimitExceeded {
+            limit,
+            actual: actual as u64,
+        });
     }
+    Ok(())
 }
 
 /// Deserialize from str.
 ///
 /// This function simple wraps the `&str` with `Cursor` and calls [from_buf_reader](from_buf_reader).
 ///
+/// Besides [RESP](crate::RESP), values can be deserialized into fixed-size arrays such as `[RESP; N]`,
+/// which need an array of exactly `N` elements. To convert the elements into other types,
+/// use [value::from_value](crate::value::from_value).
+///
+/// # Examples
+/// ```
+/// use serde_resp::{bulk, de, value, RESP};
+///
+/// // Reply to TIME
+/// let reply = "*2\r\n$10\r\n1700000000\r\n$6\r\n123456\r\n";
+/// let [seconds, micros]: [RESP; 2] = de::from_str(reply).unwrap();
+/// assert_eq!(bulk!(b"1700000000".to_vec()), seconds);
+/// assert_eq!(123456, value::from_value::<u32>(micros).unwrap());
+///
+/// let err = de::from_str::<[RESP; 2]>("*3\r\n:1\r\n:2\r\n:3\r\n").unwrap_err();
+/// assert_eq!(
+///     "decoding failed after 4 bytes: invalid length 3, expected an array of length 2",
+///     format!("{:#}", err)
+/// );
+/// ```
+///
 /// # Errors
 /// Please refer to [Error](Error)
 pub fn from_str<T>(s: &str) -> Result<T>
 where
     T: DeserializeOwned,
 {
-    let mut reader = Cursor::new(s);
-    from_buf_reader(&mut reader)
+    from_buf_reader(Cursor::new(s))
+}
+
+/// Same as [from_str](from_str), but fails if `s` has anything after the value, which
+/// [from_str](from_str) ignores. This catches protocol bugs such as a reply with an extra CRLF in tests.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{de, simple, RESP};
+///
+/// assert_eq!(simple!("OK".to_owned()), de::from_str_exact::<RESP>("+OK\r\n").unwrap());
+/// assert!(de::from_str::<RESP>("+OK\r\ngarbage").is_ok());
+/// assert!(de::from_str_exact::<RESP>("+OK\r\ngarbage").unwrap_err().is_protocol());
+/// ```
+///
+/// # Errors
+/// Please refer to [Error](Error). Bytes after the value fail with [Error::TrailingBytes](Error::TrailingBytes).
+pub fn from_str_exact<T>(s: &str) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let mut deserializer = Deserializer::from_buf_reader(s.as_bytes());
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
 }
 
 /// Deserialize from reader with `Read` trait.
 ///
-/// This function simply wraps the reader with a `BufReader` and calls [from_buf_reader](from_buf_reader).
-/// If your reader has `BufRead` trait, use [from_buf_reader](from_buf_reader) instead.
+/// The reader can be passed by value or by `&mut`.
+/// This function simply wraps the reader with a `BufReader` and calls [from_buf_reader](from_buf_reader),
+/// so it may read past the end of the value, and those bytes are lost.
+/// If your reader has `BufRead` trait, such as a `BufReader` or `&[u8]`, use [from_buf_reader](from_buf_reader)
+/// instead, which does not buffer again and leaves anything after the value in the reader.
+///
+/// # Errors
+/// Please refer to [Error](Error)
+pub fn from_reader<T, R>(reader: R) -> Result<T>
+where
+    T: DeserializeOwned,
+    R: Read,
+{
+    from_buf_reader(BufReader::new(reader))
+}
+
+/// Same as [from_reader](from_reader), with a `BufReader` of `capacity` bytes instead of the default 8 KiB.
+///
+/// Larger buffers mean fewer reads for bulk-heavy replies, smaller ones less memory per connection.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{bulk, de, RESP};
+///
+/// let reply = format!("${}\r\n{}\r\n", 100_000, "x".repeat(100_000));
+/// let value: RESP = de::from_reader_with_capacity(1 << 20, reply.as_bytes()).unwrap();
+/// assert_eq!(bulk!(vec![b'x'; 100_000]), value);
+/// ```
 ///
 /// # Errors
 /// Please refer to [Error](Error)
-pub fn from_reader<T, R>(reader: &mut R) -> Result<T>
+pub fn from_reader_with_capacity<T, R>(capacity: usize, reader: R) -> Result<T>
 where
     T: DeserializeOwned,
     R: Read,
 {
-    let mut reader = BufReader::new(reader);
-    from_buf_reader(&mut reader)
+    from_buf_reader(BufReader::with_capacity(capacity, reader))
 }
 
 /// Deserialize from reader with `BufRead` trait.
 ///
+/// The reader can be passed by value or by `&mut`. Passed by `&mut`, it is left right after the value.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{de, int, simple, RESP};
+/// use std::io::BufReader;
+///
+/// let mut reader = BufReader::new("+OK\r\n:1\r\n".as_bytes());
+/// assert_eq!(simple!("OK".to_owned()), de::from_buf_reader::<RESP, _>(&mut reader).unwrap());
+/// assert_eq!(int!(1), de::from_buf_reader::<RESP, _>(reader).unwrap());
+/// ```
+///
 /// # Errors
 /// Please refer to [Error](Error)
-pub fn from_buf_reader<T, R>(reader: &mut R) -> Result<T>
+pub fn from_buf_reader<T, R>(reader: R) -> Result<T>
 where
     T: DeserializeOwned,
     R: BufRead,
@@ -62,11 +751,952 @@ where
     Ok(t)
 }
 
-impl<'de, R: BufRead> Deserializer<'de, R> {
-    // read until LF, trim end, and parse to isize.
+/// Deserialize the frame at the start of `bytes`, and return it along with its length in bytes,
+/// which is where the next frame begins.
+///
+/// This suits network buffers holding several frames, or a frame and the start of the next one,
+/// without wrapping them in a `Cursor` to find out how much was read.
+///
+/// # Errors
+/// Please refer to [Error](Error). If `bytes` end in the middle of the frame,
+/// [is_incomplete](Error::is_incomplete) is true, and reading more input may complete it.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{de, int, simple, RESP};
+///
+/// let mut buf = b"+OK\r\n:1\r\n$5\r\nhel".to_vec();
+/// let (reply, len) = de::from_bytes(&buf).unwrap();
+/// assert_eq!((simple!("OK".to_owned()), 5), (reply, len));
+/// buf.drain(..len);
+/// assert_eq!((int!(1), 4), de::from_bytes(&buf).unwrap());
+/// buf.drain(..4);
+/// assert!(de::from_bytes(&buf).unwrap_err().is_incomplete());
+/// ```
+pub fn from_bytes(bytes: &[u8]) -> Result<(RESPType, usize)> {
+    let mut deserializer = Deserializer::from_buf_reader(bytes);
+    let value = RESPType::deserialize(&mut deserializer)?;
+    Ok((value, deserializer.pos))
+}
+
+/// Length of the frame at the start of `buf`, or `None` if `buf` does not hold all of it yet.
+///
+/// The frame is scanned without being decoded or copied, which makes this a cheap test of whether to read
+/// more from a socket before decoding. Any bytes after the frame are ignored.
+///
+/// A malformed frame never completes, so it gives `None` as well. To tell it apart from one that is
+/// incomplete, decode `buf` with [from_bytes](from_bytes) or [iter_frames](iter_frames), or cap the bytes
+/// buffered while waiting.
+///
+/// # Examples
+/// ```
+/// use serde_resp::de;
+///
+/// assert_eq!(Some(12), de::check_complete(b"*2\r\n:1\r\n:2\r\n+OK"));
+/// assert_eq!(None, de::check_complete(b"*2\r\n:1\r\n"));
+/// assert_eq!(None, de::check_complete(b"$5\r\nhel"));
+/// assert_eq!(Some(11), de::check_complete(b"$5\r\nhello\r\n"));
+/// ```
+pub fn check_complete(buf: &[u8]) -> Option<usize> {
+    lazy::FrameSplitter::default().split(buf).ok().flatten()
+}
+
+/// Decode the frame at the start of `bytes` into a [RESPRef](crate::RESPRef), which borrows its strings from `bytes`
+/// instead of copying them. Any bytes after the frame are ignored.
+///
+/// Rust types can then be deserialized from the [RESPRef](crate::RESPRef), borrowing from `bytes` as well.
+///
+/// # Errors
+/// Fails like [from_str](from_str) on a malformed or incomplete frame, or if a bulk string is streamed in
+/// chunks, as it is not a single slice of `bytes`.
+///
+/// # Examples
+/// ```
+/// use serde::Deserialize;
+/// use serde_resp::{de, RESPRef};
+///
+/// let reply = de::from_slice(b"$5\r\nhello\r\n+OK\r\n").unwrap();
+/// assert_eq!(RESPRef::BulkString(Some(&b"hello"[..])), reply);
+/// assert_eq!("hello", <&str>::deserialize(reply).unwrap());
+///
+/// assert!(de::from_slice(b"*2\r\n:1\r\n").unwrap_err().is_eof());
+/// assert!(de::from_slice(b"$?\r\n;5\r\nhello\r\n;0\r\n").is_err());
+/// ```
+pub fn from_slice(bytes: &[u8]) -> Result<RESPRef<'_>> {
+    borrowed::parse(bytes).map(|(value, _)| value)
+}
+
+/// Deserialize one value from an async reader with tokio's `AsyncBufRead` trait. Requires feature `tokio`.
+///
+/// The lines and payloads of the frame are awaited as they arrive, then the complete frame is deserialized
+/// like with [from_buf_reader](from_buf_reader). The reader can be passed by value or by `&mut`.
+/// Passed by `&mut`, it is left right after the value.
+///
+/// Huge frames that are already buffered are read in pieces, giving the executor a chance to run other tasks
+/// in between, so they cannot hold up a worker. To give up on a frame, drop the future, or
+/// use [from_async_reader_cancellable](from_async_reader_cancellable).
+///
+/// # Errors
+/// Please refer to [Error](Error). The bytes read before the error are lost.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, bulk, de, int, RESP};
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let mut reader = "*2\r\n$3\r\nGET\r\n$1\r\na\r\n:1\r\n".as_bytes();
+/// let command: RESP = de::from_async_reader(&mut reader).await.unwrap();
+/// assert_eq!(array![bulk!(b"GET".to_vec()), bulk!(b"a".to_vec())], command);
+/// assert_eq!(int!(1), de::from_async_reader::<RESP, _>(&mut reader).await.unwrap());
+/// assert!(de::from_async_reader::<RESP, _>(reader).await.unwrap_err().is_eof());
+/// # });
+/// ```
+#[cfg(feature = "tokio")]
+pub async fn from_async_reader<T, R>(reader: R) -> Result<T>
+where
+    T: DeserializeOwned,
+    R: tokio::io::AsyncBufRead + Unpin,
+{
+    from_async_reader_with_limits(reader, Limits::default()).await
+}
+
+/// Like [from_async_reader](from_async_reader), failing once the value goes over `limits`.
+/// Requires feature `tokio`.
+///
+/// Lines, bulk strings, arrays and nesting over the limits fail as soon as they are read, without waiting
+/// for the rest of the frame, as a peer sending an endless line or announcing a huge value could otherwise
+/// make the frame buffered grow without bound.
+///
+/// # Errors
+/// Fails with [Error::LimitExceeded](Error::LimitExceeded) like a [Deserializer](Deserializer) with `limits`.
+/// Please refer to [Error](Error).
+///
+/// # Examples
+/// ```
+/// use serde_resp::de::{self, Limits};
+/// use serde_resp::{LimitKind, RESP};
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let limits = Limits { max_line_len: 16, max_bulk_len: 64, ..Limits::default() };
+/// let endless = "+".repeat(100_000);
+/// let err = de::from_async_reader_with_limits::<RESP, _>(endless.as_bytes(), limits).await.unwrap_err();
+/// assert_eq!(LimitKind::LineLength, err.limit().unwrap().0);
+/// let huge = "$1000000000\r\n".as_bytes();
+/// let err = de::from_async_reader_with_limits::<RESP, _>(huge, limits).await.unwrap_err();
+/// assert_eq!(Some((LimitKind::BulkLength, 1000000000)), err.limit());
+/// # });
+/// ```
+#[cfg(feature = "tokio")]
+pub async fn from_async_reader_with_limits<T, R>(mut reader: R, limits: Limits) -> Result<T>
+where
+    T: DeserializeOwned,
+    R: tokio::io::AsyncBufRead + Unpin,
+{
+    let mut frame = Vec::new();
+    async_io::read_frame(&mut async_io::Tokio(&mut reader), &mut frame, limits).await?;
+    T::deserialize(&mut Deserializer::from_buf_reader(&frame[..]).with_limits(limits))
+}
+
+/// Deserialize one value from an async reader with the `AsyncBufRead` trait of futures-io, such as those of
+/// async-std and smol. Requires feature `futures-io`.
+///
+/// Reads like [from_async_reader](from_async_reader) does with tokio's traits.
+///
+/// # Errors
+/// Please refer to [Error](Error). The bytes read before the error are lost.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, bulk, de, int, RESP};
+///
+/// futures::executor::block_on(async {
+///     let mut reader = "*1\r\n$4\r\nPING\r\n:1\r\n".as_bytes();
+///     let command: RESP = de::from_futures_reader(&mut reader).await.unwrap();
+///     assert_eq!(array![bulk!(b"PING".to_vec())], command);
+///     assert_eq!(int!(1), de::from_futures_reader::<RESP, _>(reader).await.unwrap());
+/// });
+/// ```
+#[cfg(feature = "futures-io")]
+pub async fn from_futures_reader<T, R>(reader: R) -> Result<T>
+where
+    T: DeserializeOwned,
+    R: futures_io::AsyncBufRead + Unpin,
+{
+    from_futures_reader_with_limits(reader, Limits::default()).await
+}
+
+/// Like [from_futures_reader](from_futures_reader), failing once the value goes over `limits`,
+/// as [from_async_reader_with_limits](from_async_reader_with_limits) does. Requires feature `futures-io`.
+///
+/// # Examples
+/// ```
+/// use serde_resp::de::{self, Limits};
+/// use serde_resp::{LimitKind, RESP};
+///
+/// futures::executor::block_on(async {
+///     let limits = Limits { max_depth: 2, ..Limits::default() };
+///     let deep = "*1\r\n*1\r\n*1\r\n:1\r\n".as_bytes();
+///     let err = de::from_futures_reader_with_limits::<RESP, _>(deep, limits).await.unwrap_err();
+///     assert_eq!(Some((LimitKind::Depth, 3)), err.limit());
+/// });
+/// ```
+#[cfg(feature = "futures-io")]
+pub async fn from_futures_reader_with_limits<T, R>(mut reader: R, limits: Limits) -> Result<T>
+where
+    T: DeserializeOwned,
+    R: futures_io::AsyncBufRead + Unpin,
+{
+    let mut frame = Vec::new();
+    async_io::read_frame(&mut async_io::Futures(&mut reader), &mut frame, limits).await?;
+    T::deserialize(&mut Deserializer::from_buf_reader(&frame[..]).with_limits(limits))
+}
+
+/// Like [from_async_reader](from_async_reader), giving up as soon as `cancelled` completes, such as
+/// the `cancelled()` future of a tokio-util `CancellationToken`. Requires feature `tokio`.
+///
+/// # Errors
+/// Once cancelled, fails with an [IO error](Error::Io) of kind `Interrupted`.
+/// Please refer to [Error](Error).
+///
+/// # Examples
+/// ```
+/// use serde_resp::{de, RESP};
+/// use std::io;
+/// use tokio::io::{AsyncWriteExt, BufReader};
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let (reader, mut writer) = tokio::io::duplex(64);
+/// // The rest of the frame never arrives
+/// writer.write_all(b"*2\r\n:1\r\n").await.unwrap();
+/// let reader = BufReader::new(reader);
+/// let err = de::from_async_reader_cancellable::<RESP, _, _>(reader, async {}).await.unwrap_err();
+/// assert!(err.is_io());
+/// assert_eq!(Some(io::ErrorKind::Interrupted), err.io_kind());
+/// # });
+/// ```
+#[cfg(feature = "tokio")]
+pub async fn from_async_reader_cancellable<T, R, C>(reader: R, cancelled: C) -> Result<T>
+where
+    T: DeserializeOwned,
+    R: tokio::io::AsyncBufRead + Unpin,
+    C: std::future::Future<Output = ()>,
+{
+    async_io::cancellable(from_async_reader(reader), cancelled).await
+}
+
+/// Deserialize every value from reader with `BufRead` trait until it is exhausted.
+///
+/// The reader can be passed by value or by `&mut`.
+/// This is useful for decoding pipelined replies. Reading stops at the first value that fails to
+/// deserialize, including one cut off by the end of input.
+///
+/// # Errors
+/// Failures are wrapped in [Error::Batch](Error::Batch), carrying the index of the failed value
+/// and the number of bytes consumed by the values before it.
+/// Please refer to [Error](Error)
+///
+/// # Examples
+/// ```
+/// use serde_resp::{de, int, simple, Error, RESP};
+///
+/// let mut reader = "+OK\r\n:1\r\n".as_bytes();
+/// let values: Vec<RESP> = de::read_many(&mut reader).unwrap();
+/// assert_eq!(vec![simple!("OK".to_owned()), int!(1)], values);
+///
+/// let mut reader = "+OK\r\n:1\r\n:x\r\n".as_bytes();
+/// match de::read_many::<RESP, _>(&mut reader).unwrap_err() {
+///     Error::Batch { index, consumed, error, .. } => {
+///         assert_eq!(2, index);
+///         assert_eq!(9, consumed);
+///         assert!(error.is_protocol());
+///     }
+///     e => panic!("unexpected error {}", e),
+/// }
+/// ```
+pub fn read_many<T, R>(reader: R) -> Result<Vec<T>>
+where
+    T: DeserializeOwned,
+    R: BufRead,
+{
+    read_many_until(reader).map_err(|(error, _)| error)
+}
+
+/// Deserialize every value in `s`, like [read_many](read_many), such as a captured pipeline.
+///
+/// # Errors
+/// Like [read_many](read_many). A value cut off by the end of `s` fails, and
+/// [is_incomplete](Error::is_incomplete) is true.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{de, int, simple, RESP};
+///
+/// let values: Vec<RESP> = de::from_str_multi("+OK\r\n:1\r\n").unwrap();
+/// assert_eq!(vec![simple!("OK".to_owned()), int!(1)], values);
+///
+/// let err = de::from_str_multi::<RESP>("+OK\r\n*2\r\n:1\r\n").unwrap_err();
+/// assert_eq!(Some(1), err.batch_index());
+/// assert!(err.is_incomplete());
+/// ```
+pub fn from_str_multi<T>(s: &str) -> Result<Vec<T>>
+where
+    T: DeserializeOwned,
+{
+    read_many(s.as_bytes())
+}
+
+/// Deserialize every value in `bytes`, like [from_str_multi](from_str_multi), such as an AOF fragment.
+///
+/// # Errors
+/// Like [from_str_multi](from_str_multi).
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, bulk, de, RESP};
+///
+/// let aof = b"*2\r\n$4\r\nINCR\r\n$1\r\na\r\n*2\r\n$4\r\nINCR\r\n$1\r\nb\r\n";
+/// let commands: Vec<RESP> = de::from_bytes_multi(aof).unwrap();
+/// assert_eq!(array![bulk!(b"INCR".to_vec()), bulk!(b"b".to_vec())], commands[1]);
+/// ```
+pub fn from_bytes_multi<T>(bytes: &[u8]) -> Result<Vec<T>>
+where
+    T: DeserializeOwned,
+{
+    read_many(bytes)
+}
+
+/// Replace the values in `values` with every value from `reader` until it is exhausted,
+/// like [read_many](read_many) into [RESPType](RESPType)s.
+///
+/// The allocations of `values` and of the bulk strings in its old values are reused, so a loop decoding
+/// batch after batch into the same `Vec` allocates little once it warmed up. To keep reusing buffers
+/// across more than one reader, use [Deserializer::decode_into](Deserializer::decode_into) and
+/// [Deserializer::recycle](Deserializer::recycle).
+///
+/// # Errors
+/// Like [read_many](read_many). The values read before the failure are left in `values`.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{bulk, de, int, RESP};
+///
+/// let mut values = Vec::new();
+/// for batch in &["$5\r\nhello\r\n:1\r\n", "$5\r\nworld\r\n"] {
+///     de::decode_into(batch.as_bytes(), &mut values).unwrap();
+/// }
+/// assert_eq!(vec![bulk!(b"world".to_vec())], values);
+///
+/// assert!(de::decode_into(":2\r\n:x\r\n".as_bytes(), &mut values).is_err());
+/// assert_eq!(vec![int!(2)], values);
+/// ```
+pub fn decode_into<R: BufRead>(reader: R, values: &mut Vec<RESPType>) -> Result<()> {
+    Deserializer::from_buf_reader(reader).decode_into(values)
+}
+
+/// Split `buf` into its complete frames, as subslices, without decoding them.
+///
+/// Each frame is checked like a [LazyFrame](crate::LazyFrame) is, which is much cheaper than decoding it.
+/// This is useful for sharding or indexing captures and AOF files before decoding the frames needed.
+///
+/// # Errors
+/// Errors are wrapped in [Error::Batch](Error::Batch) like those of [read_many](read_many), and end the iteration.
+/// A frame cut off by the end of `buf` gives an error for which [is_incomplete](Error::is_incomplete) is true,
+/// and whose bytes are left in [remainder](Frames::remainder), to complete with more input.
+///
+/// # Examples
+/// ```
+/// use serde_resp::de;
+///
+/// let buf = b"+OK\r\n*2\r\n:1\r\n:2\r\n$5\r\nhel";
+/// let mut frames = de::iter_frames(buf);
+/// assert_eq!(b"+OK\r\n", frames.next().unwrap().unwrap());
+/// assert_eq!(b"*2\r\n:1\r\n:2\r\n", frames.next().unwrap().unwrap());
+/// assert!(frames.next().unwrap().unwrap_err().is_incomplete());
+/// assert!(frames.next().is_none());
+/// assert_eq!(b"$5\r\nhel", frames.remainder());
+///
+/// let err = de::iter_frames(b":1\r\n?\r\n").last().unwrap().unwrap_err();
+/// assert!(err.is_protocol());
+/// ```
+pub fn iter_frames(buf: &[u8]) -> Frames<'_> {
+    Frames {
+        rest: buf,
+        consumed: 0,
+        index: 0,
+        scratch: Vec::new(),
+        done: false,
+    }
+}
+
+/// Iterator over the complete frames of a buffer, returned by [iter_frames](iter_frames).
+#[derive(Clone, Debug)]
+pub struct Frames<'a> {
+    rest: &'a [u8],
+    consumed: usize,
+    index: usize,
+    // Frames are copied here while they are checked.
+    scratch: Vec<u8>,
+    done: bool,
+}
+
+impl<'a> Frames<'a> {
+    /// The bytes after the frames returned so far.
+    pub fn remainder(&self) -> &'a [u8] {
+        self.rest
+    }
+
+    /// Number of bytes in the frames returned so far.
+    pub fn consumed(&self) -> usize {
+        self.consumed
+    }
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = Result<&'a [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.rest.is_empty() {
+            return None;
+        }
+        let mut reader = self.rest;
+        self.scratch.clear();
+        match lazy::read_frame(&mut reader, &mut self.scratch) {
+            Ok(_) => {
+                let (frame, rest) = self.rest.split_at(self.rest.len() - reader.len());
+                self.rest = rest;
+                self.consumed += frame.len();
+                self.index += 1;
+                Some(Ok(frame))
+            }
+            Err(error) => {
+                self.done = true;
+                Some(Err(Error::Batch {
+                    index: self.index,
+                    consumed: self.consumed,
+                    error: Box::new(error),
+                }))
+            }
+        }
+    }
+}
+
+impl<'a> FusedIterator for Frames<'a> {}
+
+/// Iterator over the values of a reader, returned by [Deserializer::iter](Deserializer::iter).
+pub struct Values<'a, T, R: BufRead, M: Metrics = ()> {
+    deserializer: &'a mut Deserializer<R, M>,
+    // Position of the deserializer when the iterator was created.
+    start: usize,
+    index: usize,
+    done: bool,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<'a, T, R: BufRead, M: Metrics> Values<'a, T, R, M> {
+    /// Number of bytes read since the iterator was created.
+    pub fn consumed(&self) -> usize {
+        self.deserializer.pos - self.start
+    }
+}
+
+impl<'a, T, R, M> Iterator for Values<'a, T, R, M>
+where
+    T: DeserializeOwned,
+    R: BufRead,
+    M: Metrics,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let consumed = self.consumed();
+        let value = match self.deserializer.reader.fill_buf() {
+            Ok([]) => {
+                self.done = true;
+                return None;
+            }
+            Ok(_) => T::deserialize(&mut *self.deserializer),
+            Err(e) => Err(Error::from(e)),
+        };
+        match value {
+            Ok(value) => {
+                self.index += 1;
+                Some(Ok(value))
+            }
+            Err(error) => {
+                self.done = true;
+                Some(Err(Error::Batch {
+                    index: self.index,
+                    consumed,
+                    error: Box::new(error),
+                }))
+            }
+        }
+    }
+}
+
+impl<'a, T, R, M> FusedIterator for Values<'a, T, R, M>
+where
+    T: DeserializeOwned,
+    R: BufRead,
+    M: Metrics,
+{
+}
+
+/// Deserialize every value in the file at `path`, like [read_many](read_many), reading it through a memory map.
+///
+/// This is meant for offline analysis of large AOF files and captures: the file is not read into memory up front,
+/// and pages are loaded as decoding reaches them. Requires feature `mmap`.
+///
+/// Frames are decoded like with [from_slice](from_slice), so their strings are borrowed from the map
+/// rather than copied, and `T` copies only what it keeps. `T` is deserialized from each frame the same way
+/// as from a [RESP](crate::RESP) value, see [value](crate::value).
+///
+/// # Safety
+/// The file must not be truncated or modified while it is decoded, by this or any other process.
+/// The map would change under the decoder, which is undefined behavior, and truncating the file
+/// may crash the process, as memory maps cannot report it as an error.
+///
+/// # Errors
+/// Fails if the file cannot be opened or mapped, and otherwise like [read_many](read_many).
+/// Like [from_slice](from_slice), bulk strings streamed in chunks are not supported.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, bulk, de, RESP};
+///
+/// let path = std::env::temp_dir().join("serde_resp_from_file.aof");
+/// std::fs::write(&path, "*2\r\n$6\r\nSELECT\r\n$1\r\n0\r\n*1\r\n$5\r\nMULTI\r\n").unwrap();
+/// // SAFETY: nothing else writes the file while it is decoded.
+/// let commands: Vec<RESP> = unsafe { de::from_file(&path) }.unwrap();
+/// assert_eq!(2, commands.len());
+/// assert_eq!(array![bulk!(b"MULTI".to_vec())], commands[1]);
+///
+/// let commands: Vec<Vec<String>> = unsafe { de::from_file(&path) }.unwrap();
+/// assert_eq!(vec!["SELECT", "0"], commands[0]);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[cfg(feature = "mmap")]
+pub unsafe fn from_file<T, P>(path: P) -> Result<Vec<T>>
+where
+    T: DeserializeOwned,
+    P: AsRef<std::path::Path>,
+{
+    let file = std::fs::File::open(path)?;
+    // SAFETY: the map is only read, and only while decoding. The caller ensures the file is not modified meanwhile.
+    let map = unsafe { memmap2::Mmap::map(&file)? };
+    let mut values = Vec::new();
+    let mut rest = &map[..];
+    while !rest.is_empty() {
+        let consumed = map.len() - rest.len();
+        let value = match borrowed::parse(rest) {
+            Ok((frame, len)) => {
+                rest = &rest[len..];
+                T::deserialize(frame)
+            }
+            Err(error) => Err(error),
+        };
+        match value {
+            Ok(value) => values.push(value),
+            Err(error) => {
+                return Err(Error::Batch {
+                    index: values.len(),
+                    consumed,
+                    error: Box::new(error),
+                })
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// Deserialize every value in `bytes` in parallel, with the same results and errors as [read_many](read_many).
+///
+/// The frames are first split apart by a scan that checks their structure without decoding them,
+/// then decoded on the [rayon](https://docs.rs/rayon) thread pool. This pays off for large
+/// buffers, such as captures and AOF files, on machines with several cores. Requires feature `rayon`.
+///
+/// # Errors
+/// Failures are wrapped in [Error::Batch](Error::Batch), carrying the index of the first failed value
+/// and the number of bytes before it.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{de, int, simple, RESP};
+///
+/// let values: Vec<RESP> = de::decode_batch_parallel(b"+OK\r\n:1\r\n").unwrap();
+/// assert_eq!(vec![simple!("OK".to_owned()), int!(1)], values);
+///
+/// let err = de::decode_batch_parallel::<RESP>(b"+OK\r\n:1\r\n:x\r\n:2\r\n").unwrap_err();
+/// assert_eq!(Some(2), err.batch_index());
+/// assert_eq!(Some(9), err.batch_consumed());
+/// ```
+#[cfg(feature = "rayon")]
+pub fn decode_batch_parallel<T>(bytes: &[u8]) -> Result<Vec<T>>
+where
+    T: DeserializeOwned + Send,
+{
+    use rayon::prelude::*;
+
+    // Split into frames, stopping at the first one that fails to scan.
+    let mut frames = Vec::new();
+    let mut failed = None;
+    let mut rest = bytes;
+    let mut scratch = Vec::new();
+    while !rest.is_empty() {
+        let start = bytes.len() - rest.len();
+        scratch.clear();
+        match crate::lazy::read_frame(&mut rest, &mut scratch) {
+            Ok(_) => frames.push(start..bytes.len() - rest.len()),
+            Err(error) => {
+                failed = Some((frames.len(), start, error));
+                break;
+            }
+        }
+    }
+    let decoded: Vec<Result<T>> = frames
+        .par_iter()
+        .map(|frame| from_buf_reader(&bytes[frame.clone()]))
+        .collect();
+    let mut values = Vec::with_capacity(decoded.len());
+    for (index, value) in decoded.into_iter().enumerate() {
+        match value {
+            Ok(value) => values.push(value),
+            Err(error) => {
+                failed = Some((index, frames[index].start, error));
+                break;
+            }
+        }
+    }
+    match failed {
+        None => Ok(values),
+        Some((index, consumed, error)) => Err(Error::Batch {
+            index,
+            consumed,
+            error: Box::new(error),
+        }),
+    }
+}
+
+// Same as read_many, but on failure also return the position where decoding stopped.
+pub(crate) fn read_many_until<T, R>(reader: R) -> std::result::Result<Vec<T>, (Error, usize)>
+where
+    T: DeserializeOwned,
+    R: BufRead,
+{
+    read_all(&mut Deserializer::from_buf_reader(reader))
+}
+
+// Deserialize values until the reader is exhausted. Positions count from where the deserializer was.
+fn read_all<T, R, M>(
+    deserializer: &mut Deserializer<R, M>,
+) -> std::result::Result<Vec<T>, (Error, usize)>
+where
+    T: DeserializeOwned,
+    R: BufRead,
+    M: Metrics,
+{
+    let mut values = Vec::new();
+    read_all_into(deserializer, &mut values)?;
+    Ok(values)
+}
+
+// Like read_all, appending the values to `values`. Indices in errors count from its initial length.
+fn read_all_into<T, R, M>(
+    deserializer: &mut Deserializer<R, M>,
+    values: &mut Vec<T>,
+) -> std::result::Result<(), (Error, usize)>
+where
+    T: DeserializeOwned,
+    R: BufRead,
+    M: Metrics,
+{
+    let mut iter = deserializer.iter();
+    while let Some(value) = iter.next() {
+        match value {
+            Ok(value) => values.push(value),
+            Err(error) => return Err((error, iter.consumed())),
+        }
+    }
+    Ok(())
+}
+
+impl<R: BufRead, M: Metrics> Deserializer<R, M> {
+    // read until LF, giving up once the line is longer than allowed, and strip the line ending.
+    fn read_line(&mut self) -> Result<String> {
+        let mut buffer = Vec::new();
+        self.read_line_into(&mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    // read_line into `buffer`, replacing its content, without checking UTF-8.
+    fn read_line_into(&mut self, buffer: &mut Vec<u8>) -> Result<()> {
+        buffer.clear();
+        let max = self.limits.max_line_len;
+        let read = (&mut self.reader)
+            .take((max as u64).saturating_add(1))
+            .read_until(b'\n', buffer);
+        self.pos += buffer.len();
+        self.metrics.bytes_read(buffer.len());
+        self.metrics.data_read(buffer);
+        read?;
+        check_limit(LimitKind::LineLength, max, buffer.len())?;
+        self.charge(buffer.len())?;
+        if buffer.pop() != Some(b'\n') {
+            return Err(Error::Eof);
+        }
+        if buffer.last() == Some(&b'\r') {
+            buffer.pop();
+        }
+        Ok(())
+    }
+
+    // read the rest of a bulk string after its type byte into `buffer`, replacing its content.
+    // Streamed bulk strings, `$?` followed by chunks, are read whole. False for a null bulk string.
+    fn read_bulk_string_into(&mut self, buffer: &mut Vec<u8>) -> Result<bool> {
+        match self.read_len()? {
+            None => self.read_chunks_into(buffer)?,
+            Some(len) if len < 0 => return Ok(false),
+            Some(len) => self.read_bulk_into(len as usize, buffer)?,
+        }
+        Ok(true)
+    }
+
+    // read the chunks of a streamed bulk string, `;4\r\nHell\r\n;1\r\no\r\n;0\r\n`, into `buffer`, replacing its content.
+    fn read_chunks_into(&mut self, buffer: &mut Vec<u8>) -> Result<()> {
+        buffer.clear();
+        loop {
+            let mut type_byte = [0u8; 1];
+            self.charge(1)?;
+            self.read_exact(&mut type_byte)?;
+            if type_byte[0] != b';' {
+                return Err(Error::Syntax);
+            }
+            match self.read_isize()? {
+                0 => return Ok(()),
+                len if len < 0 => return Err(Error::Syntax),
+                len => self.append_bulk(len as usize, buffer)?,
+            }
+        }
+    }
+
+    // read the payload of a bulk string of `len` bytes and its CRLF into `buffer`, replacing its content.
+    fn read_bulk_into(&mut self, len: usize, buffer: &mut Vec<u8>) -> Result<()> {
+        buffer.clear();
+        self.append_bulk(len, buffer)
+    }
+
+    // read a payload of `len` bytes and its CRLF, appending the payload to `buffer`.
+    // The limit on bulk strings applies to the whole buffer, so to all chunks of a streamed one.
+    fn append_bulk(&mut self, len: usize, buffer: &mut Vec<u8>) -> Result<()> {
+        let start = buffer.len();
+        check_limit(
+            LimitKind::BulkLength,
+            self.limits.max_bulk_len,
+            start.saturating_add(len),
+        )?;
+        let total = len + 2; // also read CRLF
+        self.charge(total)?;
+        buffer.reserve(total.min(self.bulk_chunk_size));
+        let end = start + total;
+        while buffer.len() < end {
+            let at = buffer.len();
+            buffer.resize(at + (end - at).min(self.bulk_chunk_size), 0);
+            self.read_exact(&mut buffer[at..])?;
+        }
+        if buffer[start + len..] != *b"\r\n" {
+            return Err(Error::Syntax); // Not CRLF
+        }
+        buffer.truncate(start + len);
+        Ok(())
+    }
+
+    // read the rest of an array of numbers, after its type byte.
+    fn read_number_array<T: FromStr>(&mut self) -> Result<Option<Vec<T>>> {
+        let len = match self.read_len()? {
+            Some(x) if x < 0 => return Ok(None),
+            Some(x) => Some(x as usize),
+            None => None, // streamed
+        };
+        if let Some(len) = len {
+            check_limit(LimitKind::ArrayLength, self.limits.max_array_len, len)?;
+        }
+        check_limit(LimitKind::Depth, self.limits.max_depth, self.depth + 1)?;
+        // The length is untrusted, so do not allocate for all of it up front.
+        let mut values = Vec::with_capacity(len.unwrap_or(0).min(4096));
+        let mut text = Vec::new();
+        for index in 0.. {
+            match len {
+                Some(len) if index == len => break,
+                Some(_) => {}
+                None if self.end_of_stream()? => break,
+                None => check_limit(LimitKind::ArrayLength, self.limits.max_array_len, index + 1)?,
+            }
+            let mut type_byte = [0u8; 1];
+            self.charge(1)?;
+            self.read_exact(&mut type_byte)?;
+            match type_byte[0] {
+                b':' | b',' | b'(' => self.read_line_into(&mut text)?,
+                b'$' => {
+                    if !self.read_bulk_string_into(&mut text)? {
+                        return Err(not_a_number(index, "a null bulk string"));
+                    }
+                }
+                b'+' => return Err(not_a_number(index, "a simple string")),
+                b'-' => return Err(not_a_number(index, "an error")),
+                b'*' => return Err(not_a_number(index, "an array")),
+                b'#' => return Err(not_a_number(index, "a boolean")),
+                b'=' => return Err(not_a_number(index, "a verbatim string")),
+                b'_' => return Err(not_a_number(index, "a null")),
+                b'>' => return Err(not_a_number(index, "a push")),
+                b'|' => return Err(not_a_number(index, "an attribute")),
+                b'%' => return Err(not_a_number(index, "a map")),
+                b'~' => return Err(not_a_number(index, "a set")),
+                _ => return Err(Error::Syntax),
+            }
+            let number = str::from_utf8(&text)
+                .ok()
+                .and_then(|text| text.trim_end().parse().ok())
+                .ok_or_else(|| {
+                    not_a_number(index, &format!("{:?}", String::from_utf8_lossy(&text)))
+                })?;
+            values.push(number);
+        }
+        Ok(Some(values))
+    }
+
+    // fill the whole buffer, failing with Eof if the reader runs out first.
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            let available = match self.reader.fill_buf() {
+                Ok(available) => available,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if available.is_empty() {
+                return Err(Error::Eof);
+            }
+            let n = available.len().min(buf.len() - filled);
+            buf[filled..filled + n].copy_from_slice(&available[..n]);
+            self.reader.consume(n);
+            self.pos += n;
+            self.metrics.bytes_read(n);
+            self.metrics.data_read(&buf[filled..filled + n]);
+            filled += n;
+        }
+        Ok(())
+    }
+
+    // whether the next byte ends a streamed aggregate, reading the end `.\r\n` if so.
+    fn end_of_stream(&mut self) -> Result<bool> {
+        let next = loop {
+            match self.reader.fill_buf() {
+                Ok(available) => break available.first().copied(),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        };
+        match next {
+            None => Err(Error::Eof),
+            Some(b'.') => {
+                let mut end = [0u8; 1];
+                self.charge(1)?;
+                self.read_exact(&mut end)?;
+                if !self.read_line()?.is_empty() {
+                    return Err(Error::Syntax);
+                }
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+        }
+    }
+
+    // read the rest of an array that must have exactly `len` elements.
+    fn read_array_of<'de, V>(&mut self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let x = self.read_isize()?;
+        if x < 0 {
+            return Err(de::Error::invalid_value(
+                de::Unexpected::Other("null array"),
+                &visitor,
+            ));
+        }
+        if x as usize != len {
+            return Err(de::Error::invalid_length(x as usize, &visitor));
+        }
+        check_limit(LimitKind::Depth, self.limits.max_depth, self.depth + 1)?;
+        self.depth += 1;
+        let value = visitor.visit_seq(RESPArray::new(self, Some(len)));
+        self.depth -= 1;
+        value
+    }
+
+    // charge `n` bytes to the budget, if any.
+    fn charge(&self, n: usize) -> Result<()> {
+        match &self.budget {
+            Some(budget) => budget.charge(n),
+            None => Ok(()),
+        }
+    }
+
+    // report a frame read at the top level, or the error that stopped it, recording where it did.
+    fn frame_read<T>(&mut self, type_byte: u8, start: usize, value: Result<T>) -> Result<T> {
+        match value {
+            Ok(_) => {
+                self.last_frame_len = Some(self.pos - start);
+                if let Some(kind) = RESPKind::from_type_byte(type_byte) {
+                    self.metrics.frame_read(kind);
+                }
+                value
+            }
+            Err(Error::Eof) if self.pos == start => {
+                self.metrics.error(ErrorKind::Eof);
+                Err(Error::CleanEof.at(self.pos))
+            }
+            Err(e) => {
+                self.metrics.error(e.kind());
+                Err(e.at(self.pos))
+            }
+        }
+    }
+
+    // give the `len` elements of an array, a push, an attribute, a map or a set, after its header, to `visitor`.
+    // Streamed aggregates, with no `len`, end with `.` instead.
+    fn visit_elements<'de, V>(&mut self, len: Option<usize>, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if let Some(len) = len {
+            check_limit(LimitKind::ArrayLength, self.limits.max_array_len, len)?;
+        }
+        check_limit(LimitKind::Depth, self.limits.max_depth, self.depth + 1)?;
+        self.depth += 1;
+        let value = visitor.visit_seq(RESPArray::new(self, len));
+        self.depth -= 1;
+        value
+    }
+
+    // read the length in the header of a bulk string or an aggregate, None for `?`, the length of streamed ones.
+    fn read_len(&mut self) -> Result<Option<isize>> {
+        let buffer = self.read_line()?;
+        if buffer == "?" {
+            return Ok(None);
+        }
+        self.parse_isize(&buffer).map(Some)
+    }
+
+    // read a line and parse to isize.
     fn read_isize(&mut self) -> Result<isize> {
-        let mut buffer = String::new();
-        self.reader.read_line(&mut buffer)?;
+        let buffer = self.read_line()?;
+        self.parse_isize(&buffer)
+    }
+
+    fn parse_isize(&self, buffer: &str) -> Result<isize> {
         let trimmed = buffer.trim_end();
         match trimmed.parse::<isize>() {
             Ok(x) => Ok(x),
@@ -75,7 +1705,7 @@ impl<'de, R: BufRead> Deserializer<'de, R> {
     }
 }
 
-impl<'de, 'a, R: BufRead> de::Deserializer<'de> for &'a mut Deserializer<'de, R> {
+impl<'de, R: BufRead, M: Metrics> de::Deserializer<'de> for &mut Deserializer<R, M> {
     type Error = Error;
 
     // You see, this is a bit hacky...
@@ -83,23 +1713,45 @@ impl<'de, 'a, R: BufRead> de::Deserializer<'de> for &'a mut Deserializer<'de, R>
     where
         V: Visitor<'de>,
     {
+        let top_level = self.depth == 0;
+        let start = self.pos;
         let mut buf = [0u8; 1];
-        self.reader.read_exact(&mut buf)?;
-        match buf[0] {
-            b'+' => self.deserialize_str(visitor),      // SimpleString
-            b'-' => self.deserialize_string(visitor),   // Error
-            b':' => self.deserialize_i64(visitor),      // Integer
-            b'$' => self.deserialize_byte_buf(visitor), // BulkString
-            b'*' => self.deserialize_seq(visitor),      // Array
-            _ => return Err(Error::Syntax),
+        let value = match self.charge(1).and_then(|()| self.read_exact(&mut buf)) {
+            Err(e) => Err(e),
+            Ok(()) => match buf[0] {
+                b'+' => (&mut *self).deserialize_str(visitor), // SimpleString
+                b'-' => (&mut *self).deserialize_string(visitor), // Error
+                b':' => (&mut *self).deserialize_i64(visitor), // Integer
+                b'$' => (&mut *self).deserialize_byte_buf(visitor), // BulkString
+                b'*' => (&mut *self).deserialize_seq(visitor), // Array
+                b',' => (&mut *self).deserialize_f64(visitor), // Double
+                b'#' => (&mut *self).deserialize_bool(visitor), // Boolean
+                b'(' => visitor.visit_enum(Tagged::new(self, RESPKind::BigNumber)),
+                b'=' => visitor.visit_enum(Tagged::new(self, RESPKind::Verbatim)),
+                b'_' => visitor.visit_enum(Tagged::new(self, RESPKind::Null)),
+                b'>' => visitor.visit_enum(Tagged::new(self, RESPKind::Push)),
+                b'|' => visitor.visit_enum(Tagged::new(self, RESPKind::Attribute)),
+                b'%' => visitor.visit_enum(Tagged::new(self, RESPKind::Map)),
+                b'~' => visitor.visit_enum(Tagged::new(self, RESPKind::Set)),
+                byte => Err(Error::unexpected(byte, self.pos)),
+            },
+        };
+        if top_level {
+            return self.frame_read(buf[0], start, value);
         }
+        value
     }
 
-    fn deserialize_bool<V>(self, _visitor: V) -> Result<V::Value>
+    // Boolean
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        match self.read_line()?.as_str() {
+            "t" => visitor.visit_bool(true),
+            "f" => visitor.visit_bool(false),
+            _ => Err(Error::Syntax),
+        }
     }
 
     fn deserialize_i8<V>(self, _visitor: V) -> Result<V::Value>
@@ -127,8 +1779,7 @@ impl<'de, 'a, R: BufRead> de::Deserializer<'de> for &'a mut Deserializer<'de, R>
     where
         V: Visitor<'de>,
     {
-        let mut buffer = String::new();
-        self.reader.read_line(&mut buffer)?;
+        let buffer = self.read_line()?;
         match buffer.trim_end().parse::<i64>() {
             Ok(x) => visitor.visit_i64(x),
             Err(_) => Err(Error::Syntax),
@@ -170,11 +1821,16 @@ impl<'de, 'a, R: BufRead> de::Deserializer<'de> for &'a mut Deserializer<'de, R>
         unimplemented!()
     }
 
-    fn deserialize_f64<V>(self, _visitor: V) -> Result<V::Value>
+    // Double
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        let buffer = self.read_line()?;
+        match buffer.trim_end().parse::<f64>() {
+            Ok(x) => visitor.visit_f64(x),
+            Err(_) => Err(Error::Syntax),
+        }
     }
 
     fn deserialize_char<V>(self, _visitor: V) -> Result<V::Value>
@@ -189,9 +1845,8 @@ impl<'de, 'a, R: BufRead> de::Deserializer<'de> for &'a mut Deserializer<'de, R>
     where
         V: Visitor<'de>,
     {
-        let mut buffer = String::new();
-        self.reader.read_line(&mut buffer)?;
-        visitor.visit_str(buffer.trim_end())
+        let buffer = self.read_line()?;
+        visitor.visit_str(&buffer)
     }
 
     // Error
@@ -199,9 +1854,8 @@ impl<'de, 'a, R: BufRead> de::Deserializer<'de> for &'a mut Deserializer<'de, R>
     where
         V: Visitor<'de>,
     {
-        let mut buffer = String::new();
-        self.reader.read_line(&mut buffer)?;
-        visitor.visit_string(buffer.trim_end().to_string())
+        let buffer = self.read_line()?;
+        visitor.visit_string(buffer)
     }
 
     fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value>
@@ -216,15 +1870,11 @@ impl<'de, 'a, R: BufRead> de::Deserializer<'de> for &'a mut Deserializer<'de, R>
     where
         V: Visitor<'de>,
     {
-        let x = self.read_isize()?;
-        if x < 0 {
+        let mut buffer = self.spare.pop().unwrap_or_default();
+        if !self.read_bulk_string_into(&mut buffer)? {
+            self.recycle_buffer(buffer);
             return visitor.visit_none();
         }
-        let mut buffer = vec![0u8; (x + 2) as usize]; // also read CRLF
-        self.reader.read_exact(&mut buffer)?;
-        if buffer.split_off(x as usize) != b"\r\n" {
-            return Err(Error::Syntax); // Not CRLF
-        }
         visitor.visit_byte_buf(buffer)
     }
 
@@ -259,22 +1909,35 @@ impl<'de, 'a, R: BufRead> de::Deserializer<'de> for &'a mut Deserializer<'de, R>
     // Deserialization of compound types like sequences and maps happens by
     // passing the visitor an "Access" object that gives it the ability to
     // iterate through the data contained in the sequence.
-    fn deserialize_seq<V>(mut self, visitor: V) -> Result<V::Value>
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let x = self.read_isize()?;
-        if x < 0 {
-            return visitor.visit_unit();
+        match self.read_len()? {
+            Some(x) if x < 0 => visitor.visit_unit(),
+            Some(x) => self.visit_elements(Some(x as usize), visitor),
+            None => self.visit_elements(None, visitor),
         }
-        visitor.visit_seq(RESPArray::new(&mut self, x as usize))
     }
 
-    fn deserialize_tuple<V>(self, _len: usize, _visitor: V) -> Result<V::Value>
+    // Fixed-size arrays such as `[RESPType; N]`, which need an array of exactly `len` elements.
+    // Unlike the methods above, the type byte has not been read yet.
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        let top_level = self.depth == 0;
+        let start = self.pos;
+        let mut buf = [0u8; 1];
+        let value = match self.charge(1).and_then(|()| self.read_exact(&mut buf)) {
+            Err(e) => Err(e),
+            Ok(()) if buf[0] != b'*' => Err(Error::unexpected(buf[0], self.pos)),
+            Ok(()) => self.read_array_of(len, visitor),
+        };
+        if top_level {
+            return self.frame_read(buf[0], start, value);
+        }
+        value
     }
 
     fn deserialize_tuple_struct<V>(
@@ -335,41 +1998,261 @@ impl<'de, 'a, R: BufRead> de::Deserializer<'de> for &'a mut Deserializer<'de, R>
     }
 }
 
-struct RESPArray<'a, 'de: 'a, R: BufRead> {
-    de: &'a mut Deserializer<'de, R>,
-    remain_len: usize,
+struct RESPArray<'a, R: BufRead, M: Metrics> {
+    de: &'a mut Deserializer<R, M>,
+    // None for a streamed aggregate, which ends with `.` instead.
+    remain_len: Option<usize>,
+    // Elements read from a streamed aggregate, to limit them as they come.
+    streamed_len: usize,
 }
 
-impl<'a, 'de, R: BufRead> RESPArray<'a, 'de, R> {
-    fn new(de: &'a mut Deserializer<'de, R>, len: usize) -> Self {
+impl<'a, R: BufRead, M: Metrics> RESPArray<'a, R, M> {
+    fn new(de: &'a mut Deserializer<R, M>, len: Option<usize>) -> Self {
         RESPArray {
             de,
             remain_len: len,
+            streamed_len: 0,
         }
     }
 }
 
 // `SeqAccess` is provided to the `Visitor` to give it the ability to iterate
 // through elements of the sequence.
-impl<'de, 'a, R: BufRead> SeqAccess<'de> for RESPArray<'a, 'de, R> {
+impl<'de, 'a, R: BufRead, M: Metrics> SeqAccess<'de> for RESPArray<'a, R, M> {
     type Error = Error;
 
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
     where
         T: DeserializeSeed<'de>,
     {
-        if self.remain_len == 0 {
-            return Ok(None);
+        match &mut self.remain_len {
+            Some(0) => return Ok(None),
+            Some(len) => *len -= 1,
+            None if self.de.end_of_stream()? => return Ok(None),
+            None => {
+                self.streamed_len += 1;
+                let max = self.de.limits.max_array_len;
+                check_limit(LimitKind::ArrayLength, max, self.streamed_len)?;
+            }
         }
-        self.remain_len -= 1;
         seed.deserialize(&mut *self.de).map(Some)
     }
 
     fn size_hint(&self) -> Option<usize> {
-        Some(self.remain_len)
+        self.remain_len
+    }
+}
+
+// Names of the RESP3 types that visitors receive as enum variants, see `Tagged`.
+const TAGGED_TOKENS: &[&str] = &[
+    BIG_NUMBER_TOKEN,
+    VERBATIM_TOKEN,
+    NULL_TOKEN,
+    PUSH_TOKEN,
+    ATTRIBUTE_TOKEN,
+    MAP_TOKEN,
+    SET_TOKEN,
+];
+
+// RESP3 types with no counterpart in the serde data model are given to visitors as enum variants,
+// named by the same tokens that mark them when serializing. The type byte is already read.
+struct Tagged<'a, R: BufRead, M: Metrics> {
+    de: &'a mut Deserializer<R, M>,
+    kind: RESPKind,
+}
+
+impl<'a, R: BufRead, M: Metrics> Tagged<'a, R, M> {
+    fn new(de: &'a mut Deserializer<R, M>, kind: RESPKind) -> Self {
+        Tagged { de, kind }
+    }
+
+    fn token(&self) -> &'static str {
+        match self.kind {
+            RESPKind::BigNumber => BIG_NUMBER_TOKEN,
+            RESPKind::Verbatim => VERBATIM_TOKEN,
+            RESPKind::Null => NULL_TOKEN,
+            RESPKind::Push => PUSH_TOKEN,
+            RESPKind::Attribute => ATTRIBUTE_TOKEN,
+            RESPKind::Map => MAP_TOKEN,
+            RESPKind::Set => SET_TOKEN,
+            kind => unreachable!("{:?} is not tagged", kind),
+        }
+    }
+}
+
+impl<'de, 'a, R: BufRead, M: Metrics> EnumAccess<'de> for Tagged<'a, R, M> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let token = de::value::StrDeserializer::<Error>::new(self.token());
+        Ok((seed.deserialize(token)?, self))
     }
 }
 
+impl<'de, 'a, R: BufRead, M: Metrics> VariantAccess<'de> for Tagged<'a, R, M> {
+    type Error = Error;
+
+    // The content, read by `deserialize_any` below, as `()`: only nulls have none.
+    fn unit_variant(self) -> Result<()> {
+        de::Deserialize::deserialize(self)
+    }
+
+    // The content, read by `deserialize_any` below.
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::invalid_type(
+            de::Unexpected::NewtypeVariant,
+            &visitor,
+        ))
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::invalid_type(
+            de::Unexpected::NewtypeVariant,
+            &visitor,
+        ))
+    }
+}
+
+// Deserializer of the content of a tagged type.
+impl<'de, 'a, R: BufRead, M: Metrics> de::Deserializer<'de> for Tagged<'a, R, M> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.kind {
+            RESPKind::BigNumber => {
+                let digits = self.de.read_line()?;
+                if !is_big_number(&digits) {
+                    return Err(Error::Syntax);
+                }
+                visitor.visit_string(digits)
+            }
+            // The whole payload, `format:data`, as bytes.
+            RESPKind::Verbatim => {
+                let len = self.de.read_isize()?;
+                if len < 0 {
+                    return Err(Error::Syntax);
+                }
+                let mut buffer = self.de.spare.pop().unwrap_or_default();
+                self.de.read_bulk_into(len as usize, &mut buffer)?;
+                if !is_verbatim(&buffer) {
+                    return Err(Error::Syntax);
+                }
+                visitor.visit_byte_buf(buffer)
+            }
+            RESPKind::Null => {
+                if !self.de.read_line()?.is_empty() {
+                    return Err(Error::Syntax);
+                }
+                visitor.visit_unit()
+            }
+            RESPKind::Push => match self.de.read_isize()? {
+                len if len < 0 => Err(Error::Syntax),
+                len => self.de.visit_elements(Some(len as usize), visitor),
+            },
+            // The keys and values, followed by the value the attribute is about, as a sequence.
+            RESPKind::Attribute => {
+                let len = match self.de.read_isize()? {
+                    pairs if pairs < 0 => return Err(Error::Syntax),
+                    pairs => (pairs as usize)
+                        .checked_mul(2)
+                        .and_then(|len| len.checked_add(1)),
+                };
+                let len = len.ok_or(Error::Syntax)?;
+                self.de.visit_elements(Some(len), visitor)
+            }
+            // The keys and values, as a sequence.
+            RESPKind::Map => match self.de.read_len()? {
+                Some(pairs) if pairs < 0 => Err(Error::Syntax),
+                Some(pairs) => {
+                    let len = (pairs as usize).checked_mul(2).ok_or(Error::Syntax)?;
+                    self.de.visit_elements(Some(len), visitor)
+                }
+                None => self.de.visit_elements(None, visitor),
+            },
+            RESPKind::Set => match self.de.read_len()? {
+                Some(len) if len < 0 => Err(Error::Syntax),
+                len => self.de.visit_elements(len.map(|len| len as usize), visitor),
+            },
+            kind => unreachable!("{:?} is not tagged", kind),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+// Whether `text` is an integer: an optional sign followed by digits.
+pub(crate) fn is_big_number(text: &str) -> bool {
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+// Whether `payload` is the payload of a verbatim string: a format of three bytes of text, a `:` and the data.
+pub(crate) fn is_verbatim(payload: &[u8]) -> bool {
+    match payload.get(..4) {
+        Some([format @ .., b':']) => std::str::from_utf8(format).is_ok(),
+        _ => false,
+    }
+}
+
+// Split the payload of a verbatim string, checked by `is_verbatim`, into its format and its data.
+pub(crate) fn split_verbatim(mut payload: Vec<u8>) -> RESPType {
+    let data = payload.split_off(4);
+    payload.truncate(3);
+    RESPType::Verbatim {
+        format: String::from_utf8(payload).expect("checked by is_verbatim"),
+        data,
+    }
+}
+
+// Build an attribute from its keys and values followed by the value it is about, as written on the wire.
+// None if there is no value or a key has no value.
+pub(crate) fn join_attribute(mut elements: Vec<RESPType>) -> Option<RESPType> {
+    let value = elements.pop()?;
+    let mut attributes = Vec::with_capacity(elements.len() / 2);
+    let mut elements = elements.into_iter();
+    while let Some(k) = elements.next() {
+        attributes.push((k, elements.next()?));
+    }
+    Some(RESPType::Attribute {
+        attributes,
+        value: Box::new(value),
+    })
+}
+
+// Build a map from its keys and values, as written on the wire. None if a key has no value.
+pub(crate) fn join_map(elements: Vec<RESPType>) -> Option<RESPType> {
+    let mut pairs = Vec::with_capacity(elements.len() / 2);
+    let mut elements = elements.into_iter();
+    while let Some(k) = elements.next() {
+        pairs.push((k, elements.next()?));
+    }
+    Some(RESPType::Map(pairs))
+}
+
 struct RESPTypeVisitor;
 
 impl<'de> Visitor<'de> for RESPTypeVisitor {
@@ -386,6 +2269,20 @@ impl<'de> Visitor<'de> for RESPTypeVisitor {
         Ok(RESPType::Integer(v))
     }
 
+    fn visit_f64<E>(self, v: f64) -> std::result::Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(RESPType::Double(v))
+    }
+
+    fn visit_bool<E>(self, v: bool) -> std::result::Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(RESPType::Boolean(v))
+    }
+
     // SimpleString
     fn visit_str<E>(self, v: &str) -> std::result::Result<Self::Value, E>
     where
@@ -402,6 +2299,39 @@ impl<'de> Visitor<'de> for RESPTypeVisitor {
         Ok(RESPType::Error(v))
     }
 
+    // RESP3 types marked by name, see `Tagged`.
+    fn visit_enum<A>(self, data: A) -> std::result::Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        let (token, variant): (String, _) = data.variant()?;
+        match token.as_str() {
+            BIG_NUMBER_TOKEN => variant.newtype_variant().map(RESPType::BigNumber),
+            NULL_TOKEN => variant.unit_variant().map(|()| RESPType::Null),
+            PUSH_TOKEN => variant.newtype_variant().map(RESPType::Push),
+            ATTRIBUTE_TOKEN => join_attribute(variant.newtype_variant()?).ok_or_else(|| {
+                de::Error::invalid_value(
+                    de::Unexpected::Other("malformed attribute"),
+                    &"keys and values followed by a value",
+                )
+            }),
+            MAP_TOKEN => join_map(variant.newtype_variant()?).ok_or_else(|| {
+                de::Error::invalid_value(de::Unexpected::Other("malformed map"), &"keys and values")
+            }),
+            SET_TOKEN => variant.newtype_variant().map(RESPType::Set),
+            VERBATIM_TOKEN => match variant.newtype_variant()? {
+                RESPType::BulkString(Some(payload)) if is_verbatim(&payload) => {
+                    Ok(split_verbatim(payload))
+                }
+                _ => Err(de::Error::invalid_value(
+                    de::Unexpected::Other("malformed verbatim string"),
+                    &"format:data",
+                )),
+            },
+            _ => Err(de::Error::unknown_variant(&token, TAGGED_TOKENS)),
+        }
+    }
+
     // BulkString
     fn visit_byte_buf<E>(self, v: Vec<u8>) -> std::result::Result<Self::Value, E>
     where
@@ -410,6 +2340,14 @@ impl<'de> Visitor<'de> for RESPTypeVisitor {
         Ok(RESPType::BulkString(Some(v)))
     }
 
+    // BulkString, from a RESPRef
+    fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(RESPType::BulkString(Some(v.to_vec())))
+    }
+
     // null BulkString
     fn visit_none<E>(self) -> std::result::Result<Self::Value, E>
     where
@@ -433,7 +2371,8 @@ impl<'de> Visitor<'de> for RESPTypeVisitor {
     where
         A: SeqAccess<'de>,
     {
-        let mut arr: Vec<RESPType> = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        // The length is the peer's word, so at most a few elements are allocated ahead of those read.
+        let mut arr: Vec<RESPType> = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
         loop {
             match seq.next_element()? {
                 None => break,
@@ -0,0 +1,129 @@
+//! [miette](https://docs.rs/miette) diagnostics for decoding errors. Requires the `diagnostics` feature.
+//!
+//! [Error](crate::Error) implements `miette::Diagnostic` with an error code and help text.
+//! To also point at the bytes that broke the parse, decode with [read_many](read_many),
+//! which keeps the input around in the returned [SourceError](SourceError).
+
+use crate::{de, Error, ErrorKind};
+
+use miette::{Diagnostic, LabeledSpan, SourceCode, SourceSpan};
+use serde::de::DeserializeOwned;
+
+use std::fmt::{self, Display};
+
+impl Diagnostic for Error {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        let code = match self.kind() {
+            ErrorKind::Message => "serde_resp::message",
+            ErrorKind::Eof => "serde_resp::eof",
+            ErrorKind::Protocol => "serde_resp::protocol",
+            ErrorKind::Limit => "serde_resp::limit",
+            ErrorKind::Io => "serde_resp::io",
+            ErrorKind::Utf8 => "serde_resp::utf8",
+            ErrorKind::InvalidValue => "serde_resp::invalid_value",
+        };
+        Some(Box::new(code))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        let help = match self.kind() {
+            ErrorKind::Eof => "the input ends in the middle of a value",
+            ErrorKind::Protocol => {
+                "every value starts with a RESP2 or RESP3 type byte and every line ends with CRLF"
+            }
+            ErrorKind::Limit => "the value is larger than the configured limits allow",
+            ErrorKind::Utf8 => "bulk strings are the only binary-safe type",
+            ErrorKind::InvalidValue => "send text with line breaks as a bulk string",
+            ErrorKind::Message | ErrorKind::Io => return None,
+        };
+        Some(Box::new(help))
+    }
+}
+
+/// An [Error](Error) together with the input it was raised on, labeling the bytes that failed to decode.
+#[derive(Debug)]
+pub struct SourceError {
+    error: Error,
+    source: Vec<u8>,
+    span: SourceSpan,
+}
+
+impl SourceError {
+    /// The underlying error.
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+
+    /// Convert into the underlying error, dropping the input.
+    pub fn into_error(self) -> Error {
+        self.error
+    }
+}
+
+impl Display for SourceError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.error.fmt(formatter)
+    }
+}
+
+impl std::error::Error for SourceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        std::error::Error::source(&self.error)
+    }
+}
+
+impl Diagnostic for SourceError {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.error.code()
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.error.help()
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let label = match &self.error {
+            Error::Batch { error, .. } => format!("{:#}", error),
+            error => format!("{:#}", error),
+        };
+        Some(Box::new(std::iter::once(LabeledSpan::new_with_span(
+            Some(label),
+            self.span,
+        ))))
+    }
+}
+
+/// Deserialize every value in `input`, like [de::read_many](crate::de::read_many).
+///
+/// # Errors
+/// On failure, the returned [SourceError](SourceError) holds a copy of `input` and labels the value
+/// that failed to decode, from its first byte up to where decoding stopped.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{diagnostics, RESP};
+///
+/// let err = diagnostics::read_many::<RESP>(b"+OK\r\n:1\r\n:x\r\n").unwrap_err();
+/// let report = miette::Report::new(err);
+/// assert!(format!("{:?}", report).contains("serde_resp::protocol"));
+/// ```
+pub fn read_many<T>(input: &[u8]) -> Result<Vec<T>, SourceError>
+where
+    T: DeserializeOwned,
+{
+    de::read_many_until(&mut &input[..]).map_err(|(error, end)| {
+        let start = match &error {
+            Error::Batch { consumed, .. } => *consumed,
+            _ => 0,
+        };
+        SourceError {
+            error,
+            source: input.to_vec(),
+            span: (start..end).into(),
+        }
+    })
+}
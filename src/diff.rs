@@ -0,0 +1,123 @@
+//! Structural comparison of RESP values.
+//!
+//! [diff](diff) finds where two values first differ, for test failures and logs that are readable
+//! even when the values are large. [assert_resp_eq](crate::assert_resp_eq) reports mismatches with it.
+
+use crate::{Path, RESPType};
+
+use std::fmt::{self, Display};
+
+/// First place where two RESP values differ, returned by [diff](diff).
+///
+/// Its `Display` renders the path and both sides, e.g. `at [1][0]: left is integer 2, right is integer 3`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RespDiff<'a> {
+    path: Path,
+    left: &'a RESPType,
+    right: &'a RESPType,
+}
+
+impl<'a> RespDiff<'a> {
+    /// Position of the differing values.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The differing value on the left.
+    ///
+    /// If both are arrays, they differ in length and are otherwise equal.
+    pub fn left(&self) -> &'a RESPType {
+        self.left
+    }
+
+    /// The differing value on the right.
+    pub fn right(&self) -> &'a RESPType {
+        self.right
+    }
+}
+
+impl<'a> Display for RespDiff<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "at {}: left is {}, right is {}",
+            self.path,
+            Describe(self.left),
+            Describe(self.right)
+        )
+    }
+}
+
+// Short human-readable description of a single node.
+struct Describe<'a>(&'a RESPType);
+
+impl<'a> Display for Describe<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            RESPType::SimpleString(s) => write!(formatter, "simple string {:?}", s),
+            RESPType::Error(s) => write!(formatter, "error {:?}", s),
+            RESPType::Integer(i) => write!(formatter, "integer {}", i),
+            RESPType::BulkString(None) => formatter.write_str("null bulk string"),
+            RESPType::BulkString(Some(b)) => {
+                write!(formatter, "bulk string b\"{}\"", b.escape_ascii())
+            }
+            RESPType::Array(None) => formatter.write_str("null array"),
+            RESPType::Null => formatter.write_str("null"),
+            RESPType::Array(Some(arr)) => write!(formatter, "array of {} elements", arr.len()),
+            RESPType::Double(v) => write!(formatter, "double {}", v),
+            RESPType::Boolean(v) => write!(formatter, "boolean {}", v),
+            RESPType::BigNumber(s) => write!(formatter, "big number {}", s),
+            RESPType::Verbatim { format, data } => {
+                write!(
+                    formatter,
+                    "verbatim string {}:\"{}\"",
+                    format,
+                    data.escape_ascii()
+                )
+            }
+            RESPType::Push(arr) => write!(formatter, "push of {} elements", arr.len()),
+            RESPType::Attribute { attributes, .. } => {
+                write!(formatter, "attribute of {} keys", attributes.len())
+            }
+            RESPType::Map(pairs) => write!(formatter, "map of {} keys", pairs.len()),
+            RESPType::Set(elements) => write!(formatter, "set of {} elements", elements.len()),
+        }
+    }
+}
+
+/// Find the first place where `left` and `right` differ in depth-first order, or `None` if they are equal.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, bulk, diff, int, RESP};
+///
+/// let left = array![int!(1), array![bulk!(b"foo".to_vec())]];
+/// let right = array![int!(1), array![bulk!(b"bar".to_vec())]];
+/// let difference = diff::diff(&left, &right).unwrap();
+/// assert_eq!(&[1, 0], difference.path().indices());
+/// assert_eq!(&bulk!(b"bar".to_vec()), difference.right());
+/// assert_eq!(
+///     "at [1][0]: left is bulk string b\"foo\", right is bulk string b\"bar\"",
+///     difference.to_string()
+/// );
+///
+/// assert!(diff::diff(&left, &left.clone()).is_none());
+/// ```
+pub fn diff<'a>(left: &'a RESPType, right: &'a RESPType) -> Option<RespDiff<'a>> {
+    if left == right {
+        return None;
+    }
+    if left.kind() == right.kind() {
+        for (i, (l, r)) in left.children().zip(right.children()).enumerate() {
+            if let Some(mut difference) = diff(l, r) {
+                difference.path.push_front(i);
+                return Some(difference);
+            }
+        }
+    }
+    Some(RespDiff {
+        path: Path::default(),
+        left,
+        right,
+    })
+}
@@ -0,0 +1,49 @@
+use crate::{ser, RESPType};
+
+impl RESPType {
+    /// Convert this value to RESP2, the way Redis replies to RESP2 clients, such as for a proxy
+    /// between a RESP3 server and RESP2 clients.
+    ///
+    /// RESP2 values are kept as they are, and so are the RESP2 values nested in RESP3 ones. Doubles
+    /// and big numbers become bulk strings of their text, booleans the integers `1` and `0`,
+    /// verbatim strings bulk strings of their data, nulls null bulk strings, maps flat arrays of their
+    /// keys and values, like the RESP2 reply of `HGETALL`, and sets and pushes arrays.
+    /// Attributes are dropped, keeping the value they are about. Writing the result is the same as
+    /// writing the value with a [RESP2 serializer](crate::ser::Serializer::with_protocol).
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{array, bulk, bulk_null, int, simple, RESP};
+    ///
+    /// let reply = RESP::push(vec![
+    ///     RESP::double(1.5),
+    ///     RESP::boolean(true),
+    ///     RESP::Null,
+    ///     RESP::attribute(vec![(simple!("ttl".to_owned()), int!(3600))], RESP::verbatim("txt", "hi")),
+    /// ]);
+    /// assert_eq!(
+    ///     array![bulk!(b"1.5".to_vec()), int!(1), bulk_null!(), bulk!(b"hi".to_vec())],
+    ///     reply.downgrade_to_resp2()
+    /// );
+    /// ```
+    pub fn downgrade_to_resp2(mut self) -> Self {
+        self.walk_mut(|_, value| {
+            while let RESPType::Attribute { value: inner, .. } = value {
+                *value = std::mem::replace(&mut **inner, RESPType::Null);
+            }
+            *value = match std::mem::replace(value, RESPType::Null) {
+                RESPType::Double(v) => RESPType::bulk(ser::format_double(v)),
+                RESPType::Boolean(v) => RESPType::Integer(v as i64),
+                RESPType::BigNumber(s) => RESPType::bulk(s),
+                RESPType::Verbatim { data, .. } => RESPType::bulk(data),
+                RESPType::Null => RESPType::bulk_null(),
+                RESPType::Push(arr) | RESPType::Set(arr) => RESPType::Array(Some(arr)),
+                RESPType::Map(pairs) => {
+                    RESPType::array(pairs.into_iter().flat_map(|(k, v)| vec![k, v]))
+                }
+                other => other,
+            };
+        });
+        self
+    }
+}
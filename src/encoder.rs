@@ -0,0 +1,298 @@
+//! Streaming encoder that checks the shape of the frames it writes.
+//!
+//! [Encoder](Encoder) writes frames one piece at a time, without building a [RESPType](crate::RESPType) first.
+//! Beginning an array returns an [ArrayEncoder](ArrayEncoder) that borrows the encoder, so nothing else can be
+//! written until it is done, and that must receive exactly as many elements as announced before it is
+//! [finished](ArrayEncoder::finish). Writing too many elements, finishing too early, or writing after an
+//! array was dropped unfinished fails instead of writing a malformed frame. An element that is rejected,
+//! such as a simple string containing CR or LF, is not written nor counted. Once a write to the writer
+//! fails partway, every later call fails too, as the frame cannot be completed.
+//!
+//! # Examples
+//! ```
+//! use serde_resp::encoder::Encoder;
+//! use serde_resp::{int, RESP};
+//!
+//! let mut encoder = Encoder::new(Vec::new());
+//! let mut array = encoder.array(3).unwrap();
+//! array.bulk(b"SET").unwrap();
+//! array.bulk(b"counter").unwrap();
+//! let mut nested = array.array(2).unwrap();
+//! nested.int(1).unwrap();
+//! nested.value(&int!(2)).unwrap();
+//! nested.finish().unwrap();
+//! array.finish().unwrap();
+//! encoder.simple("OK").unwrap();
+//! assert_eq!(
+//!     b"*3\r\n$3\r\nSET\r\n$7\r\ncounter\r\n*2\r\n:1\r\n:2\r\n+OK\r\n",
+//!     &encoder.finish().unwrap()[..]
+//! );
+//!
+//! let mut encoder = Encoder::new(Vec::new());
+//! let mut array = encoder.array(1).unwrap();
+//! array.int(1).unwrap();
+//! assert!(array.int(2).is_err());
+//!
+//! let mut encoder = Encoder::new(Vec::new());
+//! let mut array = encoder.array(2).unwrap();
+//! array.int(1).unwrap();
+//! assert!(array.finish().is_err());
+//! assert!(encoder.int(2).is_err());
+//!
+//! let mut encoder = Encoder::new(Vec::new());
+//! let mut array = encoder.array(2).unwrap();
+//! array.int(1).unwrap();
+//! assert!(array.simple("a\r\nb").is_err());
+//! assert_eq!(1, array.remaining());
+//! array.simple("b").unwrap();
+//! array.finish().unwrap();
+//! assert_eq!(b"*2\r\n:1\r\n+b\r\n", &encoder.finish().unwrap()[..]);
+//! ```
+
+use crate::{ser, Error, RESPKind, Result};
+
+use serde::Serialize;
+use std::io::Write;
+
+/// Writer of RESP frames, piece by piece. Refer to [encoder](self).
+pub struct Encoder<W: Write> {
+    writer: W,
+    // Elements still expected by each array being written, outermost first.
+    remaining: Vec<usize>,
+    // Set once a write failed, leaving part of a frame in `writer`.
+    broken: bool,
+}
+
+impl<W: Write> Encoder<W> {
+    /// Encode into `writer`.
+    pub fn new(writer: W) -> Self {
+        Encoder {
+            writer,
+            remaining: Vec::new(),
+            broken: false,
+        }
+    }
+
+    /// Get a reference to the writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Get a mutable reference to the writer. Writing to it directly may break the frames.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Get the writer back. Fails if an array was dropped before receiving all its elements,
+    /// or if a write failed partway.
+    pub fn finish(self) -> Result<W> {
+        self.check_depth(0)?;
+        Ok(self.writer)
+    }
+
+    /// Write a simple string. Fails if it contains CR or LF.
+    pub fn simple(&mut self, s: &str) -> Result<()> {
+        self.check_depth(0)?;
+        check_line(RESPKind::SimpleString, s)?;
+        self.write_line(RESPKind::SimpleString, s)
+    }
+
+    /// Write an error. Fails if it contains CR or LF.
+    pub fn err_str(&mut self, s: &str) -> Result<()> {
+        self.check_depth(0)?;
+        check_line(RESPKind::Error, s)?;
+        self.write_line(RESPKind::Error, s)
+    }
+
+    /// Write an integer.
+    pub fn int(&mut self, i: i64) -> Result<()> {
+        self.check_depth(0)?;
+        self.write_header(b':', i)
+    }
+
+    /// Write a bulk string.
+    pub fn bulk(&mut self, bytes: &[u8]) -> Result<()> {
+        self.check_depth(0)?;
+        self.write_bulk(bytes)
+    }
+
+    /// Write a null bulk string.
+    pub fn bulk_null(&mut self) -> Result<()> {
+        self.check_depth(0)?;
+        self.write_all(b"$-1\r\n")
+    }
+
+    /// Write a null array.
+    pub fn array_null(&mut self) -> Result<()> {
+        self.check_depth(0)?;
+        self.write_all(b"*-1\r\n")
+    }
+
+    /// Write any serializable value, such as a [RESPType](crate::RESPType), as a whole.
+    /// If it fails to serialize, nothing is written.
+    pub fn value<T: Serialize>(&mut self, value: &T) -> Result<()> {
+        self.check_depth(0)?;
+        let bytes = ser::to_vec(value)?;
+        self.write_all(&bytes)
+    }
+
+    /// Begin an array of `len` elements, to be written to the returned [ArrayEncoder](ArrayEncoder).
+    pub fn array(&mut self, len: usize) -> Result<ArrayEncoder<'_, W>> {
+        self.check_depth(0)?;
+        self.begin_array(len)
+    }
+
+    // fail unless the innermost array being written is at `depth`, i.e. no inner array was left unfinished,
+    // and no write failed.
+    fn check_depth(&self, depth: usize) -> Result<()> {
+        if self.broken {
+            return Err(Error::Message("an earlier write failed partway".to_owned()));
+        }
+        match self.remaining.get(depth) {
+            Some(&missing) => Err(unfinished(missing)),
+            None => Ok(()),
+        }
+    }
+
+    fn begin_array(&mut self, len: usize) -> Result<ArrayEncoder<'_, W>> {
+        self.write_header(b'*', len)?;
+        self.remaining.push(len);
+        let depth = self.remaining.len();
+        Ok(ArrayEncoder {
+            encoder: self,
+            depth,
+        })
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+        let written = self.writer.write_all(buf);
+        self.broken |= written.is_err();
+        Ok(written?)
+    }
+
+    fn write_header<I: itoa::Integer>(&mut self, type_byte: u8, v: I) -> Result<()> {
+        let mut buf = [0; ser::HEADER_MAX_LEN];
+        self.write_all(ser::encode_header(&mut buf, type_byte, v))
+    }
+
+    // `s` must be checked with `check_line` first.
+    fn write_line(&mut self, kind: RESPKind, s: &str) -> Result<()> {
+        self.write_all(&[kind.type_byte()])?;
+        self.write_all(s.as_bytes())?;
+        self.write_all(b"\r\n")
+    }
+
+    fn write_bulk(&mut self, bytes: &[u8]) -> Result<()> {
+        self.write_header(b'$', bytes.len())?;
+        self.write_all(bytes)?;
+        self.write_all(b"\r\n")
+    }
+}
+
+/// An array being written by an [Encoder](Encoder), returned by [Encoder::array](Encoder::array).
+///
+/// It has the same methods as [Encoder](Encoder) to write each element, which fail once all announced
+/// elements are written. [finish](ArrayEncoder::finish) it afterwards. If it is dropped before receiving
+/// all its elements, the enclosing array or encoder fails on its next use.
+#[must_use = "an array must be finished after writing its elements"]
+pub struct ArrayEncoder<'a, W: Write> {
+    encoder: &'a mut Encoder<W>,
+    // Position of this array in `encoder.remaining`, plus one.
+    depth: usize,
+}
+
+impl<'a, W: Write> ArrayEncoder<'a, W> {
+    /// Elements still expected.
+    pub fn remaining(&self) -> usize {
+        self.encoder.remaining[self.depth - 1]
+    }
+
+    /// End the array. Fails if it did not receive all its elements.
+    pub fn finish(self) -> Result<()> {
+        self.encoder.check_depth(self.depth)?;
+        match self.remaining() {
+            0 => Ok(()),
+            missing => Err(unfinished(missing)),
+        }
+    }
+
+    /// Write a simple string as the next element. Fails if it contains CR or LF.
+    pub fn simple(&mut self, s: &str) -> Result<()> {
+        check_line(RESPKind::SimpleString, s)?;
+        self.element()?.write_line(RESPKind::SimpleString, s)
+    }
+
+    /// Write an error as the next element. Fails if it contains CR or LF.
+    pub fn err_str(&mut self, s: &str) -> Result<()> {
+        check_line(RESPKind::Error, s)?;
+        self.element()?.write_line(RESPKind::Error, s)
+    }
+
+    /// Write an integer as the next element.
+    pub fn int(&mut self, i: i64) -> Result<()> {
+        self.element()?.write_header(b':', i)
+    }
+
+    /// Write a bulk string as the next element.
+    pub fn bulk(&mut self, bytes: &[u8]) -> Result<()> {
+        self.element()?.write_bulk(bytes)
+    }
+
+    /// Write a null bulk string as the next element.
+    pub fn bulk_null(&mut self) -> Result<()> {
+        self.element()?.write_all(b"$-1\r\n")
+    }
+
+    /// Write a null array as the next element.
+    pub fn array_null(&mut self) -> Result<()> {
+        self.element()?.write_all(b"*-1\r\n")
+    }
+
+    /// Write any serializable value, such as a [RESPType](crate::RESPType), as the next element.
+    /// If it fails to serialize, nothing is written nor counted.
+    pub fn value<T: Serialize>(&mut self, value: &T) -> Result<()> {
+        let bytes = ser::to_vec(value)?;
+        self.element()?.write_all(&bytes)
+    }
+
+    /// Begin an array of `len` elements as the next element.
+    pub fn array(&mut self, len: usize) -> Result<ArrayEncoder<'_, W>> {
+        self.element()?.begin_array(len)
+    }
+
+    // count the next element, failing if there is no room for it.
+    fn element(&mut self) -> Result<&mut Encoder<W>> {
+        self.encoder.check_depth(self.depth)?;
+        let remaining = &mut self.encoder.remaining[self.depth - 1];
+        match remaining.checked_sub(1) {
+            Some(left) => {
+                *remaining = left;
+                Ok(&mut *self.encoder)
+            }
+            None => Err(Error::Message(
+                "array has no room for more elements".to_owned(),
+            )),
+        }
+    }
+}
+
+impl<'a, W: Write> Drop for ArrayEncoder<'a, W> {
+    // Complete arrays are done, incomplete ones stay to fail the next use of the encoder.
+    fn drop(&mut self) {
+        if self.encoder.remaining.len() == self.depth && self.remaining() == 0 {
+            self.encoder.remaining.pop();
+        }
+    }
+}
+
+// fail if `s` cannot be written as a line, as it contains CR or LF.
+fn check_line(kind: RESPKind, s: &str) -> Result<()> {
+    if s.contains(['\r', '\n']) {
+        return Err(Error::LineBreak { kind });
+    }
+    Ok(())
+}
+
+fn unfinished(missing: usize) -> Error {
+    Error::Message(format!("array is missing {} elements", missing))
+}
@@ -1,27 +1,362 @@
-use std;
 use std::fmt::{self, Display};
 
+use crate::{RESPKind, RESPType};
 use serde::{de, ser};
 use std::io;
-use std::io::ErrorKind;
 use std::string::FromUtf8Error;
+use std::sync::Arc;
 
 /// Result type used by the crate.
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// Error type used by the crate.
-#[derive(Clone, Debug, PartialEq)]
+///
+/// New variants and fields may be added in minor releases, so matches on it need a wildcard arm.
+/// Prefer [kind](Error::kind) and the other accessors to matching on variants.
+/// Errors cannot be compared with `==`, as the `io::Error`s they keep cannot.
+///
+/// `Display` shows only the outermost layer: the error a [Batch](Error::Batch),
+/// [Position](Error::Position) or [Io](Error::Io) error wraps is its
+/// [source](std::error::Error::source). Format with `{:#}` to show the whole chain.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{de, RESP};
+/// use std::error::Error as _;
+///
+/// let err = de::from_str::<RESP>("*1\r\n:x\r\n").unwrap_err();
+/// assert_eq!("decoding failed after 8 bytes", err.to_string());
+/// assert!(err.source().unwrap().to_string().starts_with("syntax error"));
+/// assert!(format!("{:#}", err).starts_with("decoding failed after 8 bytes: syntax error"));
+/// ```
+#[derive(Clone, Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// For custom errors by serde.
     Message(String),
-    /// Unexpected EOF.
+    /// Unexpected EOF, in the middle of a value.
     Eof,
+    /// The input ended between values, before the first byte of the next one.
+    CleanEof,
     /// Syntax error.
     Syntax,
+    /// Bytes after the value, where the input should have ended.
+    TrailingBytes,
     /// IO error.
-    Io(String),
+    #[non_exhaustive]
+    Io {
+        /// The underlying `io::Error`, shared so the error can be cloned.
+        error: Arc<io::Error>,
+    },
     /// Trying to convert non-utf-8 bytes to string.
-    FromUtf8(String),
+    #[non_exhaustive]
+    FromUtf8 {
+        /// Length of the valid utf-8 prefix.
+        valid_up_to: usize,
+        /// Length of the invalid sequence after it, `None` if the input ended in the middle of a character.
+        error_len: Option<usize>,
+    },
+    /// A value is larger than the configured [Limits](crate::de::Limits) allow.
+    #[non_exhaustive]
+    LimitExceeded {
+        /// Which limit was exceeded.
+        limit: LimitKind,
+        /// Size of the offending value. For [LimitKind::LineLength](LimitKind::LineLength),
+        /// reading stops once the limit is exceeded so this is a lower bound.
+        actual: u64,
+    },
+    /// Decoding one of several consecutive values failed.
+    #[non_exhaustive]
+    Batch {
+        /// Index of the value that failed to decode.
+        index: usize,
+        /// Bytes consumed by the values before it.
+        consumed: usize,
+        /// Why it failed.
+        error: Box<Error>,
+    },
+    /// Decoding failed at a position in the input.
+    #[non_exhaustive]
+    Position {
+        /// Bytes read when the error was detected, so the error is in the bytes before.
+        offset: usize,
+        /// The byte just before `offset`, if that one byte is to blame, such as an unknown type byte.
+        byte: Option<u8>,
+        /// Why it failed.
+        error: Box<Error>,
+    },
+    /// A simple string, error or big number to serialize contains CR or LF, which would end its line early
+    /// and make the rest of it read as another frame.
+    #[non_exhaustive]
+    LineBreak {
+        /// Type of the offending value.
+        kind: RESPKind,
+    },
+}
+
+/// Limit in [Error::LimitExceeded](Error::LimitExceeded).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum LimitKind {
+    /// Length of a bulk string.
+    BulkLength,
+    /// Number of elements in an array.
+    ArrayLength,
+    /// Nesting depth of arrays.
+    Depth,
+    /// Length of a line.
+    LineLength,
+    /// Bytes charged to a [Budget](crate::de::Budget).
+    Budget,
+}
+
+impl Display for LimitKind {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(match self {
+            LimitKind::BulkLength => "bulk string length",
+            LimitKind::ArrayLength => "array length",
+            LimitKind::Depth => "nesting depth",
+            LimitKind::LineLength => "line length",
+            LimitKind::Budget => "memory budget",
+        })
+    }
+}
+
+/// Broad category of an [Error](Error), see [Error::kind](Error::kind).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Custom errors by serde.
+    Message,
+    /// Input ended before a complete value was read.
+    Eof,
+    /// Input does not follow the RESP protocol.
+    Protocol,
+    /// A configured limit was exceeded.
+    Limit,
+    /// IO error.
+    Io,
+    /// Non-utf-8 bytes where a string was expected.
+    Utf8,
+    /// A value cannot be serialized as it is, such as a simple string containing CR or LF.
+    InvalidValue,
+}
+
+impl Error {
+    /// Category of this error, for deciding how to react to it without matching on messages.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{de, ErrorKind, RESP};
+    ///
+    /// let err = de::from_str::<RESP>("?\r\n").unwrap_err();
+    /// assert_eq!(ErrorKind::Protocol, err.kind());
+    /// assert!(err.is_protocol());
+    ///
+    /// let err = de::from_str::<RESP>("").unwrap_err();
+    /// assert_eq!(ErrorKind::Eof, err.kind());
+    /// assert!(err.is_eof());
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Message(_) => ErrorKind::Message,
+            Error::Eof | Error::CleanEof => ErrorKind::Eof,
+            Error::Syntax | Error::TrailingBytes => ErrorKind::Protocol,
+            Error::Io { .. } => ErrorKind::Io,
+            Error::FromUtf8 { .. } => ErrorKind::Utf8,
+            Error::LimitExceeded { .. } => ErrorKind::Limit,
+            Error::Batch { error, .. } | Error::Position { error, .. } => error.kind(),
+            Error::LineBreak { .. } => ErrorKind::InvalidValue,
+        }
+    }
+
+    /// Whether the input ended, between values or in the middle of one, see [ErrorKind::Eof](ErrorKind::Eof).
+    pub fn is_eof(&self) -> bool {
+        self.kind() == ErrorKind::Eof
+    }
+
+    /// Whether the input ended in the middle of a value, such as when a peer closed the connection
+    /// while sending it.
+    ///
+    /// More input may turn this into a successful read.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{de, RESP};
+    ///
+    /// let err = de::from_str::<RESP>("*2\r\n:1\r\n").unwrap_err();
+    /// assert!(err.is_incomplete());
+    /// assert!(!err.is_clean_eof());
+    ///
+    /// let err = de::from_str::<RESP>("").unwrap_err();
+    /// assert!(!err.is_incomplete());
+    /// assert!(err.is_clean_eof());
+    /// assert!(err.is_eof());
+    /// ```
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self.innermost(), Error::Eof)
+    }
+
+    /// Whether the input ended between values, such as when a peer closed the connection after a
+    /// complete reply. For a buffer, this means it is empty.
+    pub fn is_clean_eof(&self) -> bool {
+        matches!(self.innermost(), Error::CleanEof)
+    }
+
+    /// Whether the input is malformed, see [ErrorKind::Protocol](ErrorKind::Protocol).
+    pub fn is_protocol(&self) -> bool {
+        self.kind() == ErrorKind::Protocol
+    }
+
+    /// Whether a configured limit was exceeded, see [ErrorKind::Limit](ErrorKind::Limit).
+    pub fn is_limit(&self) -> bool {
+        self.kind() == ErrorKind::Limit
+    }
+
+    /// Whether this is an IO error, see [ErrorKind::Io](ErrorKind::Io).
+    pub fn is_io(&self) -> bool {
+        self.kind() == ErrorKind::Io
+    }
+
+    /// The exceeded limit and the size of the offending value, for limit errors.
+    pub fn limit(&self) -> Option<(LimitKind, u64)> {
+        match self.innermost() {
+            Error::LimitExceeded { limit, actual } => Some((*limit, *actual)),
+            _ => None,
+        }
+    }
+
+    /// Kind of the underlying `io::Error`, for IO errors.
+    pub fn io_kind(&self) -> Option<io::ErrorKind> {
+        self.io_error().map(io::Error::kind)
+    }
+
+    /// The underlying `io::Error`, for IO errors.
+    ///
+    /// It is also the last [source](std::error::Error::source) in the error's chain.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{de, RESP};
+    /// use std::error::Error as _;
+    /// use std::io::{self, Read};
+    ///
+    /// struct Broken;
+    /// impl Read for Broken {
+    ///     fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+    ///         Err(io::Error::new(io::ErrorKind::ConnectionReset, "peer reset"))
+    ///     }
+    /// }
+    ///
+    /// let err = de::from_reader::<RESP, _>(Broken).unwrap_err();
+    /// assert_eq!(Some(io::ErrorKind::ConnectionReset), err.io_kind());
+    /// assert_eq!("peer reset", err.io_error().unwrap().to_string());
+    /// assert_eq!(
+    ///     "decoding failed after 0 bytes: an IO error occurred: peer reset",
+    ///     format!("{:#}", err)
+    /// );
+    /// ```
+    pub fn io_error(&self) -> Option<&io::Error> {
+        match self.innermost() {
+            Error::Io { error } => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Length of the valid utf-8 prefix, for utf-8 errors.
+    pub fn utf8_valid_up_to(&self) -> Option<usize> {
+        match self.innermost() {
+            Error::FromUtf8 { valid_up_to, .. } => Some(*valid_up_to),
+            _ => None,
+        }
+    }
+
+    /// Index of the value that failed to decode, for errors from decoding several values.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{de, RESP};
+    ///
+    /// let err = de::read_many::<RESP, _>(&mut "+OK\r\n:x\r\n".as_bytes()).unwrap_err();
+    /// assert_eq!(Some(1), err.batch_index());
+    /// assert_eq!(Some(5), err.batch_consumed());
+    /// assert!(err.is_protocol());
+    /// ```
+    pub fn batch_index(&self) -> Option<usize> {
+        match self {
+            Error::Batch { index, .. } => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// Bytes consumed by the values before the one that failed to decode, for errors from decoding several values.
+    pub fn batch_consumed(&self) -> Option<usize> {
+        match self {
+            Error::Batch { consumed, .. } => Some(*consumed),
+            _ => None,
+        }
+    }
+
+    /// Bytes read when decoding failed, counted from where the deserializer started.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{de, RESP};
+    ///
+    /// let err = de::from_str::<RESP>("*2\r\n:1\r\n!x\r\n").unwrap_err();
+    /// assert_eq!(Some(9), err.offset());
+    /// assert_eq!(Some(b'!'), err.unexpected_byte());
+    /// assert_eq!("decoding failed after 9 bytes (unexpected '!')", err.to_string());
+    ///
+    /// let err = de::read_many::<RESP, _>(&mut "+OK\r\n$3\r\nabcd\r\n".as_bytes()).unwrap_err();
+    /// assert_eq!(Some(1), err.batch_index());
+    /// assert_eq!(Some(14), err.offset());
+    /// ```
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Error::Position { offset, .. } => Some(*offset),
+            Error::Batch { error, .. } => error.offset(),
+            _ => None,
+        }
+    }
+
+    /// The byte that decoding failed on, if that one byte is to blame, such as an unknown type byte.
+    pub fn unexpected_byte(&self) -> Option<u8> {
+        match self {
+            Error::Position { byte, .. } => *byte,
+            Error::Batch { error, .. } => error.unexpected_byte(),
+            _ => None,
+        }
+    }
+
+    // Record that decoding failed after `offset` bytes, unless the error already says where.
+    pub(crate) fn at(self, offset: usize) -> Error {
+        match self {
+            Error::Position { .. } => self,
+            error => Error::Position {
+                offset,
+                byte: None,
+                error: Box::new(error),
+            },
+        }
+    }
+
+    // A syntax error caused by `byte`, the last of `offset` bytes read.
+    pub(crate) fn unexpected(byte: u8, offset: usize) -> Error {
+        Error::Position {
+            offset,
+            byte: Some(byte),
+            error: Box::new(Error::Syntax),
+        }
+    }
+
+    // The error without any wrapping for context.
+    fn innermost(&self) -> &Error {
+        match self {
+            Error::Batch { error, .. } | Error::Position { error, .. } => error.innermost(),
+            error => error,
+        }
+    }
 }
 
 impl ser::Error for Error {
@@ -41,24 +376,88 @@ impl Display for Error {
         match self {
             Error::Message(msg) => formatter.write_str(msg),
             Error::Eof => formatter.write_str("unexpected end of input"),
+            Error::CleanEof => formatter.write_str("end of input"),
+            Error::TrailingBytes => formatter.write_str("trailing bytes after the value"),
             Error::Syntax => formatter.write_str(
                 "syntax error. possible error: invalid integer or type byte, missing CRLF.",
             ),
-            Error::Io(e) => formatter.write_str(&format!("an IO error occurred: {}", e)),
-            Error::FromUtf8(e) => {
-                formatter.write_str(&format!("an string conversion error occurred: {}", e))
+            Error::Io { error } => {
+                formatter.write_str("an IO error occurred")?;
+                if formatter.alternate() {
+                    write!(formatter, ": {}", error)?;
+                }
+                Ok(())
+            }
+            Error::FromUtf8 {
+                valid_up_to,
+                error_len,
+            } => formatter.write_str(&match error_len {
+                Some(len) => format!(
+                    "invalid utf-8 sequence of {} bytes from index {}",
+                    len, valid_up_to
+                ),
+                None => format!("incomplete utf-8 byte sequence from index {}", valid_up_to),
+            }),
+            Error::LimitExceeded { limit, actual } => {
+                formatter.write_str(&format!("{} limit exceeded: {}", limit, actual))
+            }
+            Error::Batch {
+                index,
+                consumed,
+                error,
+            } => {
+                write!(
+                    formatter,
+                    "value {} (starting at byte {}) failed to decode",
+                    index, consumed
+                )?;
+                write_source(formatter, error)
+            }
+            Error::Position {
+                offset,
+                byte,
+                error,
+            } => {
+                write!(formatter, "decoding failed after {} bytes", offset)?;
+                if let Some(byte) = byte {
+                    write!(
+                        formatter,
+                        " (unexpected '{}')",
+                        char::from(*byte).escape_default()
+                    )?;
+                }
+                write_source(formatter, error)
+            }
+            Error::LineBreak { kind } => {
+                formatter.write_str(&format!("{:?} contains CR or LF", kind))
             }
         }
     }
 }
 
-impl std::error::Error for Error {}
+// With `{:#}`, follow the outer layer with the error it wraps, itself in full.
+fn write_source(formatter: &mut fmt::Formatter, error: &Error) -> fmt::Result {
+    if formatter.alternate() {
+        write!(formatter, ": {:#}", error)?;
+    }
+    Ok(())
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { error } => Some(&**error),
+            Error::Batch { error, .. } | Error::Position { error, .. } => Some(&**error),
+            _ => None,
+        }
+    }
+}
 
 impl From<io::Error> for Error {
     fn from(e: io::Error) -> Self {
         match e.kind() {
-            ErrorKind::UnexpectedEof => Error::Eof,
-            _ => Error::Io(format!("{:?}", e)),
+            io::ErrorKind::UnexpectedEof => Error::Eof,
+            _ => Error::Io { error: Arc::new(e) },
         }
     }
 }
@@ -66,6 +465,98 @@ impl From<io::Error> for Error {
 /// This happens when to_string on non-utf8 bytes
 impl From<FromUtf8Error> for Error {
     fn from(e: FromUtf8Error) -> Self {
-        Error::FromUtf8(format!("{:?}", e))
+        let e = e.utf8_error();
+        Error::FromUtf8 {
+            valid_up_to: e.valid_up_to(),
+            error_len: e.error_len(),
+        }
+    }
+}
+
+impl RESPType {
+    /// A copy of this value cut down to size, for previews in logs and error messages.
+    ///
+    /// Arrays, sets and pushes keep their first `max_elems` elements, followed by a simple string `... N more elements`.
+    /// Maps keep their first `max_elems` keys, followed by a key `... N more keys` with a null value,
+    /// and attributes the same with `... N more attributes`.
+    /// Bulk strings keep their first `max_bulk_bytes` bytes, followed by `... N more bytes`.
+    /// Simple strings and errors are cut the same way, at a character boundary.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{array, bulk, int, simple, RESP};
+    ///
+    /// let reply = array![bulk!(b"a long bulk string".to_vec()), int!(2), int!(3), int!(4)];
+    /// assert_eq!(
+    ///     array![
+    ///         bulk!(b"a long... 12 more bytes".to_vec()),
+    ///         int!(2),
+    ///         simple!("... 2 more elements".to_owned()),
+    ///     ],
+    ///     reply.truncated(2, 6)
+    /// );
+    /// ```
+    pub fn truncated(&self, max_elems: usize, max_bulk_bytes: usize) -> RESPType {
+        let line = |s: &str| {
+            if s.len() <= max_bulk_bytes {
+                return s.to_owned();
+            }
+            let mut end = max_bulk_bytes;
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            format!("{}... {} more bytes", &s[..end], s.len() - end)
+        };
+        let elements = |arr: &[RESPType]| {
+            let mut cut: Vec<RESPType> = arr
+                .iter()
+                .take(max_elems)
+                .map(|element| element.truncated(max_elems, max_bulk_bytes))
+                .collect();
+            if arr.len() > max_elems {
+                cut.push(RESPType::SimpleString(format!(
+                    "... {} more elements",
+                    arr.len() - max_elems
+                )));
+            }
+            cut
+        };
+        let pairs = |pairs: &[(RESPType, RESPType)], what: &str| {
+            let mut cut: Vec<(RESPType, RESPType)> = pairs
+                .iter()
+                .take(max_elems)
+                .map(|(k, v)| {
+                    (
+                        k.truncated(max_elems, max_bulk_bytes),
+                        v.truncated(max_elems, max_bulk_bytes),
+                    )
+                })
+                .collect();
+            if pairs.len() > max_elems {
+                let more = format!("... {} more {}", pairs.len() - max_elems, what);
+                cut.push((RESPType::SimpleString(more), RESPType::Null));
+            }
+            cut
+        };
+        match self {
+            RESPType::SimpleString(s) => RESPType::SimpleString(line(s)),
+            RESPType::Error(s) => RESPType::Error(line(s)),
+            RESPType::BulkString(Some(b)) if b.len() > max_bulk_bytes => {
+                let mut cut = b[..max_bulk_bytes].to_vec();
+                cut.extend_from_slice(
+                    format!("... {} more bytes", b.len() - max_bulk_bytes).as_bytes(),
+                );
+                RESPType::BulkString(Some(cut))
+            }
+            RESPType::Array(Some(arr)) => RESPType::Array(Some(elements(arr))),
+            RESPType::Push(arr) => RESPType::Push(elements(arr)),
+            RESPType::Attribute { attributes, value } => RESPType::attribute(
+                pairs(attributes, "attributes"),
+                value.truncated(max_elems, max_bulk_bytes),
+            ),
+            RESPType::Map(map) => RESPType::Map(pairs(map, "keys")),
+            RESPType::Set(arr) => RESPType::Set(elements(arr)),
+            value => value.clone(),
+        }
     }
 }
@@ -0,0 +1,601 @@
+use crate::de::{check_limit, Limits};
+use crate::ser::ENCODED_TOKEN;
+use crate::shared::Encoded;
+use crate::{de, ser, Error, LimitKind, RESPKind, RESPType, Result};
+
+use serde::de::DeserializeOwned;
+use serde::{Serialize, Serializer};
+use std::convert::TryFrom;
+use std::io::{BufRead, Read, Write};
+use std::ops::Range;
+use std::str;
+
+/// A complete frame kept as its raw bytes, decoded only on demand.
+///
+/// Reading one checks the structure of the frame, such as its type bytes, lengths and line endings,
+/// without building a [RESPType](crate::RESPType). Proxies and caches that forward most frames untouched
+/// can read them as `LazyFrame`s, look at their [kind](LazyFrame::kind) or a few [elements](LazyFrame::element),
+/// and [decode](LazyFrame::decode) only those they need.
+///
+/// Serializing it with [ser::to_writer](crate::ser::to_writer) writes its bytes as they are.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{bulk, ser, LazyFrame, RESPKind, RESP};
+///
+/// let mut reader = "*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n+OK\r\n".as_bytes();
+/// let command = LazyFrame::read_from(&mut reader).unwrap();
+/// assert_eq!(RESPKind::Array, command.kind());
+/// assert_eq!(Some(2), command.array_len());
+/// assert_eq!(bulk!(b"GET".to_vec()), command.element(0).unwrap().decode::<RESP>().unwrap());
+///
+/// // Forward it untouched
+/// let mut upstream = Vec::new();
+/// ser::to_writer(&command, &mut upstream).unwrap();
+/// assert_eq!(b"*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n", &upstream[..]);
+///
+/// let reply = LazyFrame::read_from(&mut reader).unwrap();
+/// assert_eq!(RESPKind::SimpleString, reply.kind());
+/// assert!(LazyFrame::read_from(&mut reader).unwrap_err().is_eof());
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct LazyFrame {
+    bytes: Vec<u8>,
+    kind: RESPKind,
+}
+
+impl LazyFrame {
+    /// Read one complete frame from `reader`, which is left right after it if passed by `&mut`.
+    ///
+    /// # Errors
+    /// Fails like [de::from_buf_reader](crate::de::from_buf_reader) would on a malformed or incomplete frame.
+    /// The bytes read before the error are lost.
+    pub fn read_from<R: BufRead>(mut reader: R) -> Result<Self> {
+        let mut bytes = Vec::new();
+        let kind = read_frame(&mut reader, &mut bytes)?;
+        Ok(LazyFrame { bytes, kind })
+    }
+
+    /// Check that `bytes` hold exactly one complete frame and keep them.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{array, bulk_null, int, LazyFrame, RESP};
+    ///
+    /// let frame = LazyFrame::from_bytes(b"*3\r\n*2\r\n:1\r\n$-1\r\n$2\r\nab\r\n:3\r\n".to_vec()).unwrap();
+    /// assert_eq!(array![int!(1), bulk_null!()], frame.element(0).unwrap().decode::<RESP>().unwrap());
+    /// assert_eq!(b":3\r\n", frame.element(2).unwrap().as_bytes());
+    /// assert!(frame.element(3).is_none());
+    ///
+    /// assert!(LazyFrame::from_bytes(b"*2\r\n:1\r\n".to_vec()).unwrap_err().is_eof());
+    /// assert!(LazyFrame::from_bytes(b"$2\r\nabc\r\n".to_vec()).unwrap_err().is_protocol());
+    /// assert!(LazyFrame::from_bytes(b":1\r\n:2\r\n".to_vec()).is_err());
+    ///
+    /// // More elements than can be counted
+    /// let huge = b"*2\r\n*9223372036854775807\r\n*9223372036854775807\r\n*9223372036854775807\r\n";
+    /// assert!(LazyFrame::from_bytes(huge.to_vec()).unwrap_err().is_protocol());
+    /// let huge = b"*2\r\n|9223372036854775807\r\n";
+    /// assert!(LazyFrame::from_bytes(huge.to_vec()).unwrap_err().is_protocol());
+    /// let huge = b"$?\r\n;18446744073709551615\r\n";
+    /// assert!(LazyFrame::from_bytes(huge.to_vec()).unwrap_err().is_protocol());
+    /// ```
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self> {
+        let mut reader = &bytes[..];
+        let mut checked = Vec::with_capacity(bytes.len());
+        let kind = read_frame(&mut reader, &mut checked)?;
+        if !reader.is_empty() {
+            return Err(Error::Message(format!(
+                "{} bytes after the frame",
+                reader.len()
+            )));
+        }
+        Ok(LazyFrame {
+            bytes: checked,
+            kind,
+        })
+    }
+
+    /// Type of the frame.
+    pub fn kind(&self) -> RESPKind {
+        self.kind
+    }
+
+    /// The raw bytes of the frame.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Get the raw bytes of the frame back.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Decode the frame, usually into a [RESPType](crate::RESPType).
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T> {
+        de::from_buf_reader(&self.bytes[..])
+    }
+
+    /// Number of elements if the frame is a non-null array.
+    pub fn array_len(&self) -> Option<usize> {
+        array_len(&self.bytes, 0)
+    }
+
+    /// The element at `index` if the frame is a non-null array, without decoding the others.
+    pub fn element(&self, index: usize) -> Option<LazyFrame> {
+        if index >= self.array_len()? {
+            return None;
+        }
+        let mut start = header(&self.bytes, 0).0;
+        for _ in 0..index {
+            start = frame_end(&self.bytes, start);
+        }
+        let end = frame_end(&self.bytes, start);
+        Some(LazyFrame {
+            bytes: self.bytes[start..end].to_vec(),
+            kind: RESPKind::from_type_byte(self.bytes[start])?,
+        })
+    }
+
+    /// Replace the element at `path`, a list of array indices, with `value`, leaving the other bytes as they are.
+    /// An empty path replaces the whole frame.
+    ///
+    /// Array headers count elements rather than bytes, so only the replaced element is encoded again.
+    ///
+    /// # Errors
+    /// Fails if there is no element at `path`, or if `value` fails to serialize.
+    pub fn replace(&mut self, path: &[usize], value: &RESPType) -> Result<()> {
+        let range = self.locate(path)?;
+        let encoded = ser::to_vec(value)?;
+        self.bytes.splice(range, encoded);
+        if path.is_empty() {
+            self.kind = value.kind();
+        }
+        Ok(())
+    }
+
+    /// Change the payload of the bulk string at `path` with `patch`, such as to rewrite a key prefix in a proxy.
+    /// Only the length header of that bulk string is written again, the other bytes stay as they are.
+    ///
+    /// # Errors
+    /// Fails if there is no element at `path`, or if it is not a non-null bulk string.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{bulk, LazyFrame, RESP};
+    ///
+    /// let mut command = LazyFrame::from_bytes(b"*2\r\n$3\r\nGET\r\n$8\r\nuser:123\r\n".to_vec()).unwrap();
+    /// command
+    ///     .patch_bulk(&[1], |key| {
+    ///         key.splice(0..0, b"tenant7:".iter().copied());
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(b"*2\r\n$3\r\nGET\r\n$16\r\ntenant7:user:123\r\n", command.as_bytes());
+    ///
+    /// command.replace(&[0], &bulk!(b"GETDEL".to_vec())).unwrap();
+    /// assert_eq!(b"*2\r\n$6\r\nGETDEL\r\n$16\r\ntenant7:user:123\r\n", command.as_bytes());
+    /// assert!(command.patch_bulk(&[2], |_| {}).is_err());
+    /// ```
+    pub fn patch_bulk<F>(&mut self, path: &[usize], patch: F) -> Result<()>
+    where
+        F: FnOnce(&mut Vec<u8>),
+    {
+        let range = self.locate(path)?;
+        let (payload_start, len) = header(&self.bytes, range.start);
+        if self.bytes[range.start] != b'$' || len < 0 {
+            return Err(Error::Message(format!(
+                "element at {:?} is not a bulk string",
+                path
+            )));
+        }
+        let mut payload = if self.bytes[range.start + 1] == b'?' {
+            // Streamed in chunks, written back whole.
+            match de::from_buf_reader(&self.bytes[range.clone()])? {
+                RESPType::BulkString(Some(payload)) => payload,
+                _ => unreachable!("checked by read_frame"),
+            }
+        } else {
+            self.bytes[payload_start..range.end - 2].to_vec()
+        };
+        patch(&mut payload);
+        let mut header = [0; ser::HEADER_MAX_LEN];
+        let header = ser::encode_header(&mut header, b'$', payload.len());
+        let mut encoded = Vec::with_capacity(header.len() + payload.len() + 2);
+        encoded.extend_from_slice(header);
+        encoded.extend_from_slice(&payload);
+        encoded.extend_from_slice(b"\r\n");
+        self.bytes.splice(range, encoded);
+        Ok(())
+    }
+
+    // Range of the bytes of the element at `path`.
+    fn locate(&self, path: &[usize]) -> Result<Range<usize>> {
+        let mut start = 0;
+        for &index in path {
+            match array_len(&self.bytes, start) {
+                Some(len) if index < len => {}
+                _ => return Err(Error::Message(format!("no element at {:?}", path))),
+            }
+            let mut element = header(&self.bytes, start).0;
+            for _ in 0..index {
+                element = frame_end(&self.bytes, element);
+            }
+            start = element;
+        }
+        Ok(start..frame_end(&self.bytes, start))
+    }
+
+    /// Write the raw bytes of the frame to `writer`.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&self.bytes)?;
+        Ok(())
+    }
+}
+
+impl Serialize for LazyFrame {
+    fn serialize<S: Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> {
+        s.serialize_newtype_struct(ENCODED_TOKEN, &Encoded(&self.bytes))
+    }
+}
+
+// Copy one complete frame from `reader` to the end of `out`, checking its structure, and return its type.
+pub(crate) fn read_frame<R: BufRead>(reader: &mut R, out: &mut Vec<u8>) -> Result<RESPKind> {
+    let mut scanner = FrameScanner::default();
+    let mut next = Next::Line;
+    let frame_start = out.len();
+    loop {
+        let start = out.len();
+        next = match next {
+            Next::Line => {
+                reader.read_until(b'\n', out)?;
+                if out.len() == frame_start {
+                    return Err(Error::CleanEof);
+                }
+                scanner.line(&out[start..])?
+            }
+            Next::Payload(len) => {
+                let read = reader.take(len).read_to_end(out)?;
+                if (read as u64) < len {
+                    return Err(Error::Eof);
+                }
+                scanner.payload(&out[start..])?
+            }
+            Next::Done(kind) => return Ok(kind),
+        };
+    }
+}
+
+// Finds where complete frames end in bytes that arrive piece by piece, scanning each byte once.
+// Lines, payloads, aggregates and nesting over `limits` fail as soon as they are seen, rather than once they are buffered.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct FrameSplitter {
+    limits: Limits,
+    scanner: FrameScanner,
+    next: Next,
+    // Bytes of the frame scanned so far.
+    scanned: usize,
+}
+
+impl FrameSplitter {
+    pub(crate) fn new(limits: Limits) -> Self {
+        FrameSplitter {
+            limits,
+            scanner: FrameScanner::new(limits),
+            ..FrameSplitter::default()
+        }
+    }
+
+    // Length of the complete frame at the start of `buf`, or None if more bytes are needed.
+    // `buf` must start with the bytes it was given before, less the frames whose length was returned.
+    pub(crate) fn split(&mut self, buf: &[u8]) -> Result<Option<usize>> {
+        loop {
+            let rest = &buf[self.scanned..];
+            let len = match self.next {
+                Next::Line => match rest.iter().position(|&b| b == b'\n') {
+                    Some(i) => i + 1,
+                    None => {
+                        let len = rest.len().saturating_sub(1);
+                        check_limit(LimitKind::LineLength, self.limits.max_line_len, len)?;
+                        return Ok(None);
+                    }
+                },
+                Next::Payload(len) if (rest.len() as u64) < len => return Ok(None),
+                Next::Payload(len) => len as usize,
+                Next::Done(_) => {
+                    let len = self.scanned;
+                    *self = FrameSplitter::new(self.limits);
+                    return Ok(Some(len));
+                }
+            };
+            let piece = &rest[..len];
+            self.next = match self.next {
+                Next::Line => {
+                    check_limit(LimitKind::LineLength, self.limits.max_line_len, len - 1)?;
+                    self.scanner.line(piece)?
+                }
+                _ => self.scanner.payload(piece)?,
+            };
+            if let Next::Payload(len) = self.next {
+                let len = usize::try_from(len - 2).unwrap_or(usize::MAX);
+                check_limit(LimitKind::BulkLength, self.limits.max_bulk_len, len)?;
+            }
+            self.scanned += len;
+        }
+    }
+}
+
+// What a FrameScanner needs next.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) enum Next {
+    // A line, up to and including its LF.
+    #[default]
+    Line,
+    // This many bytes of a bulk string, a verbatim string or a chunk, including their CRLF.
+    Payload(u64),
+    // Nothing, the frame is complete.
+    Done(RESPKind),
+}
+
+// Checks the structure of a frame fed to it piece by piece, as told by `Next`, without doing any IO.
+// Nested arrays are counted rather than recursed into, so deep nesting cannot overflow the stack.
+// Elements of streamed aggregates are not counted: `remaining` is 0 while waiting for the next one or their end.
+// The number of elements of aggregates and their nesting are checked against `limits`.
+#[derive(Clone, Debug)]
+pub(crate) struct FrameScanner {
+    limits: Limits,
+    kind: Option<RESPKind>,
+    remaining: u64,
+    // Aggregates being read, innermost last.
+    open: Vec<Open>,
+    // Whether the chunks of a streamed bulk string are being read.
+    chunks: bool,
+}
+
+// An aggregate being read by a FrameScanner.
+#[derive(Clone, Copy, Debug)]
+enum Open {
+    // Its elements are read once `remaining` is back to this.
+    Sized(u64),
+    // Streamed, with `remaining` outside of it and the number of its elements so far.
+    Streamed(u64, usize),
+}
+
+impl Default for FrameScanner {
+    fn default() -> Self {
+        FrameScanner::new(Limits::default())
+    }
+}
+
+impl FrameScanner {
+    pub(crate) fn new(limits: Limits) -> Self {
+        FrameScanner {
+            limits,
+            kind: None,
+            remaining: 1,
+            open: Vec::new(),
+            chunks: false,
+        }
+    }
+
+    // Check a line, which fails with Eof if it does not end with LF.
+    pub(crate) fn line(&mut self, line: &[u8]) -> Result<Next> {
+        if line.last() != Some(&b'\n') {
+            return Err(Error::Eof);
+        }
+        if self.chunks {
+            return self.chunk_header(line);
+        }
+        if let Some(Open::Streamed(outer, len)) = self.open.last_mut() {
+            if matches!(line, b".\r\n" | b".\n") {
+                self.remaining = *outer;
+                self.open.pop();
+                return Ok(self.after_value());
+            }
+            *len += 1;
+            check_limit(LimitKind::ArrayLength, self.limits.max_array_len, *len)?;
+        }
+        // 0 for the elements of a streamed aggregate
+        self.remaining = self.remaining.saturating_sub(1);
+        let line_kind = RESPKind::from_type_byte(line[0]).ok_or(Error::Syntax)?;
+        let content = line[1..line.len() - 1]
+            .strip_suffix(b"\r")
+            .unwrap_or(&line[1..line.len() - 1]);
+        let content = match str::from_utf8(content) {
+            Ok(content) => content,
+            Err(e) => {
+                return Err(Error::FromUtf8 {
+                    valid_up_to: e.valid_up_to(),
+                    error_len: e.error_len(),
+                })
+            }
+        };
+        self.kind.get_or_insert(line_kind);
+        match line_kind {
+            RESPKind::BulkString if content == "?" => {
+                self.chunks = true;
+                return Ok(Next::Line);
+            }
+            RESPKind::Array | RESPKind::Map | RESPKind::Set if content == "?" => {
+                self.open(Open::Streamed(self.remaining, 0))?;
+                self.remaining = 0;
+                return Ok(Next::Line);
+            }
+            _ => {}
+        }
+        let number = match line_kind {
+            RESPKind::SimpleString
+            | RESPKind::Error
+            | RESPKind::Double
+            | RESPKind::Boolean
+            | RESPKind::BigNumber
+            | RESPKind::Null => 0,
+            _ => content
+                .trim_end()
+                .parse::<i64>()
+                .map_err(|_| Error::Syntax)?,
+        };
+        match line_kind {
+            // also read CRLF
+            RESPKind::BulkString | RESPKind::Verbatim if number >= 0 => {
+                return Ok(Next::Payload(number as u64 + 2))
+            }
+            RESPKind::Array | RESPKind::Push if number >= 0 => {
+                self.open_sized(Some(number as u64))?
+            }
+            RESPKind::Push => return Err(Error::Syntax),
+            // keys and values, then the value
+            RESPKind::Attribute if number >= 0 => {
+                let values = (number as u64)
+                    .checked_mul(2)
+                    .and_then(|keys| keys.checked_add(1));
+                self.open_sized(values)?
+            }
+            RESPKind::Attribute => return Err(Error::Syntax),
+            RESPKind::Map if number >= 0 => self.open_sized((number as u64).checked_mul(2))?,
+            RESPKind::Set if number >= 0 => self.open_sized(Some(number as u64))?,
+            RESPKind::Map | RESPKind::Set => return Err(Error::Syntax),
+            _ => {}
+        }
+        Ok(self.after_value())
+    }
+
+    // Check a payload of the length asked for.
+    pub(crate) fn payload(&mut self, payload: &[u8]) -> Result<Next> {
+        if !payload.ends_with(b"\r\n") {
+            return Err(Error::Syntax);
+        }
+        if self.chunks {
+            return Ok(Next::Line);
+        }
+        Ok(self.after_value())
+    }
+
+    // Check the header of a chunk of a streamed bulk string, after its `$?` line.
+    fn chunk_header(&mut self, line: &[u8]) -> Result<Next> {
+        if line[0] != b';' {
+            return Err(Error::Syntax);
+        }
+        let len = str::from_utf8(&line[1..])
+            .ok()
+            .and_then(|len| len.trim_end().parse::<u64>().ok())
+            .ok_or(Error::Syntax)?;
+        if len > 0 {
+            // also read CRLF
+            return Ok(Next::Payload(len.checked_add(2).ok_or(Error::Syntax)?));
+        }
+        self.chunks = false;
+        Ok(self.after_value())
+    }
+
+    // Open an aggregate of `values` values to read, failing if there are more than can be counted or allowed.
+    fn open_sized(&mut self, values: Option<u64>) -> Result<()> {
+        let outer = self.remaining;
+        let values = values.ok_or(Error::Syntax)?;
+        let remaining = outer.checked_add(values).ok_or(Error::Syntax)?;
+        let len = usize::try_from(values).unwrap_or(usize::MAX);
+        check_limit(LimitKind::ArrayLength, self.limits.max_array_len, len)?;
+        self.open(Open::Sized(outer))?;
+        self.remaining = remaining;
+        Ok(())
+    }
+
+    // Open an aggregate nested in those being read, failing if it is nested too deep.
+    fn open(&mut self, open: Open) -> Result<()> {
+        check_limit(LimitKind::Depth, self.limits.max_depth, self.open.len() + 1)?;
+        self.open.push(open);
+        Ok(())
+    }
+
+    // Close the sized aggregates whose elements were all read, and tell whether the frame is complete.
+    fn after_value(&mut self) -> Next {
+        while let Some(&Open::Sized(outer)) = self.open.last() {
+            if outer != self.remaining {
+                break;
+            }
+            self.open.pop();
+        }
+        match self.kind {
+            Some(kind) if self.remaining == 0 && self.open.is_empty() => Next::Done(kind),
+            _ => Next::Line,
+        }
+    }
+}
+
+// Position after the line starting at `start`, and the number in it for integers and headers.
+// `bytes` must hold a frame checked by `read_frame`.
+fn header(bytes: &[u8], start: usize) -> (usize, i64) {
+    let end = start
+        + bytes[start..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .expect("checked frames end lines with LF");
+    let number = match bytes[start] {
+        b':' | b'$' | b';' | b'=' | b'*' | b'>' | b'|' | b'%' | b'~' => {
+            str::from_utf8(&bytes[start + 1..end])
+                .ok()
+                .and_then(|line| line.trim_end().parse().ok())
+                .unwrap_or(0)
+        }
+        _ => 0,
+    };
+    (end + 1, number)
+}
+
+// Position after the frame starting at `start`. `bytes` must hold a frame checked by `read_frame`.
+fn frame_end(bytes: &[u8], mut start: usize) -> usize {
+    let mut remaining = 1u64;
+    let mut streams = Vec::new();
+    while remaining > 0 || !streams.is_empty() {
+        let type_byte = bytes[start];
+        let streamed = bytes[start + 1] == b'?';
+        let (end, number) = header(bytes, start);
+        start = end;
+        if remaining == 0 && type_byte == b'.' {
+            remaining = streams.pop().expect("in a streamed aggregate");
+            continue;
+        }
+        // 0 for the elements of a streamed aggregate
+        remaining = remaining.saturating_sub(1);
+        match type_byte {
+            b'$' if streamed => start = chunks_end(bytes, start),
+            b'*' | b'%' | b'~' if streamed => {
+                streams.push(remaining);
+                remaining = 0;
+            }
+            b'$' | b'=' if number >= 0 => start += number as usize + 2,
+            b'*' | b'>' | b'~' if number >= 0 => remaining += number as u64,
+            b'%' if number >= 0 => remaining += 2 * number as u64,
+            b'|' if number >= 0 => remaining += 2 * number as u64 + 1,
+            _ => {}
+        }
+    }
+    start
+}
+
+// Number of elements of the array starting at `start`, counting those of a streamed array. None if it is not a non-null array.
+// `bytes` must hold a frame checked by `read_frame`.
+fn array_len(bytes: &[u8], start: usize) -> Option<usize> {
+    if bytes[start] != b'*' {
+        return None;
+    }
+    let (mut element, len) = header(bytes, start);
+    if bytes[start + 1] != b'?' {
+        return usize::try_from(len).ok();
+    }
+    let mut len = 0;
+    while bytes[element] != b'.' {
+        element = frame_end(bytes, element);
+        len += 1;
+    }
+    Some(len)
+}
+
+// Position after the chunks of a streamed bulk string starting at `start`, checked by `read_frame`.
+fn chunks_end(bytes: &[u8], mut start: usize) -> usize {
+    loop {
+        let (end, len) = header(bytes, start);
+        if len == 0 {
+            return end;
+        }
+        start = end + len as usize + 2;
+    }
+}
@@ -17,16 +17,134 @@
 //! - `Bulk String`
 //!     + [RESP::BulkString(Option<Vec<u8>>)](RESPType::BulkString)
 //!         + Use `None` for null bulk strings and `Some` for non-null ones.
+//!         + RESP3 bulk strings streamed in chunks are deserialized whole.
 //! - `Array`
 //!     + [RESP::Array(Option<Vec<RESP>>)](RESPType::Array)
 //!         + Use `None` for null arrays and `Some` for non-null ones.
+//!         + RESP3 arrays streamed with `*?` are deserialized whole.
 //!
-//! To serialize, use [ser::to_string](ser::to_string) or [ser::to_writer](ser::to_writer).
+//! RESP3 adds the following types.
+//!
+//! - `Double`
+//!     + [RESP::Double(f64)](RESPType::Double)
+//! - `Boolean`
+//!     + [RESP::Boolean(bool)](RESPType::Boolean)
+//! - `Big Number`
+//!     + [RESP::BigNumber(String)](RESPType::BigNumber)
+//! - `Verbatim String`
+//!     + [RESP::Verbatim { format: String, data: Vec<u8> }](RESPType::Verbatim)
+//! - `Null`
+//!     + [RESP::Null](RESPType::Null)
+//! - `Push`
+//!     + [RESP::Push(Vec<RESP>)](RESPType::Push)
+//! - `Attribute`
+//!     + [RESP::Attribute { attributes: Vec<(RESP, RESP)>, value: Box<RESP> }](RESPType::Attribute)
+//! - `Map`
+//!     + [RESP::Map(Vec<(RESP, RESP)>)](RESPType::Map)
+//!         + RESP3 maps streamed with `%?` are deserialized whole.
+//! - `Set`
+//!     + [RESP::Set(Vec<RESP>)](RESPType::Set)
+//!         + RESP3 sets streamed with `~?` are deserialized whole.
+//!
+//! To serialize, use [ser::to_vec](ser::to_vec) or [ser::to_writer](ser::to_writer), or [ser::to_string](ser::to_string) and [ser::to_fmt_writer](ser::to_fmt_writer) for text.
+//! With feature `tokio`, [ser::to_async_writer](ser::to_async_writer) writes to a tokio `AsyncWrite`,
+//! and with feature `sink`, [sink::RespSink](sink::RespSink) is a `Sink` of frames over one.
+//! With feature `futures-io`, [ser::to_futures_writer](ser::to_futures_writer) writes to a futures-io `AsyncWrite`.
+//! [ser::serialized_size](ser::serialized_size) tells how many bytes a value takes without serializing it.
 //!
 //! To deserialize, use [de::from_str](de::from_str) or [de::from_reader](de::from_reader) or [de::from_buf_reader](de::from_buf_reader).
+//! To also check that nothing follows the value, use [de::from_str_exact](de::from_str_exact) or [Deserializer::end](de::Deserializer::end).
+//! To deserialize all values from a pipeline, use [de::read_many](de::read_many),
+//! [de::from_str_multi](de::from_str_multi) or [de::from_bytes_multi](de::from_bytes_multi),
+//! or [Deserializer::iter](de::Deserializer::iter) to handle them one at a time as they arrive.
+//! With feature `tokio`, [de::from_async_reader](de::from_async_reader) reads from a tokio `AsyncBufRead`,
+//! and with feature `stream`, [stream::RespStream](stream::RespStream) is a `Stream` of the frames of a tokio `AsyncRead`.
+//! With feature `futures-io`, [de::from_futures_reader](de::from_futures_reader) reads from a futures-io `AsyncBufRead`.
+//! To deserialize a frame out of a network buffer and know where the next one begins, use [de::from_bytes](de::from_bytes).
+//! To decode a buffer without copying its strings, use [de::from_slice](de::from_slice), which borrows them in a [RESPRef](RESPRef).
+//! To split a buffer into its frames without decoding them, use [de::iter_frames](de::iter_frames),
+//! or [de::check_complete](de::check_complete) to find out whether it holds a whole frame yet.
+//! To parse bytes as they arrive from any event loop, without IO, feed them to a [parser::RespParser](parser::RespParser),
+//! which also reads from non-blocking sockets without losing the frames cut off when they would block.
+//!
+//! To convert other Rust types to and from [RESP](RESP) values in memory, use [value::to_value](value::to_value) and [value::from_value](value::from_value).
+//!
+//! To send and receive frames over any transport, use [connection::Connection](connection::Connection).
+//! For pipelined clients, [correlator::Correlator](correlator::Correlator) pairs commands with their replies.
+//! To forward frames without decoding them, read them as [LazyFrame](LazyFrame)s.
+//! To write frames piece by piece without building a [RESP](RESP) value first, use [encoder::Encoder](encoder::Encoder).
+//!
+//! Servers can recognize the commands of requests without allocating with [command::CommandTable](command::CommandTable).
+//! To route keys in Redis Cluster, [cluster::key_hash_slot](cluster::key_hash_slot) maps them to hash slots.
+//! To convert the replies of common commands, such as `HGETALL` and `TIME`, to Rust types, use [replies](replies).
+//! To find where two [RESP](RESP) values differ, use [diff::diff](diff::diff).
+//! To serve RESP2 clients with RESP3 replies, convert them with [RESP::downgrade_to_resp2](RESPType::downgrade_to_resp2),
+//! and the other way around with [RESP::upgrade_hint](RESPType::upgrade_hint).
+//! For fixtures and snapshots, [text](text) escapes values into single lines of text.
+//! For load and stress tests, [testing::Generator](testing::Generator) generates random values from a seed.
 //!
 //! For usage examples, refer to [RESP](RESP)
 //!
+//! ## Features
+//!
+//! - `diagnostics`: [miette](https://docs.rs/miette) diagnostics pointing at the bytes that failed to decode.
+//!   Refer to [diagnostics](diagnostics).
+//! - `arbitrary`: [arbitrary::Arbitrary](https://docs.rs/arbitrary) for [RESP](RESP), for fuzzing.
+//!   Refer to [testing](testing).
+//! - `proptest`: [proptest](https://docs.rs/proptest) strategies for [RESP](RESP) values and wire bytes.
+//!   Refer to [testing](testing).
+//! - `quickcheck`: [quickcheck::Arbitrary](https://docs.rs/quickcheck) for [RESP](RESP), with shrinking.
+//!   Refer to [testing](testing).
+//! - `redis-protocol`: conversions between [RESP](RESP) and the RESP2 and RESP3 frames of
+//!   [redis-protocol](https://docs.rs/redis-protocol), with `From` and `TryFrom`.
+//! - `mmap`: [de::from_file](de::from_file), which decodes large files through a memory map. It is `unsafe`,
+//!   as the file must not change while it is decoded.
+//! - `rayon`: [de::decode_batch_parallel](de::decode_batch_parallel), which decodes many values in parallel.
+//! - `json`: [webdis](webdis), the JSON mapping of Webdis, for HTTP gateways.
+//! - `cli`: the `resp-cli` binary, which decodes RESP into readable text, JSON or escaped literals, and encodes JSON or literals into RESP.
+//! - `tokio`: [de::from_async_reader](de::from_async_reader) and [ser::to_async_writer](ser::to_async_writer),
+//!   which read and write values over tokio's async IO traits.
+//! - `stream`: [stream](stream), a `Stream` of the frames read from a tokio `AsyncRead`.
+//! - `futures-io`: [de::from_futures_reader](de::from_futures_reader) and [ser::to_futures_writer](ser::to_futures_writer),
+//!   which read and write values over the async IO traits of futures-io, for async-std and smol, without tokio.
+//! - `sink`: [sink](sink), a `Sink` of the frames written to a tokio `AsyncWrite`, with backpressure.
+//! - `codec`: [codec](codec), a [tokio-util](https://docs.rs/tokio-util) codec to read and write frames through `Framed`.
+//!
+//! ## Matching on values
+//!
+//! Since 0.4.0, [RESPType](RESPType) and [RESPKind](RESPKind) are `#[non_exhaustive]`, so that
+//! new types, such as those of RESP3, can be added without a breaking release.
+//! A `match` on them outside this crate needs a wildcard arm:
+//! ```
+//! use serde_resp::{RESPKind, RESP};
+//!
+//! fn describe(value: &RESP) -> &'static str {
+//!     match value {
+//!         RESP::Integer(_) => "integer",
+//!         RESP::BulkString(None) | RESP::Array(None) => "null",
+//!         _ => match value.kind() {
+//!             RESPKind::SimpleString | RESPKind::Error => "line",
+//!             _ => "other",
+//!         },
+//!     }
+//! }
+//!
+//! assert_eq!("integer", describe(&RESP::int(1)));
+//! assert_eq!("line", describe(&RESP::simple("OK")));
+//! assert_eq!("null", describe(&RESP::bulk_null()));
+//! ```
+//! To migrate from 0.3, add such an arm to every exhaustive `match`, for example returning an error
+//! for types your code does not handle. Matching on [kind](RESPType::kind) is often shorter when
+//! the content does not matter. Building values is unchanged: use the variants directly, the macros
+//! below, or constructor functions such as [RESP::simple](RESPType::simple) and [RESP::array](RESPType::array).
+//!
+//! ## WebAssembly
+//!
+//! The crate works on `wasm32-unknown-unknown`. (De)serialize in memory with [ser::to_string](ser::to_string),
+//! or [ser::to_writer](ser::to_writer) into a `Vec<u8>`, and [de::from_str](de::from_str),
+//! or [de::from_reader](de::from_reader) from a `&[u8]`.
+//! [capture](capture) is not available there, as it needs the system clock.
+//!
 //! ## Macros
 //!
 //! Since 0.3.0, you can start using very handy macros! Here is a demo:
@@ -53,16 +171,69 @@
 //!     assert_eq!(resp_array, deserialized);
 //! ```
 
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+mod async_io;
+mod borrowed;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub mod capture;
+pub mod cluster;
+#[cfg(feature = "codec")]
+pub mod codec;
+pub mod command;
+pub mod connection;
+pub mod correlator;
 pub mod de;
+#[cfg(feature = "diagnostics")]
+pub mod diagnostics;
+pub mod diff;
+mod downgrade;
+pub mod encoder;
 mod error;
+mod lazy;
 mod macros;
+pub mod metrics;
+mod normalize;
+pub mod parser;
+#[cfg(feature = "redis-protocol")]
+mod redis_protocol_compat;
+pub mod replies;
 pub mod ser;
+mod shared;
+#[cfg(feature = "sink")]
+pub mod sink;
+mod stats;
+#[cfg(feature = "stream")]
+pub mod stream;
+pub mod testing;
+pub mod text;
+mod upgrade;
+pub mod value;
+mod walk;
+#[cfg(feature = "json")]
+pub mod webdis;
 
-pub use error::{Error, Result};
+#[doc(hidden)]
+pub use macros::support as __private;
+
+pub use borrowed::RESPRef;
+pub use error::{Error, ErrorKind, LimitKind, Result};
+pub use lazy::LazyFrame;
+pub use normalize::NormalizeOptions;
+pub use shared::SharedResp;
+pub use stats::Stats;
+pub use upgrade::UpgradeHint;
+pub use walk::Path;
 
 /// This enum creates a one-to-one type mapping with RESP types.
 /// Please only use variants of this type for (de)serialize operations.
-#[derive(Eq, PartialEq, Clone, Debug)]
+///
+/// New variants may be added in minor versions, so matches outside this crate need a wildcard arm.
+/// Refer to [Matching on values](crate#matching-on-values).
+///
+/// Values compare equal when they have the same type and content. Doubles are compared as numbers,
+/// except that NaN equals NaN, so that every value equals itself.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
 pub enum RESPType {
     /// Correspond to simple string in RESP.
     /// Also refer to [simple!](simple!) macro.
@@ -163,6 +334,10 @@ pub enum RESPType {
     /// // Null
     /// let deserialized: RESP = de::from_str("$-1\r\n").unwrap();
     /// assert_eq!(bulk_null!(), deserialized);
+    ///
+    /// // RESP3 streamed in chunks, read whole
+    /// let deserialized: RESP = de::from_str("$?\r\n;4\r\nfoob\r\n;2\r\nar\r\n;0\r\n").unwrap();
+    /// assert_eq!(bulk!(b"foobar".to_vec()), deserialized);
     /// ```
     BulkString(Option<Vec<u8>>),
     /// Correspond to array in RESP. Use None for null array and Some for non-null ones.
@@ -234,6 +409,15 @@ pub enum RESPType {
     ///     serialized
     /// );
     ///
+    /// // Spread an existing Vec (or iterator) and append more elements
+    /// let existing = vec![int!(1), int!(2)];
+    /// let obj = array![..existing, int!(3)];
+    /// assert_eq!(array![int!(1), int!(2), int!(3)], obj);
+    ///
+    /// let obj = array![..(1..=3).map(|i| int!(i))];
+    /// let serialized = ser::to_string(&obj).unwrap();
+    /// assert_eq!("*3\r\n:1\r\n:2\r\n:3\r\n".to_owned(), serialized);
+    ///
     /// // Null elements in Arrays
     /// let obj = array![
     ///     bulk!(b"foo".to_vec()),
@@ -278,9 +462,457 @@ pub enum RESPType {
     ///     ],
     /// ];
     /// assert_eq!(expected, deserialized);
+    ///
+    /// // RESP3 streamed, ending with `.`, read whole
+    /// let deserialized: RESP = de::from_str("*?\r\n:1\r\n:2\r\n.\r\n").unwrap();
+    /// assert_eq!(array![int!(1), int!(2)], deserialized);
     /// ```
     Array(Option<Vec<RESPType>>),
+    /// Correspond to double in RESP3, a floating point number such as `,3.14\r\n`.
+    ///
+    /// Infinities are written `inf` and `-inf`, and NaN `nan`. Very large and very small numbers
+    /// are written with an exponent, such as `1e300`.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{de, ser, RESP};
+    ///
+    /// /// Serialization
+    /// let serialized = ser::to_string(&RESP::Double(3.14159)).unwrap();
+    /// assert_eq!(",3.14159\r\n".to_owned(), serialized);
+    /// let serialized = ser::to_string(&RESP::Double(f64::NEG_INFINITY)).unwrap();
+    /// assert_eq!(",-inf\r\n".to_owned(), serialized);
+    ///
+    /// /// Deserialization
+    /// let deserialized: RESP = de::from_str(",1.5e3\r\n").unwrap();
+    /// assert_eq!(RESP::Double(1500.0), deserialized);
+    /// let deserialized: RESP = de::from_str(",nan\r\n").unwrap();
+    /// assert_eq!(RESP::Double(f64::NAN), deserialized);
+    /// ```
+    Double(f64),
+    /// Correspond to boolean in RESP3, `#t\r\n` or `#f\r\n`.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{de, ser, RESP};
+    ///
+    /// /// Serialization
+    /// let serialized = ser::to_string(&RESP::Boolean(true)).unwrap();
+    /// assert_eq!("#t\r\n".to_owned(), serialized);
+    ///
+    /// /// Deserialization
+    /// let deserialized: RESP = de::from_str("#f\r\n").unwrap();
+    /// assert_eq!(RESP::Boolean(false), deserialized);
+    /// ```
+    Boolean(bool),
+    /// Correspond to big number in RESP3, an integer of any size such as `(3492890328409238509324850943850943825024385\r\n`.
+    ///
+    /// The number is kept as its decimal text, an optional `-` or `+` sign followed by digits.
+    /// To get it as a Rust integer, parse the text, or use [value::from_value](value::from_value)
+    /// with an integer type large enough for it, such as `i128`.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{de, ser, value, RESP};
+    ///
+    /// /// Serialization
+    /// let obj = RESP::big_number("3492890328409238509324850943850943825024385");
+    /// let serialized = ser::to_string(&obj).unwrap();
+    /// assert_eq!("(3492890328409238509324850943850943825024385\r\n".to_owned(), serialized);
+    ///
+    /// /// Deserialization
+    /// let deserialized: RESP = de::from_str("(-170141183460469231731687303715884105728\r\n").unwrap();
+    /// assert_eq!(RESP::big_number("-170141183460469231731687303715884105728"), deserialized);
+    /// assert_eq!(i128::MIN, value::from_value::<i128>(deserialized).unwrap());
+    ///
+    /// assert!(de::from_str::<RESP>("(12a\r\n").unwrap_err().is_protocol());
+    /// ```
+    BigNumber(String),
+    /// Correspond to verbatim string in RESP3, a bulk string with a format such as `=15\r\ntxt:Some string\r\n`.
+    ///
+    /// `format` is three characters, `txt` for plain text or `mkd` for markdown, and `data` is the text
+    /// after the `:`. Serializing fails if `format` is not three bytes long.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{de, ser, RESP};
+    ///
+    /// /// Serialization
+    /// let obj = RESP::verbatim("txt", &b"Some string"[..]);
+    /// let serialized = ser::to_string(&obj).unwrap();
+    /// assert_eq!("=15\r\ntxt:Some string\r\n".to_owned(), serialized);
+    /// assert!(ser::to_string(&RESP::verbatim("text", &b""[..])).is_err());
+    ///
+    /// /// Deserialization
+    /// let deserialized: RESP = de::from_str("=10\r\nmkd:# Tips\r\n").unwrap();
+    /// assert_eq!(
+    ///     RESP::Verbatim {
+    ///         format: "mkd".to_owned(),
+    ///         data: b"# Tips".to_vec(),
+    ///     },
+    ///     deserialized
+    /// );
+    /// assert!(de::from_str::<RESP>("=5\r\ntxt-a\r\n").unwrap_err().is_protocol());
+    /// ```
+    Verbatim {
+        /// The format of `data`, such as `txt`.
+        format: String,
+        /// The text.
+        data: Vec<u8>,
+    },
+    /// Correspond to null in RESP3, `_\r\n`, which replaces both null bulk strings and null arrays.
+    ///
+    /// Like them, it deserializes to `None` when an `Option` is expected.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{de, ser, value, RESP};
+    ///
+    /// /// Serialization
+    /// let serialized = ser::to_string(&RESP::Null).unwrap();
+    /// assert_eq!("_\r\n".to_owned(), serialized);
+    ///
+    /// /// Deserialization
+    /// let deserialized: RESP = de::from_str("_\r\n").unwrap();
+    /// assert_eq!(RESP::Null, deserialized);
+    /// assert_eq!(None, value::from_value::<Option<String>>(deserialized).unwrap());
+    /// ```
+    Null,
+    /// Correspond to push in RESP3, an array of elements sent out of band, such as pub/sub messages
+    /// and client-side caching invalidations: `>3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$2\r\nhi\r\n`.
+    ///
+    /// Pushes can arrive between a request and its reply, so clients must tell them apart from replies.
+    /// [Connection](connection::Connection) and [Correlator](correlator::Correlator) keep them aside.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{bulk, de, ser, RESP};
+    ///
+    /// /// Serialization
+    /// let obj = RESP::Push(vec![bulk!(b"message".to_vec()), bulk!(b"news".to_vec()), bulk!(b"hi".to_vec())]);
+    /// let serialized = ser::to_string(&obj).unwrap();
+    /// assert_eq!(">3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$2\r\nhi\r\n".to_owned(), serialized);
+    ///
+    /// /// Deserialization
+    /// let deserialized: RESP = de::from_str(">2\r\n$10\r\ninvalidate\r\n*1\r\n$3\r\nfoo\r\n").unwrap();
+    /// assert_eq!(
+    ///     RESP::Push(vec![bulk!(b"invalidate".to_vec()), RESP::array(vec![bulk!(b"foo".to_vec())])]),
+    ///     deserialized
+    /// );
+    /// ```
+    Push(Vec<RESPType>),
+    /// Correspond to attribute in RESP3: keys and values of metadata about a reply, sent just before it,
+    /// such as `|1\r\n+ttl\r\n:3600\r\n+OK\r\n`. The reply is kept in `value`.
+    ///
+    /// Clients that do not use the metadata can take `value` and ignore the rest.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{de, int, ser, simple, RESP};
+    ///
+    /// /// Serialization
+    /// let obj = RESP::attribute(vec![(simple!("ttl".to_owned()), int!(3600))], simple!("OK".to_owned()));
+    /// let serialized = ser::to_string(&obj).unwrap();
+    /// assert_eq!("|1\r\n+ttl\r\n:3600\r\n+OK\r\n".to_owned(), serialized);
+    ///
+    /// /// Deserialization
+    /// let deserialized: RESP = de::from_str("|1\r\n+popularity\r\n,0.5\r\n:42\r\n").unwrap();
+    /// match deserialized {
+    ///     RESP::Attribute { attributes, value } => {
+    ///         assert_eq!(vec![(simple!("popularity".to_owned()), RESP::Double(0.5))], attributes);
+    ///         assert_eq!(int!(42), *value);
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    Attribute {
+        /// The keys and values of the metadata, in the order received.
+        attributes: Vec<(RESPType, RESPType)>,
+        /// The reply the metadata is about.
+        value: Box<RESPType>,
+    },
+    /// Correspond to map in RESP3, keys and values such as the reply of `HGETALL` or `HELLO`:
+    /// `%2\r\n+first\r\n:1\r\n+second\r\n:2\r\n`. RESP2 replies the same as a flat array of keys and values.
+    ///
+    /// The keys and values are kept in the order received, and keys given twice are kept twice.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{de, int, ser, simple, RESP};
+    ///
+    /// /// Serialization
+    /// let obj = RESP::Map(vec![(simple!("first".to_owned()), int!(1)), (simple!("second".to_owned()), int!(2))]);
+    /// let serialized = ser::to_string(&obj).unwrap();
+    /// assert_eq!("%2\r\n+first\r\n:1\r\n+second\r\n:2\r\n".to_owned(), serialized);
+    ///
+    /// /// Deserialization
+    /// let deserialized: RESP = de::from_str("%1\r\n+proto\r\n:3\r\n").unwrap();
+    /// assert_eq!(RESP::Map(vec![(simple!("proto".to_owned()), int!(3))]), deserialized);
+    /// ```
+    Map(Vec<(RESPType, RESPType)>),
+    /// Correspond to set in RESP3, elements in no particular order, such as the reply of `SMEMBERS`:
+    /// `~2\r\n+orange\r\n+apple\r\n`. RESP2 replies the same as an array.
+    ///
+    /// The elements are kept in the order received. To compare sets regardless of order,
+    /// [normalize](RESPType::normalize) them first.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{de, ser, simple, RESP};
+    ///
+    /// /// Serialization
+    /// let obj = RESP::Set(vec![simple!("orange".to_owned()), simple!("apple".to_owned())]);
+    /// let serialized = ser::to_string(&obj).unwrap();
+    /// assert_eq!("~2\r\n+orange\r\n+apple\r\n".to_owned(), serialized);
+    ///
+    /// /// Deserialization
+    /// let deserialized: RESP = de::from_str("~1\r\n+apple\r\n").unwrap();
+    /// assert_eq!(RESP::Set(vec![simple!("apple".to_owned())]), deserialized);
+    /// ```
+    Set(Vec<RESPType>),
+}
+
+impl PartialEq for RESPType {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (RESPType::SimpleString(l), RESPType::SimpleString(r)) => l == r,
+            (RESPType::Error(l), RESPType::Error(r)) => l == r,
+            (RESPType::Integer(l), RESPType::Integer(r)) => l == r,
+            (RESPType::BulkString(l), RESPType::BulkString(r)) => l == r,
+            (RESPType::Array(l), RESPType::Array(r)) => l == r,
+            (RESPType::Double(l), RESPType::Double(r)) => l == r || (l.is_nan() && r.is_nan()),
+            (RESPType::Boolean(l), RESPType::Boolean(r)) => l == r,
+            (RESPType::BigNumber(l), RESPType::BigNumber(r)) => l == r,
+            (
+                RESPType::Verbatim { format, data },
+                RESPType::Verbatim {
+                    format: r_format,
+                    data: r_data,
+                },
+            ) => format == r_format && data == r_data,
+            (RESPType::Null, RESPType::Null) => true,
+            (RESPType::Push(l), RESPType::Push(r)) => l == r,
+            (
+                RESPType::Attribute { attributes, value },
+                RESPType::Attribute {
+                    attributes: r_attributes,
+                    value: r_value,
+                },
+            ) => attributes == r_attributes && value == r_value,
+            (RESPType::Map(l), RESPType::Map(r)) => l == r,
+            (RESPType::Set(l), RESPType::Set(r)) => l == r,
+            _ => false,
+        }
+    }
 }
 
+impl Eq for RESPType {}
+
 /// Refer to [RESPType](RESPType). This is just an alias.
 pub type RESP = RESPType;
+
+/// Type of a [RESPType](RESPType) value, without its content. Null values have the type of their non-null counterpart.
+///
+/// Like [RESPType](RESPType), new variants may be added in minor versions.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum RESPKind {
+    /// [RESPType::SimpleString](RESPType::SimpleString)
+    SimpleString,
+    /// [RESPType::Error](RESPType::Error)
+    Error,
+    /// [RESPType::Integer](RESPType::Integer)
+    Integer,
+    /// [RESPType::BulkString](RESPType::BulkString)
+    BulkString,
+    /// [RESPType::Array](RESPType::Array)
+    Array,
+    /// [RESPType::Double](RESPType::Double)
+    Double,
+    /// [RESPType::Boolean](RESPType::Boolean)
+    Boolean,
+    /// [RESPType::BigNumber](RESPType::BigNumber)
+    BigNumber,
+    /// [RESPType::Verbatim](RESPType::Verbatim)
+    Verbatim,
+    /// [RESPType::Null](RESPType::Null)
+    Null,
+    /// [RESPType::Push](RESPType::Push)
+    Push,
+    /// [RESPType::Attribute](RESPType::Attribute)
+    Attribute,
+    /// [RESPType::Map](RESPType::Map)
+    Map,
+    /// [RESPType::Set](RESPType::Set)
+    Set,
+}
+
+impl RESPType {
+    /// Type of this value.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{bulk_null, int, RESPKind, RESP};
+    ///
+    /// assert_eq!(RESPKind::Integer, int!(1).kind());
+    /// assert_eq!(RESPKind::BulkString, bulk_null!().kind());
+    /// ```
+    pub fn kind(&self) -> RESPKind {
+        match self {
+            RESPType::SimpleString(_) => RESPKind::SimpleString,
+            RESPType::Error(_) => RESPKind::Error,
+            RESPType::Integer(_) => RESPKind::Integer,
+            RESPType::BulkString(_) => RESPKind::BulkString,
+            RESPType::Array(_) => RESPKind::Array,
+            RESPType::Double(_) => RESPKind::Double,
+            RESPType::Boolean(_) => RESPKind::Boolean,
+            RESPType::BigNumber(_) => RESPKind::BigNumber,
+            RESPType::Verbatim { .. } => RESPKind::Verbatim,
+            RESPType::Null => RESPKind::Null,
+            RESPType::Push(_) => RESPKind::Push,
+            RESPType::Attribute { .. } => RESPKind::Attribute,
+            RESPType::Map(_) => RESPKind::Map,
+            RESPType::Set(_) => RESPKind::Set,
+        }
+    }
+
+    /// Same as [simple!](simple!), accepting anything that converts into a `String`.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{array, bulk, bulk_null, int, simple, RESP};
+    ///
+    /// assert_eq!(simple!("OK".to_owned()), RESP::simple("OK"));
+    /// assert_eq!(int!(1), RESP::int(1));
+    /// assert_eq!(bulk!(b"foo".to_vec()), RESP::bulk(&b"foo"[..]));
+    /// assert_eq!(
+    ///     array![int!(1), int!(2), bulk_null!()],
+    ///     RESP::array(vec![RESP::int(1), RESP::int(2), RESP::bulk_null()])
+    /// );
+    /// ```
+    pub fn simple(s: impl Into<String>) -> Self {
+        RESPType::SimpleString(s.into())
+    }
+
+    /// Same as [err_str!](err_str!), accepting anything that converts into a `String`.
+    pub fn err_str(s: impl Into<String>) -> Self {
+        RESPType::Error(s.into())
+    }
+
+    /// Same as [int!](int!).
+    pub fn int(i: i64) -> Self {
+        RESPType::Integer(i)
+    }
+
+    /// Same as [bulk!](bulk!), accepting anything that converts into a `Vec<u8>`.
+    pub fn bulk(bytes: impl Into<Vec<u8>>) -> Self {
+        RESPType::BulkString(Some(bytes.into()))
+    }
+
+    /// Same as [bulk_null!](bulk_null!).
+    pub fn bulk_null() -> Self {
+        RESPType::BulkString(None)
+    }
+
+    /// Same as [array!](array!), collecting the elements from an iterator.
+    pub fn array(elements: impl IntoIterator<Item = RESPType>) -> Self {
+        RESPType::Array(Some(elements.into_iter().collect()))
+    }
+
+    /// Same as [array_null!](array_null!).
+    pub fn array_null() -> Self {
+        RESPType::Array(None)
+    }
+
+    /// A RESP3 [double](RESPType::Double).
+    pub fn double(v: f64) -> Self {
+        RESPType::Double(v)
+    }
+
+    /// A RESP3 [boolean](RESPType::Boolean).
+    pub fn boolean(v: bool) -> Self {
+        RESPType::Boolean(v)
+    }
+
+    /// A RESP3 [big number](RESPType::BigNumber), from its decimal text.
+    pub fn big_number(digits: impl Into<String>) -> Self {
+        RESPType::BigNumber(digits.into())
+    }
+
+    /// A RESP3 [verbatim string](RESPType::Verbatim) of `data` in `format`, such as `txt`.
+    pub fn verbatim(format: impl Into<String>, data: impl Into<Vec<u8>>) -> Self {
+        RESPType::Verbatim {
+            format: format.into(),
+            data: data.into(),
+        }
+    }
+
+    /// A RESP3 [push](RESPType::Push), collecting the elements from an iterator.
+    pub fn push(elements: impl IntoIterator<Item = RESPType>) -> Self {
+        RESPType::Push(elements.into_iter().collect())
+    }
+
+    /// A RESP3 [attribute](RESPType::Attribute) of `value`, collecting the keys and values from an iterator.
+    pub fn attribute(
+        attributes: impl IntoIterator<Item = (RESPType, RESPType)>,
+        value: RESPType,
+    ) -> Self {
+        RESPType::Attribute {
+            attributes: attributes.into_iter().collect(),
+            value: Box::new(value),
+        }
+    }
+
+    /// A RESP3 [map](RESPType::Map), collecting the keys and values from an iterator.
+    pub fn map(pairs: impl IntoIterator<Item = (RESPType, RESPType)>) -> Self {
+        RESPType::Map(pairs.into_iter().collect())
+    }
+
+    /// A RESP3 [set](RESPType::Set), collecting the elements from an iterator.
+    pub fn set(elements: impl IntoIterator<Item = RESPType>) -> Self {
+        RESPType::Set(elements.into_iter().collect())
+    }
+}
+
+impl RESPKind {
+    /// The byte a value of this type starts with on the wire.
+    pub fn type_byte(self) -> u8 {
+        match self {
+            RESPKind::SimpleString => b'+',
+            RESPKind::Error => b'-',
+            RESPKind::Integer => b':',
+            RESPKind::BulkString => b'$',
+            RESPKind::Array => b'*',
+            RESPKind::Double => b',',
+            RESPKind::Boolean => b'#',
+            RESPKind::BigNumber => b'(',
+            RESPKind::Verbatim => b'=',
+            RESPKind::Null => b'_',
+            RESPKind::Push => b'>',
+            RESPKind::Attribute => b'|',
+            RESPKind::Map => b'%',
+            RESPKind::Set => b'~',
+        }
+    }
+
+    /// The type whose values start with `byte` on the wire.
+    pub fn from_type_byte(byte: u8) -> Option<RESPKind> {
+        match byte {
+            b'+' => Some(RESPKind::SimpleString),
+            b'-' => Some(RESPKind::Error),
+            b':' => Some(RESPKind::Integer),
+            b'$' => Some(RESPKind::BulkString),
+            b'*' => Some(RESPKind::Array),
+            b',' => Some(RESPKind::Double),
+            b'#' => Some(RESPKind::Boolean),
+            b'(' => Some(RESPKind::BigNumber),
+            b'=' => Some(RESPKind::Verbatim),
+            b'_' => Some(RESPKind::Null),
+            b'>' => Some(RESPKind::Push),
+            b'|' => Some(RESPKind::Attribute),
+            b'%' => Some(RESPKind::Map),
+            b'~' => Some(RESPKind::Set),
+            _ => None,
+        }
+    }
+}
@@ -41,8 +41,16 @@ macro_rules! bulk_null {
 }
 
 /// `array![...]` is equivalent to `RESP::Array(Some(vec![...]))`.
+///
+/// `array![..iter, ...]` starts from an existing `Vec<RESP>` (or any `IntoIterator<Item = RESP>`)
+/// and appends the remaining elements to it.
 #[macro_export]
 macro_rules! array {
+    (..$iter:expr $(, $x:expr)* $(,)?) => {{
+        let mut arr: Vec<RESP> = ::std::iter::IntoIterator::into_iter($iter).collect();
+        $(arr.push($x);)*
+        RESP::Array(Some(arr))
+    }};
     ($($x:expr),* $(,)?) => {
         RESP::Array(Some(vec![$($x),*]))
     };
@@ -55,3 +63,291 @@ macro_rules! array_null {
         RESP::Array(None)
     };
 }
+
+/// `resp_bytes!(...)` serializes a literal RESP value at compile time into a `&'static [u8]`.
+///
+/// The value is written as the name of the corresponding macro followed by its argument:
+/// `simple "..."`, `err_str "..."`, `int ...`, `bulk b"..."`, `bulk_null`, `array [...]` and `array_null`.
+/// Arguments must be constant expressions. Inside `array [...]`, arguments that span more than
+/// one token (such as negative integers) need to be wrapped in parentheses.
+///
+/// Simple strings and errors containing CR or LF are rejected at compile time.
+///
+/// # Examples
+/// ```
+/// use serde_resp::resp_bytes;
+///
+/// const OK: &[u8] = resp_bytes!(simple "OK");
+/// assert_eq!(b"+OK\r\n", OK);
+///
+/// const PONG: &[u8] = resp_bytes!(bulk b"PONG");
+/// assert_eq!(b"$4\r\nPONG\r\n", PONG);
+///
+/// const REPLY: &[u8] = resp_bytes!(array [int (-1), bulk_null, array [err_str "ERR"], array_null]);
+/// assert_eq!(b"*4\r\n:-1\r\n$-1\r\n*1\r\n-ERR\r\n*-1\r\n", REPLY);
+/// ```
+///
+/// ```compile_fail
+/// use serde_resp::resp_bytes;
+///
+/// const BAD: &[u8] = resp_bytes!(simple "OK\r\n+INJECTED");
+/// ```
+#[macro_export]
+macro_rules! resp_bytes {
+    (simple $s:expr) => {
+        $crate::resp_bytes!(@frame [b"+", $crate::__private::line($s).as_bytes(), b"\r\n"])
+    };
+    (err_str $s:expr) => {
+        $crate::resp_bytes!(@frame [b"-", $crate::__private::line($s).as_bytes(), b"\r\n"])
+    };
+    (int $x:expr) => {
+        $crate::resp_bytes!(@frame [b":", @int $x, b"\r\n"])
+    };
+    (bulk $x:expr) => {
+        $crate::resp_bytes!(@frame [b"$", @int $x.len() as i64, b"\r\n", $x, b"\r\n"])
+    };
+    (bulk_null) => {
+        $crate::resp_bytes!(@frame [b"$-1\r\n"])
+    };
+    (array [$($elems:tt)*]) => {
+        $crate::resp_bytes!(@array [] [] $($elems)*)
+    };
+    (array_null) => {
+        $crate::resp_bytes!(@frame [b"*-1\r\n"])
+    };
+    // Munch array elements one at a time, collecting their frames and a unit per element.
+    (@array [$($done:tt)*] [$($unit:tt)*] $kind:ident $(, $($rest:tt)*)?) => {
+        $crate::resp_bytes!(@array [$($done)* , $crate::resp_bytes!($kind)] [$($unit)* (),] $($($rest)*)?)
+    };
+    (@array [$($done:tt)*] [$($unit:tt)*] $kind:ident $arg:tt $(, $($rest:tt)*)?) => {
+        $crate::resp_bytes!(@array [$($done)* , $crate::resp_bytes!($kind $arg)] [$($unit)* (),] $($($rest)*)?)
+    };
+    (@array [$($done:tt)*] [$($unit:tt)*]) => {
+        $crate::resp_bytes!(@frame [b"*", @int <[()]>::len(&[$($unit)*]) as i64, b"\r\n" $($done)*])
+    };
+    (@frame [$($(@$int:ident)? $part:expr),*]) => {{
+        const PARTS: &[$crate::__private::Part<'static>] =
+            &[$($crate::resp_bytes!(@part $(@$int)? $part)),*];
+        const LEN: usize = $crate::__private::frame_len(PARTS);
+        const FRAME: [u8; LEN] = $crate::__private::frame::<LEN>(PARTS);
+        const BYTES: &[u8] = &FRAME;
+        BYTES
+    }};
+    (@part @int $x:expr) => {
+        $crate::__private::Part::Int($x)
+    };
+    (@part $x:expr) => {
+        $crate::__private::Part::Bytes($x)
+    };
+}
+
+/// `assert_resp_eq!(left, right)` asserts that two RESP values are equal.
+///
+/// Either side can be a [RESPType](crate::RESPType) or its wire format (`&str`, `&[u8]`, `Vec<u8>`, ...),
+/// which is deserialized before comparing and must hold exactly one frame. On mismatch, the panic message points at the first
+/// element where the two values diverge, as found by [diff::diff](crate::diff::diff).
+/// Like `assert_eq!`, a custom message can be passed after the two values.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, assert_resp_eq, bulk, int, RESP};
+///
+/// let reply = array![int!(1), bulk!(b"foo".to_vec())];
+/// assert_resp_eq!(reply, array![int!(1), bulk!(b"foo".to_vec())]);
+/// assert_resp_eq!(reply, b"*2\r\n:1\r\n$3\r\nfoo\r\n");
+/// assert_resp_eq!("*2\r\n:1\r\n$3\r\nfoo\r\n", reply, "reply to {}", "GET");
+/// ```
+///
+/// ```should_panic
+/// use serde_resp::{array, assert_resp_eq, int, RESP};
+///
+/// // panics with "... diverged at [1][0]: left is integer 2, right is integer 3 ..."
+/// assert_resp_eq!(array![int!(1), array![int!(2)]], array![int!(1), array![int!(3)]]);
+/// ```
+///
+/// ```should_panic
+/// use serde_resp::{assert_resp_eq, simple, RESP};
+///
+/// // panics with "cannot deserialize ... trailing bytes after the value ...": there is a second frame
+/// assert_resp_eq!(simple!("OK".to_owned()), b"+OK\r\n+EXTRA\r\n");
+/// ```
+#[macro_export]
+macro_rules! assert_resp_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::__private::assert_resp_eq(&$left, &$right, ::std::option::Option::None)
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        $crate::__private::assert_resp_eq(
+            &$left,
+            &$right,
+            ::std::option::Option::Some(::std::format_args!($($arg)+)),
+        )
+    };
+}
+
+// Support code for the macros above. Not part of the public API.
+#[doc(hidden)]
+pub mod support {
+    use crate::{de, diff, RESPType};
+    use serde::Deserialize;
+    use std::borrow::Cow;
+    use std::fmt;
+
+    // Anything `assert_resp_eq!` can compare: values, or wire bytes to be deserialized.
+    pub trait ToResp {
+        fn to_resp(&self) -> Cow<'_, RESPType>;
+    }
+
+    impl ToResp for RESPType {
+        fn to_resp(&self) -> Cow<'_, RESPType> {
+            Cow::Borrowed(self)
+        }
+    }
+
+    impl ToResp for [u8] {
+        fn to_resp(&self) -> Cow<'_, RESPType> {
+            let mut deserializer = de::Deserializer::from_buf_reader(self);
+            let value = RESPType::deserialize(&mut deserializer).and_then(|value| {
+                deserializer.end()?;
+                Ok(value)
+            });
+            match value {
+                Ok(value) => Cow::Owned(value),
+                Err(e) => panic!(
+                    "cannot deserialize b\"{}\" as RESP: {:#}",
+                    self.escape_ascii(),
+                    e
+                ),
+            }
+        }
+    }
+
+    impl<const N: usize> ToResp for [u8; N] {
+        fn to_resp(&self) -> Cow<'_, RESPType> {
+            self[..].to_resp()
+        }
+    }
+
+    impl ToResp for Vec<u8> {
+        fn to_resp(&self) -> Cow<'_, RESPType> {
+            self[..].to_resp()
+        }
+    }
+
+    impl ToResp for str {
+        fn to_resp(&self) -> Cow<'_, RESPType> {
+            self.as_bytes().to_resp()
+        }
+    }
+
+    impl ToResp for String {
+        fn to_resp(&self) -> Cow<'_, RESPType> {
+            self.as_bytes().to_resp()
+        }
+    }
+
+    impl<T: ToResp + ?Sized> ToResp for &T {
+        fn to_resp(&self) -> Cow<'_, RESPType> {
+            (**self).to_resp()
+        }
+    }
+
+    #[track_caller]
+    pub fn assert_resp_eq<L, R>(left: &L, right: &R, msg: Option<fmt::Arguments>)
+    where
+        L: ToResp + ?Sized,
+        R: ToResp + ?Sized,
+    {
+        let (left, right) = (left.to_resp(), right.to_resp());
+        if let Some(difference) = diff::diff(&left, &right) {
+            let msg = msg.map(|msg| format!(": {}", msg)).unwrap_or_default();
+            panic!(
+                "assertion failed: `(left == right)`{}\n diverged {}\n  left: `{:?}`,\n right: `{:?}`",
+                msg, difference, left, right
+            );
+        }
+    }
+
+    // A piece of a frame built by `resp_bytes!`.
+    #[derive(Clone, Copy)]
+    pub enum Part<'a> {
+        Bytes(&'a [u8]),
+        Int(i64),
+    }
+
+    // Reject simple strings and errors that would break out of their line.
+    pub const fn line(s: &str) -> &str {
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'\r' || bytes[i] == b'\n' {
+                panic!("simple strings and errors cannot contain CR or LF");
+            }
+            i += 1;
+        }
+        s
+    }
+
+    const fn digits(mut v: u64) -> usize {
+        let mut n = 1;
+        while v >= 10 {
+            v /= 10;
+            n += 1;
+        }
+        n
+    }
+
+    const fn int_len(v: i64) -> usize {
+        digits(v.unsigned_abs()) + (v < 0) as usize
+    }
+
+    // Length in bytes of the frame made of `parts`.
+    pub const fn frame_len(parts: &[Part]) -> usize {
+        let mut len = 0;
+        let mut i = 0;
+        while i < parts.len() {
+            len += match parts[i] {
+                Part::Bytes(b) => b.len(),
+                Part::Int(v) => int_len(v),
+            };
+            i += 1;
+        }
+        len
+    }
+
+    // Concatenate `parts` into a frame. `N` must be `frame_len(parts)`.
+    pub const fn frame<const N: usize>(parts: &[Part]) -> [u8; N] {
+        let mut buf = [0u8; N];
+        let mut pos = 0;
+        let mut i = 0;
+        while i < parts.len() {
+            match parts[i] {
+                Part::Bytes(b) => {
+                    let mut j = 0;
+                    while j < b.len() {
+                        buf[pos] = b[j];
+                        pos += 1;
+                        j += 1;
+                    }
+                }
+                Part::Int(v) => {
+                    if v < 0 {
+                        buf[pos] = b'-';
+                        pos += 1;
+                    }
+                    let mut u = v.unsigned_abs();
+                    let n = digits(u);
+                    let mut j = n;
+                    while j > 0 {
+                        j -= 1;
+                        buf[pos + j] = b'0' + (u % 10) as u8;
+                        u /= 10;
+                    }
+                    pos += n;
+                }
+            }
+            i += 1;
+        }
+        buf
+    }
+}
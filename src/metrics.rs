@@ -0,0 +1,195 @@
+//! Hooks for accounting and checksumming (de)serialized traffic, e.g. with Prometheus counters.
+//!
+//! Implement [Metrics](Metrics) and attach it with [Serializer::with_metrics](crate::ser::Serializer::with_metrics)
+//! or [Deserializer::with_metrics](crate::de::Deserializer::with_metrics).
+//! Only those two report to it, see [Metrics](Metrics) for the other readers and writers.
+//!
+//! # Examples
+//! ```
+//! use serde::{Deserialize, Serialize};
+//! use serde_resp::de::Deserializer;
+//! use serde_resp::metrics::Metrics;
+//! use serde_resp::ser::Serializer;
+//! use serde_resp::{simple, RESPKind, RESP};
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//!
+//! #[derive(Default)]
+//! struct Counters {
+//!     bytes: AtomicUsize,
+//!     simple_strings: AtomicUsize,
+//! }
+//!
+//! impl Metrics for Counters {
+//!     fn bytes_read(&self, n: usize) {
+//!         self.bytes.fetch_add(n, Ordering::Relaxed);
+//!     }
+//!
+//!     fn bytes_written(&self, n: usize) {
+//!         self.bytes.fetch_add(n, Ordering::Relaxed);
+//!     }
+//!
+//!     fn frame_read(&self, kind: RESPKind) {
+//!         if kind == RESPKind::SimpleString {
+//!             self.simple_strings.fetch_add(1, Ordering::Relaxed);
+//!         }
+//!     }
+//! }
+//!
+//! let counters = Counters::default();
+//!
+//! let mut buf = Vec::new();
+//! let mut serializer = Serializer::new(&mut buf).with_metrics(&counters);
+//! simple!("OK".to_owned()).serialize(&mut serializer).unwrap();
+//!
+//! let mut reader = &buf[..];
+//! let mut deserializer = Deserializer::from_buf_reader(&mut reader).with_metrics(&counters);
+//! RESP::deserialize(&mut deserializer).unwrap();
+//!
+//! assert_eq!(10, counters.bytes.load(Ordering::Relaxed));
+//! assert_eq!(1, counters.simple_strings.load(Ordering::Relaxed));
+//! ```
+//!
+//! The bytes themselves are reported too, so traffic can be checksummed per frame without a second pass,
+//! e.g. with a CRC32 or xxHash hasher. Here with FNV-1a:
+//! ```
+//! use serde::Serialize;
+//! use serde_resp::metrics::Metrics;
+//! use serde_resp::ser::Serializer;
+//! use serde_resp::{int, simple, RESPKind, RESP};
+//! use std::cell::{Cell, RefCell};
+//!
+//! struct Checksums {
+//!     hash: Cell<u32>,
+//!     frames: RefCell<Vec<u32>>,
+//! }
+//!
+//! impl Metrics for Checksums {
+//!     fn data_written(&self, data: &[u8]) {
+//!         let hash = data.iter().fold(self.hash.get(), |hash, &b| (hash ^ b as u32).wrapping_mul(16777619));
+//!         self.hash.set(hash);
+//!     }
+//!
+//!     fn frame_written(&self, _kind: RESPKind) {
+//!         self.frames.borrow_mut().push(self.hash.replace(2166136261));
+//!     }
+//! }
+//!
+//! let checksums = Checksums { hash: Cell::new(2166136261), frames: RefCell::new(Vec::new()) };
+//! let mut serializer = Serializer::new(Vec::new()).with_metrics(&checksums);
+//! simple!("OK".to_owned()).serialize(&mut serializer).unwrap();
+//! int!(1).serialize(&mut serializer).unwrap();
+//! simple!("OK".to_owned()).serialize(&mut serializer).unwrap();
+//! let frames = checksums.frames.borrow();
+//! assert_eq!(3, frames.len());
+//! assert_eq!(frames[0], frames[2]);
+//! assert_ne!(frames[0], frames[1]);
+//! ```
+
+use crate::{ErrorKind, RESPKind};
+
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Receives events from a [Serializer](crate::ser::Serializer) or [Deserializer](crate::de::Deserializer).
+///
+/// All methods do nothing by default. Frames are top-level values; elements of arrays are not reported separately.
+/// `()` is the implementation used when no metrics are attached.
+///
+/// Metrics cannot be attached to [RespParser](crate::parser::RespParser), nor to the `RespCodec`,
+/// `RespStream` and `RespSink` of features `codec`, `stream` and `sink`, as they do not expose the
+/// serializer or deserializer they use. Count the frames they return or are given instead, and the bytes
+/// where they are read or written, such as by wrapping the reader and writer.
+pub trait Metrics {
+    /// `n` bytes were read from the reader.
+    fn bytes_read(&self, _n: usize) {}
+
+    /// `n` bytes were written to the writer.
+    fn bytes_written(&self, _n: usize) {}
+
+    /// These bytes were read from the reader, right after [bytes_read](Metrics::bytes_read) reported their number.
+    ///
+    /// Together with [frame_read](Metrics::frame_read), this allows checksumming the exact bytes of each frame.
+    fn data_read(&self, _data: &[u8]) {}
+
+    /// These bytes were written to the writer, right after [bytes_written](Metrics::bytes_written) reported their number.
+    fn data_written(&self, _data: &[u8]) {}
+
+    /// A complete frame was read.
+    fn frame_read(&self, _kind: RESPKind) {}
+
+    /// A complete frame was written.
+    fn frame_written(&self, _kind: RESPKind) {}
+
+    /// Reading or writing a frame failed.
+    fn error(&self, _kind: ErrorKind) {}
+}
+
+impl Metrics for () {}
+
+impl<M: Metrics + ?Sized> Metrics for &M {
+    fn bytes_read(&self, n: usize) {
+        (**self).bytes_read(n)
+    }
+
+    fn bytes_written(&self, n: usize) {
+        (**self).bytes_written(n)
+    }
+
+    fn data_read(&self, data: &[u8]) {
+        (**self).data_read(data)
+    }
+
+    fn data_written(&self, data: &[u8]) {
+        (**self).data_written(data)
+    }
+
+    fn frame_read(&self, kind: RESPKind) {
+        (**self).frame_read(kind)
+    }
+
+    fn frame_written(&self, kind: RESPKind) {
+        (**self).frame_written(kind)
+    }
+
+    fn error(&self, kind: ErrorKind) {
+        (**self).error(kind)
+    }
+}
+
+macro_rules! forward_metrics {
+    ($($ptr:ident),*) => {
+        $(
+            impl<M: Metrics + ?Sized> Metrics for $ptr<M> {
+                fn bytes_read(&self, n: usize) {
+                    (**self).bytes_read(n)
+                }
+
+                fn bytes_written(&self, n: usize) {
+                    (**self).bytes_written(n)
+                }
+
+                fn data_read(&self, data: &[u8]) {
+                    (**self).data_read(data)
+                }
+
+                fn data_written(&self, data: &[u8]) {
+                    (**self).data_written(data)
+                }
+
+                fn frame_read(&self, kind: RESPKind) {
+                    (**self).frame_read(kind)
+                }
+
+                fn frame_written(&self, kind: RESPKind) {
+                    (**self).frame_written(kind)
+                }
+
+                fn error(&self, kind: ErrorKind) {
+                    (**self).error(kind)
+                }
+            }
+        )*
+    };
+}
+
+forward_metrics!(Box, Rc, Arc);
@@ -0,0 +1,71 @@
+use crate::{ser, RESPType};
+
+/// What [RESPType::normalize_with](RESPType::normalize_with) changes besides ordering.
+///
+/// By default, only ordering is normalized.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct NormalizeOptions {
+    /// Turn null arrays and RESP3 nulls into null bulk strings, so all nulls compare equal.
+    pub nulls: bool,
+}
+
+impl RESPType {
+    /// Bring this value into a canonical form, so that values that mean the same compare equal.
+    ///
+    /// Same as [normalize_with](RESPType::normalize_with) with default options.
+    pub fn normalize(&mut self) {
+        self.normalize_with(NormalizeOptions::default());
+    }
+
+    /// Bring this value into a canonical form, so that values that mean the same compare equal.
+    ///
+    /// Unordered collections are sorted: the elements of RESP3 sets, and the keys and values of RESP3 maps
+    /// and attributes by key, in the order of their encoding. RESP2 has none, so for RESP2 values only the
+    /// changes enabled in `options` apply.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{array, array_null, bulk_null, int, simple, NormalizeOptions, RESP};
+    ///
+    /// let mut reply = array![int!(1), array_null!()];
+    /// reply.normalize_with(NormalizeOptions { nulls: true });
+    /// assert_eq!(array![int!(1), bulk_null!()], reply);
+    ///
+    /// let members = RESP::set(vec![simple!("orange".to_owned()), simple!("apple".to_owned())]);
+    /// let same = RESP::set(vec![simple!("apple".to_owned()), simple!("orange".to_owned())]);
+    /// assert_eq!(
+    ///     members.normalized(NormalizeOptions::default()),
+    ///     same.normalized(NormalizeOptions::default())
+    /// );
+    /// ```
+    pub fn normalize_with(&mut self, options: NormalizeOptions) {
+        // Elements first, so that they are sorted by their canonical form.
+        for child in self.children_mut() {
+            child.normalize_with(options);
+        }
+        match self {
+            RESPType::Array(None) | RESPType::Null if options.nulls => {
+                *self = RESPType::BulkString(None)
+            }
+            RESPType::Set(elements) => elements.sort_by_cached_key(encode),
+            RESPType::Map(pairs)
+            | RESPType::Attribute {
+                attributes: pairs, ..
+            } => pairs.sort_by_cached_key(|(k, _)| encode(k)),
+            _ => {}
+        }
+    }
+
+    /// This value in canonical form, see [normalize_with](RESPType::normalize_with).
+    pub fn normalized(mut self, options: NormalizeOptions) -> Self {
+        self.normalize_with(options);
+        self
+    }
+}
+
+// Bytes to sort values by. Values that fail to serialize sort by the part written before they failed.
+fn encode(value: &RESPType) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let _ = ser::to_writer(value, &mut bytes);
+    bytes
+}
@@ -0,0 +1,160 @@
+//! Parser that does no IO, for any event loop.
+//!
+//! [RespParser](RespParser) is fed bytes as they arrive, in pieces of any size, such as those completed by
+//! io_uring or another custom runtime, and returns each frame once all of it has been fed. It keeps the bytes of the
+//! incomplete frame between calls and scans each byte once, so feeding a large frame piece by piece does not
+//! parse it over and over.
+//!
+//! It also reads from non-blocking sockets with [read_from](RespParser::read_from), which keeps what a frame
+//! has received so far when the socket would block, and carries on from there once it is readable again.
+//!
+//! # Examples
+//! ```
+//! use serde_resp::parser::RespParser;
+//! use serde_resp::{array, bulk, int, RESP};
+//!
+//! let mut parser = RespParser::new();
+//! parser.feed(b"*2\r\n$3\r\nGET\r\n$1");
+//! assert_eq!(None, parser.next_frame().unwrap());
+//! parser.feed(b"\r\na\r\n:1\r\n:");
+//! assert_eq!(Some(array![bulk!(b"GET".to_vec()), bulk!(b"a".to_vec())]), parser.next_frame().unwrap());
+//! assert_eq!(Some(int!(1)), parser.next_frame().unwrap());
+//! assert_eq!(None, parser.next_frame().unwrap());
+//! assert_eq!(b":", parser.buffer());
+//!
+//! parser.feed(b"x\r\n");
+//! assert!(parser.next_frame().unwrap_err().is_protocol());
+//! ```
+
+use crate::de::{Deserializer, Limits};
+use crate::lazy::FrameSplitter;
+use crate::{Error, RESPType, Result};
+
+use serde::Deserialize;
+use std::io::{self, Read};
+
+// Bytes asked from the reader at once.
+const READ_SIZE: usize = 8 * 1024;
+
+/// Parser of the frames in the bytes fed to it. Refer to [parser](self).
+#[derive(Debug, Default)]
+pub struct RespParser {
+    limits: Limits,
+    buf: Vec<u8>,
+    // Bytes of `buf` in the frames returned so far.
+    start: usize,
+    splitter: FrameSplitter,
+}
+
+impl RespParser {
+    /// Parse frames without limits.
+    pub fn new() -> Self {
+        RespParser::default()
+    }
+
+    /// Set the limits on frames parsed. Refer to [Limits](Limits).
+    ///
+    /// Lines, bulk strings, arrays and nesting over the limits fail as soon as they are fed, without waiting
+    /// for the rest of the frame.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self.splitter = FrameSplitter::new(limits);
+        self
+    }
+
+    /// Add bytes to parse after those fed before.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.buf_mut().extend_from_slice(bytes);
+    }
+
+    // The buffer to append bytes to, such as to read them into it directly.
+    pub(crate) fn buf_mut(&mut self) -> &mut Vec<u8> {
+        if self.start > 0 {
+            self.buf.drain(..self.start);
+            self.start = 0;
+        }
+        &mut self.buf
+    }
+
+    /// Parse the next frame, or return `None` if it has not been fed whole yet.
+    ///
+    /// # Errors
+    /// Fails if the frame is malformed or over the limits. The input cannot be parsed past that point,
+    /// so the same error is returned until the parser is dropped.
+    pub fn next_frame(&mut self) -> Result<Option<RESPType>> {
+        let len = match self.splitter.split(&self.buf[self.start..])? {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+        let frame = &self.buf[self.start..self.start + len];
+        let mut deserializer = Deserializer::from_buf_reader(frame).with_limits(self.limits);
+        let frame = RESPType::deserialize(&mut deserializer)?;
+        self.start += len;
+        Ok(Some(frame))
+    }
+
+    /// Parse the next frame, reading from `reader` until all of it is received, or return `None` if
+    /// `reader` would block before. The bytes read are kept, so calling this again once `reader` is readable
+    /// picks up where it left off.
+    ///
+    /// # Errors
+    /// Fails like [next_frame](RespParser::next_frame), and if `reader` fails. If `reader` ends between frames,
+    /// [is_clean_eof](Error::is_clean_eof) is true. If it ends in the middle of one,
+    /// [is_incomplete](Error::is_incomplete) is true, and the bytes of the frame are kept, in case `reader`
+    /// has more later.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::parser::RespParser;
+    /// use serde_resp::{int, RESP};
+    /// use std::io::{self, Read};
+    ///
+    /// // A non-blocking socket that received part of a frame.
+    /// struct Socket(Vec<u8>);
+    /// impl Read for Socket {
+    ///     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    ///         if self.0.is_empty() {
+    ///             return Err(io::ErrorKind::WouldBlock.into());
+    ///         }
+    ///         let n = self.0.len().min(buf.len());
+    ///         buf[..n].copy_from_slice(&self.0[..n]);
+    ///         self.0.drain(..n);
+    ///         Ok(n)
+    ///     }
+    /// }
+    ///
+    /// let mut parser = RespParser::new();
+    /// let mut socket = Socket(b":4".to_vec());
+    /// assert_eq!(None, parser.read_from(&mut socket).unwrap());
+    /// socket.0.extend_from_slice(b"2\r\n");
+    /// assert_eq!(Some(int!(42)), parser.read_from(&mut socket).unwrap());
+    ///
+    /// assert!(parser.read_from(&b":1"[..]).unwrap_err().is_incomplete());
+    /// assert_eq!(b":1", parser.buffer());
+    /// ```
+    pub fn read_from<R: Read>(&mut self, mut reader: R) -> Result<Option<RESPType>> {
+        loop {
+            if let Some(frame) = self.next_frame()? {
+                return Ok(Some(frame));
+            }
+            let buf = self.buf_mut();
+            let start = buf.len();
+            buf.resize(start + READ_SIZE, 0);
+            let read = reader.read(&mut buf[start..]);
+            buf.truncate(start + read.as_ref().map_or(0, |&n| n));
+            match read {
+                Ok(0) if self.buf.len() == self.start => return Err(Error::CleanEof),
+                Ok(0) => return Err(Error::Eof),
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+                Err(e) => return Err(Error::from(e)),
+            }
+        }
+    }
+
+    /// The bytes fed after the frames returned so far.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.start..]
+    }
+}
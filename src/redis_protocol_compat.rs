@@ -0,0 +1,263 @@
+//! Conversions between [RESPType](RESPType) and the frames of the [redis-protocol](https://docs.rs/redis-protocol) crate.
+
+use crate::{ser, Error, RESPType, Result};
+
+use ::redis_protocol::{resp2, resp3};
+use std::convert::TryFrom;
+
+/// Null bulk strings, null arrays and RESP3 nulls all become [Null](resp2::types::OwnedFrame::Null).
+/// RESP3 types become their RESP2 counterparts, as Redis replies to RESP2 clients: doubles and big numbers
+/// become bulk strings, booleans integers, verbatim strings bulk strings of their data, maps arrays of their
+/// keys and values, and sets and pushes arrays. Attributes are dropped, keeping the value they are about.
+///
+/// # Examples
+/// ```
+/// use redis_protocol::resp2::types::OwnedFrame;
+/// use serde_resp::{array, bulk_null, int, simple, RESP};
+/// use std::convert::TryFrom;
+///
+/// let frame = OwnedFrame::from(array![simple!("OK".to_owned()), int!(1), bulk_null!()]);
+/// assert_eq!(
+///     OwnedFrame::Array(vec![
+///         OwnedFrame::SimpleString(b"OK".to_vec()),
+///         OwnedFrame::Integer(1),
+///         OwnedFrame::Null,
+///     ]),
+///     frame
+/// );
+/// assert_eq!(
+///     array![simple!("OK".to_owned()), int!(1), bulk_null!()],
+///     RESP::try_from(frame).unwrap()
+/// );
+/// ```
+impl From<RESPType> for resp2::types::OwnedFrame {
+    fn from(value: RESPType) -> Self {
+        use resp2::types::OwnedFrame;
+        match value {
+            RESPType::SimpleString(s) => OwnedFrame::SimpleString(s.into_bytes()),
+            RESPType::Error(s) => OwnedFrame::Error(s),
+            RESPType::Integer(i) => OwnedFrame::Integer(i),
+            RESPType::BulkString(Some(b)) => OwnedFrame::BulkString(b),
+            RESPType::Array(Some(arr)) | RESPType::Push(arr) | RESPType::Set(arr) => {
+                OwnedFrame::Array(arr.into_iter().map(OwnedFrame::from).collect())
+            }
+            RESPType::Map(pairs) => OwnedFrame::Array(
+                pairs
+                    .into_iter()
+                    .flat_map(|(k, v)| vec![OwnedFrame::from(k), OwnedFrame::from(v)])
+                    .collect(),
+            ),
+            RESPType::BulkString(None) | RESPType::Array(None) | RESPType::Null => OwnedFrame::Null,
+            RESPType::Double(v) => OwnedFrame::BulkString(ser::format_double(v).into_bytes()),
+            RESPType::Boolean(v) => OwnedFrame::Integer(v as i64),
+            RESPType::BigNumber(s) => OwnedFrame::BulkString(s.into_bytes()),
+            RESPType::Verbatim { data, .. } => OwnedFrame::BulkString(data),
+            RESPType::Attribute { value, .. } => OwnedFrame::from(*value),
+        }
+    }
+}
+
+/// [Null](resp2::types::OwnedFrame::Null) becomes a null bulk string.
+/// Fails if a simple string is not valid utf-8.
+impl TryFrom<resp2::types::OwnedFrame> for RESPType {
+    type Error = Error;
+
+    fn try_from(frame: resp2::types::OwnedFrame) -> Result<Self> {
+        use resp2::types::OwnedFrame;
+        Ok(match frame {
+            OwnedFrame::SimpleString(s) => RESPType::SimpleString(String::from_utf8(s)?),
+            OwnedFrame::Error(s) => RESPType::Error(s),
+            OwnedFrame::Integer(i) => RESPType::Integer(i),
+            OwnedFrame::BulkString(b) => RESPType::BulkString(Some(b)),
+            OwnedFrame::Array(arr) => RESPType::Array(Some(
+                arr.into_iter()
+                    .map(RESPType::try_from)
+                    .collect::<Result<_>>()?,
+            )),
+            OwnedFrame::Null => RESPType::BulkString(None),
+        })
+    }
+}
+
+/// Null bulk strings, null arrays and RESP3 nulls all become [Null](resp3::types::OwnedFrame::Null).
+/// Verbatim strings in a format other than `txt` and `mkd`, which redis-protocol does not support,
+/// become blob strings of their data.
+/// Attributes are attached to the frame of their value, and dropped if it cannot have any, as nulls.
+/// In maps and attributes, of keys given twice only the last is kept, and aggregate keys, which redis-protocol
+/// cannot hash, are dropped. The same goes for the elements of sets.
+impl From<RESPType> for resp3::types::OwnedFrame {
+    fn from(value: RESPType) -> Self {
+        use resp3::types::{OwnedFrame, Resp3Frame, VerbatimStringFormat};
+        match value {
+            RESPType::SimpleString(s) => OwnedFrame::SimpleString {
+                data: s.into_bytes(),
+                attributes: None,
+            },
+            RESPType::Error(s) => OwnedFrame::SimpleError {
+                data: s,
+                attributes: None,
+            },
+            RESPType::Integer(i) => OwnedFrame::Number {
+                data: i,
+                attributes: None,
+            },
+            RESPType::BulkString(Some(b)) => OwnedFrame::BlobString {
+                data: b,
+                attributes: None,
+            },
+            RESPType::Array(Some(arr)) => OwnedFrame::Array {
+                data: arr.into_iter().map(OwnedFrame::from).collect(),
+                attributes: None,
+            },
+            RESPType::BulkString(None) | RESPType::Array(None) | RESPType::Null => OwnedFrame::Null,
+            RESPType::Push(arr) => OwnedFrame::Push {
+                data: arr.into_iter().map(OwnedFrame::from).collect(),
+                attributes: None,
+            },
+            RESPType::Double(v) => OwnedFrame::Double {
+                data: v,
+                attributes: None,
+            },
+            RESPType::Boolean(v) => OwnedFrame::Boolean {
+                data: v,
+                attributes: None,
+            },
+            RESPType::BigNumber(s) => OwnedFrame::BigNumber {
+                data: s.into_bytes(),
+                attributes: None,
+            },
+            RESPType::Verbatim { format, data } => match format.as_str() {
+                "txt" => OwnedFrame::VerbatimString {
+                    data,
+                    format: VerbatimStringFormat::Text,
+                    attributes: None,
+                },
+                "mkd" => OwnedFrame::VerbatimString {
+                    data,
+                    format: VerbatimStringFormat::Markdown,
+                    attributes: None,
+                },
+                _ => OwnedFrame::BlobString {
+                    data,
+                    attributes: None,
+                },
+            },
+            RESPType::Attribute { attributes, value } => {
+                let mut frame = OwnedFrame::from(*value);
+                let attributes = attributes
+                    .into_iter()
+                    .map(|(k, v)| (OwnedFrame::from(k), OwnedFrame::from(v)))
+                    .filter(|(k, _)| k.kind().can_hash())
+                    .collect();
+                // Fails only on frames that cannot have attributes.
+                let _ = frame.add_attributes(attributes);
+                frame
+            }
+            RESPType::Map(pairs) => OwnedFrame::Map {
+                data: pairs
+                    .into_iter()
+                    .map(|(k, v)| (OwnedFrame::from(k), OwnedFrame::from(v)))
+                    .filter(|(k, _)| k.kind().can_hash())
+                    .collect(),
+                attributes: None,
+            },
+            RESPType::Set(elements) => OwnedFrame::Set {
+                data: elements
+                    .into_iter()
+                    .map(OwnedFrame::from)
+                    .filter(|element| element.kind().can_hash())
+                    .collect(),
+                attributes: None,
+            },
+        }
+    }
+}
+
+/// [Null](resp3::types::OwnedFrame::Null) becomes [RESPType::Null](RESPType::Null), blob errors become errors,
+/// and frames with attributes become [attributes](RESPType::Attribute) about them.
+/// The keys of maps and the elements of sets come in the order redis-protocol keeps them in, which is arbitrary.
+/// Fails on frames with no equivalent, such as chunks of streamed strings, and on strings that are not valid utf-8.
+///
+/// # Examples
+/// ```
+/// use redis_protocol::resp3::types::OwnedFrame;
+/// use serde_resp::{err_str, RESP};
+/// use std::convert::TryFrom;
+///
+/// let frame = OwnedFrame::BlobError {
+///     data: b"ERR unknown command".to_vec(),
+///     attributes: None,
+/// };
+/// assert_eq!(
+///     err_str!("ERR unknown command".to_owned()),
+///     RESP::try_from(frame).unwrap()
+/// );
+///
+/// let frame = OwnedFrame::ChunkedString(b"part".to_vec());
+/// assert!(RESP::try_from(frame).is_err());
+/// ```
+impl TryFrom<resp3::types::OwnedFrame> for RESPType {
+    type Error = Error;
+
+    fn try_from(mut frame: resp3::types::OwnedFrame) -> Result<Self> {
+        use resp3::types::{OwnedFrame, Resp3Frame, VerbatimStringFormat};
+        let attributes = frame.take_attributes();
+        let value = match frame {
+            OwnedFrame::SimpleString { data, .. } => {
+                RESPType::SimpleString(String::from_utf8(data)?)
+            }
+            OwnedFrame::SimpleError { data, .. } => RESPType::Error(data),
+            OwnedFrame::BlobError { data, .. } => RESPType::Error(String::from_utf8(data)?),
+            OwnedFrame::Number { data, .. } => RESPType::Integer(data),
+            OwnedFrame::BlobString { data, .. } => RESPType::BulkString(Some(data)),
+            OwnedFrame::Array { data, .. } => RESPType::Array(Some(
+                data.into_iter()
+                    .map(RESPType::try_from)
+                    .collect::<Result<_>>()?,
+            )),
+            OwnedFrame::Null => RESPType::Null,
+            OwnedFrame::Push { data, .. } => RESPType::Push(
+                data.into_iter()
+                    .map(RESPType::try_from)
+                    .collect::<Result<_>>()?,
+            ),
+            OwnedFrame::Map { data, .. } => RESPType::Map(
+                data.into_iter()
+                    .map(|(k, v)| Ok((RESPType::try_from(k)?, RESPType::try_from(v)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            OwnedFrame::Set { data, .. } => RESPType::Set(
+                data.into_iter()
+                    .map(RESPType::try_from)
+                    .collect::<Result<_>>()?,
+            ),
+            OwnedFrame::Double { data, .. } => RESPType::Double(data),
+            OwnedFrame::Boolean { data, .. } => RESPType::Boolean(data),
+            OwnedFrame::BigNumber { data, .. } => RESPType::BigNumber(String::from_utf8(data)?),
+            OwnedFrame::VerbatimString { data, format, .. } => RESPType::Verbatim {
+                format: match format {
+                    VerbatimStringFormat::Text => "txt",
+                    VerbatimStringFormat::Markdown => "mkd",
+                }
+                .to_owned(),
+                data,
+            },
+            frame => {
+                return Err(Error::Message(format!(
+                    "{:?} frame has no equivalent",
+                    frame.kind()
+                )))
+            }
+        };
+        Ok(match attributes {
+            Some(attributes) if !attributes.is_empty() => RESPType::Attribute {
+                attributes: attributes
+                    .into_iter()
+                    .map(|(k, v)| Ok((RESPType::try_from(k)?, RESPType::try_from(v)?)))
+                    .collect::<Result<_>>()?,
+                value: Box::new(value),
+            },
+            _ => value,
+        })
+    }
+}
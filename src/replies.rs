@@ -0,0 +1,872 @@
+//! Conversions between the replies of common Redis commands and Rust types.
+//!
+//! Each conversion takes the reply as a [RESPType](crate::RESPType) and fails with an error describing
+//! the unexpected part if the reply does not have the shape of that command's reply.
+//! The reverse conversions build the replies, for servers and mocks.
+//!
+//! # Examples
+//! ```
+//! use serde_resp::{array, bulk, replies, RESP};
+//!
+//! let reply = array![
+//!     bulk!(b"name".to_vec()),
+//!     bulk!(b"Ada".to_vec()),
+//!     bulk!(b"born".to_vec()),
+//!     bulk!(b"1815".to_vec()),
+//! ];
+//! let map = replies::pairs_to_map(reply).unwrap();
+//! assert_eq!(b"Ada", &map["name"][..]);
+//! assert_eq!(b"1815", &map["born"][..]);
+//!
+//! let now = replies::time_to_system_time(array![bulk!(b"1714000000".to_vec()), bulk!(b"0".to_vec())]).unwrap();
+//! assert_eq!(now, std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_714_000_000));
+//! ```
+
+use crate::{Error, RESPType, Result};
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Convert a flat array of alternating keys and values, such as the reply to `HGETALL` or `CONFIG GET`,
+/// into a map. Keys and values may be bulk strings or simple strings, and keys must be valid UTF-8.
+/// A key appearing twice keeps its last value. RESP3 replies, which are maps, are converted too.
+///
+/// # Errors
+/// Fails if the reply is neither a non-null array nor a map, if the array has an odd number of elements,
+/// or if an element is not a string.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, bulk, replies, RESP};
+///
+/// assert!(replies::pairs_to_map(array![]).unwrap().is_empty());
+/// let err = replies::pairs_to_map(array![bulk!(b"lonely".to_vec())]).unwrap_err();
+/// assert_eq!("odd number of elements in key-value pairs: 1", err.to_string());
+///
+/// // HGETALL over RESP3
+/// let reply = RESP::Map(vec![(bulk!(b"name".to_vec()), bulk!(b"Ada".to_vec()))]);
+/// assert_eq!(b"Ada", &replies::pairs_to_map(reply).unwrap()["name"][..]);
+/// ```
+pub fn pairs_to_map(reply: RESPType) -> Result<HashMap<String, Vec<u8>>> {
+    pairs(reply)?
+        .into_iter()
+        .map(|(key, value)| Ok((key, bytes(value)?)))
+        .collect()
+}
+
+/// Convert a map into a flat array of alternating keys and values, as bulk strings.
+/// Reverse of [pairs_to_map](pairs_to_map). The pairs are in the iteration order of the map.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, bulk, replies, RESP};
+/// use std::collections::HashMap;
+///
+/// let mut map = HashMap::new();
+/// map.insert("maxmemory".to_owned(), b"0".to_vec());
+/// let reply = replies::map_to_pairs(map.clone());
+/// assert_eq!(array![bulk!(b"maxmemory".to_vec()), bulk!(b"0".to_vec())], reply);
+/// assert_eq!(map, replies::pairs_to_map(reply).unwrap());
+/// ```
+pub fn map_to_pairs(map: HashMap<String, Vec<u8>>) -> RESPType {
+    RESPType::array(
+        map.into_iter()
+            .flat_map(|(key, value)| vec![RESPType::bulk(key), RESPType::bulk(value)]),
+    )
+}
+
+/// Configuration parameters, from the reply to `CONFIG GET` or built by a server answering it.
+///
+/// Parameters are kept sorted by name. Values are kept as bytes, and read as booleans or numbers
+/// the way Redis writes them.
+///
+/// # Examples
+/// ```
+/// use serde_resp::replies::Config;
+/// use serde_resp::{array, bulk, RESP};
+///
+/// let reply = array![
+///     bulk!(b"maxmemory".to_vec()),
+///     bulk!(b"104857600".to_vec()),
+///     bulk!(b"appendonly".to_vec()),
+///     bulk!(b"yes".to_vec()),
+/// ];
+/// let config = Config::from_reply(reply).unwrap();
+/// assert_eq!(Some(104_857_600u64), config.get_number("maxmemory").unwrap());
+/// assert_eq!(Some(true), config.get_bool("appendonly").unwrap());
+/// assert_eq!(None, config.get_bool("save").unwrap());
+/// assert!(config.get_bool("maxmemory").is_err());
+///
+/// // Server side
+/// let config = Config::new()
+///     .with_number("maxmemory", 0)
+///     .with_bool("appendonly", false)
+///     .with("maxmemory-policy", "noeviction");
+/// assert_eq!(
+///     array![
+///         bulk!(b"maxmemory".to_vec()),
+///         bulk!(b"0".to_vec()),
+///         bulk!(b"maxmemory-policy".to_vec()),
+///         bulk!(b"noeviction".to_vec()),
+///     ],
+///     config.reply_to_get(&["maxmemory*"])
+/// );
+/// ```
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Config {
+    params: BTreeMap<String, Vec<u8>>,
+}
+
+impl Config {
+    /// An empty configuration.
+    pub fn new() -> Self {
+        Config::default()
+    }
+
+    /// Parse the reply to `CONFIG GET`, a flat array of alternating names and values.
+    ///
+    /// # Errors
+    /// Fails like [pairs_to_map](pairs_to_map).
+    pub fn from_reply(reply: RESPType) -> Result<Self> {
+        let params = pairs(reply)?
+            .into_iter()
+            .map(|(name, value)| Ok((name, bytes(value)?)))
+            .collect::<Result<_>>()?;
+        Ok(Config { params })
+    }
+
+    /// Value of parameter `name`.
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.params.get(name).map(Vec::as_slice)
+    }
+
+    /// Value of parameter `name` as text.
+    ///
+    /// # Errors
+    /// Fails if the value is not valid UTF-8.
+    pub fn get_str(&self, name: &str) -> Result<Option<&str>> {
+        self.get(name)
+            .map(|value| {
+                std::str::from_utf8(value).map_err(|e| Error::FromUtf8 {
+                    valid_up_to: e.valid_up_to(),
+                    error_len: e.error_len(),
+                })
+            })
+            .transpose()
+    }
+
+    /// Value of parameter `name` as a boolean, written `yes` or `no`.
+    ///
+    /// # Errors
+    /// Fails if the value is neither `yes` nor `no`.
+    pub fn get_bool(&self, name: &str) -> Result<Option<bool>> {
+        self.get(name)
+            .map(|value| match value {
+                b"yes" => Ok(true),
+                b"no" => Ok(false),
+                _ => Err(Error::Message(format!(
+                    "expected yes or no for {}, got {:?}",
+                    name,
+                    String::from_utf8_lossy(value)
+                ))),
+            })
+            .transpose()
+    }
+
+    /// Value of parameter `name` as a number.
+    ///
+    /// # Errors
+    /// Fails if the value is not a number of type `T`.
+    pub fn get_number<T: FromStr>(&self, name: &str) -> Result<Option<T>> {
+        self.get_str(name)?
+            .map(|value| {
+                value.parse().map_err(|_| {
+                    Error::Message(format!("expected a number for {}, got {:?}", name, value))
+                })
+            })
+            .transpose()
+    }
+
+    /// Set parameter `name` to `value`.
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<Vec<u8>>) {
+        self.params.insert(name.into(), value.into());
+    }
+
+    /// Set parameter `name` to `value`, when building a configuration.
+    pub fn with(mut self, name: impl Into<String>, value: impl Into<Vec<u8>>) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    /// Set parameter `name` to `yes` or `no`, when building a configuration.
+    pub fn with_bool(self, name: impl Into<String>, value: bool) -> Self {
+        self.with(name, if value { "yes" } else { "no" })
+    }
+
+    /// Set parameter `name` to a number, when building a configuration.
+    pub fn with_number<T: Display>(self, name: impl Into<String>, value: T) -> Self {
+        self.with(name, value.to_string())
+    }
+
+    /// Iterate over the parameters, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.params
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_slice()))
+    }
+
+    /// Number of parameters.
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Whether there is no parameter.
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    /// Build the reply to `CONFIG GET` with every parameter.
+    pub fn to_reply(&self) -> RESPType {
+        self.reply_with(|_| true)
+    }
+
+    /// Build the reply to `CONFIG GET` with the parameters matching any of `patterns`,
+    /// glob-style patterns where `*` matches any text and `?` any character, ignoring case like Redis.
+    pub fn reply_to_get(&self, patterns: &[&str]) -> RESPType {
+        self.reply_with(|name| patterns.iter().any(|pattern| glob_match(pattern, name)))
+    }
+
+    fn reply_with<F: Fn(&str) -> bool>(&self, matches: F) -> RESPType {
+        RESPType::array(
+            self.iter()
+                .filter(|(name, _)| matches(name))
+                .flat_map(|(name, value)| vec![RESPType::bulk(name), RESPType::bulk(value)]),
+        )
+    }
+}
+
+// Whether `text` matches glob-style `pattern`, ignoring ASCII case.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*`, and the text position it is trying.
+    let mut star = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c.eq_ignore_ascii_case(&text[t]) => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((after, tried)) => {
+                    p = after;
+                    t = tried + 1;
+                    star = Some((after, tried + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Convert the reply to `TIME`, the seconds and microseconds since the Unix epoch as bulk strings,
+/// into the time elapsed since the epoch.
+///
+/// # Errors
+/// Fails if the reply is not an array of two numbers, or if the microseconds are not below one second.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, bulk, replies, RESP};
+/// use std::time::Duration;
+///
+/// let reply = array![bulk!(b"1714000000".to_vec()), bulk!(b"250000".to_vec())];
+/// assert_eq!(Duration::new(1_714_000_000, 250_000_000), replies::time_to_duration(reply).unwrap());
+/// assert!(replies::time_to_duration(array![bulk!(b"1714000000".to_vec())]).is_err());
+/// ```
+pub fn time_to_duration(reply: RESPType) -> Result<Duration> {
+    let elements = array(reply)?;
+    if elements.len() != 2 {
+        return Err(Error::Message(format!(
+            "expected seconds and microseconds, got {} elements",
+            elements.len()
+        )));
+    }
+    let mut elements = elements.into_iter();
+    let secs: u64 = number(elements.next().expect("length checked"))?;
+    let micros: u32 = number(elements.next().expect("length checked"))?;
+    if micros >= 1_000_000 {
+        return Err(Error::Message(format!(
+            "microseconds out of range: {}",
+            micros
+        )));
+    }
+    Ok(Duration::new(secs, micros * 1000))
+}
+
+/// Convert the reply to `TIME` into a [SystemTime](std::time::SystemTime). Refer to [time_to_duration](time_to_duration).
+///
+/// # Errors
+/// Fails like [time_to_duration](time_to_duration), or if the time cannot be represented on this platform.
+pub fn time_to_system_time(reply: RESPType) -> Result<SystemTime> {
+    let since_epoch = time_to_duration(reply)?;
+    UNIX_EPOCH
+        .checked_add(since_epoch)
+        .ok_or_else(|| Error::Message(format!("time out of range: {:?}", since_epoch)))
+}
+
+/// Build the reply to `TIME` for `time`, truncated to microseconds. Reverse of [time_to_system_time](time_to_system_time).
+///
+/// # Errors
+/// Fails if `time` is before the Unix epoch.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, bulk, replies, RESP};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let time = UNIX_EPOCH + Duration::from_micros(1_714_000_000_000_042);
+/// let reply = replies::time_reply(time).unwrap();
+/// assert_eq!(array![bulk!(b"1714000000".to_vec()), bulk!(b"42".to_vec())], reply);
+/// assert_eq!(time, replies::time_to_system_time(reply).unwrap());
+/// assert!(replies::time_reply(UNIX_EPOCH - Duration::from_secs(1)).is_err());
+/// ```
+pub fn time_reply(time: SystemTime) -> Result<RESPType> {
+    let since_epoch = time
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| Error::Message(format!("time before the Unix epoch by {:?}", e.duration())))?;
+    Ok(RESPType::array(vec![
+        RESPType::bulk(since_epoch.as_secs().to_string()),
+        RESPType::bulk(since_epoch.subsec_micros().to_string()),
+    ]))
+}
+
+/// A node of a Redis Cluster, as listed in `CLUSTER SLOTS` and `CLUSTER SHARDS` replies.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ClusterNode {
+    /// Preferred endpoint to connect to, usually an IP address.
+    pub host: String,
+    /// Port of the node. The TLS port if the node only listens to TLS.
+    pub port: u16,
+    /// Node ID, if the reply includes it.
+    pub id: Option<String>,
+}
+
+/// A range of hash slots and the nodes serving it, from a `CLUSTER SLOTS` reply.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SlotRange {
+    /// First slot of the range.
+    pub start: u16,
+    /// Last slot of the range, included.
+    pub end: u16,
+    /// Node serving the range.
+    pub master: ClusterNode,
+    /// Replicas of the master.
+    pub replicas: Vec<ClusterNode>,
+}
+
+/// A shard of a Redis Cluster, from a `CLUSTER SHARDS` reply.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Shard {
+    /// Ranges of slots served by the shard, first and last slot included.
+    pub slots: Vec<(u16, u16)>,
+    /// Master of the shard, if it has one.
+    pub master: Option<ClusterNode>,
+    /// Other nodes of the shard.
+    pub replicas: Vec<ClusterNode>,
+}
+
+/// Parse the reply to `CLUSTER SLOTS`. Fields of the nodes after their ID, such as hostnames, are ignored.
+///
+/// # Errors
+/// Fails if the reply does not have the shape of a `CLUSTER SLOTS` reply.
+///
+/// # Examples
+/// ```
+/// use serde_resp::replies::{self, ClusterNode};
+/// use serde_resp::{array, bulk, int, RESP};
+///
+/// let reply = array![array![
+///     int!(0),
+///     int!(5460),
+///     array![bulk!(b"10.0.0.1".to_vec()), int!(6379), bulk!(b"09dbe9".to_vec())],
+///     array![bulk!(b"10.0.0.4".to_vec()), int!(6379), bulk!(b"4b0d3a".to_vec())],
+/// ]];
+/// let ranges = replies::cluster_slots(reply).unwrap();
+/// assert_eq!((0, 5460), (ranges[0].start, ranges[0].end));
+/// assert_eq!("10.0.0.1", ranges[0].master.host);
+/// assert_eq!(
+///     vec![ClusterNode { host: "10.0.0.4".to_owned(), port: 6379, id: Some("4b0d3a".to_owned()) }],
+///     ranges[0].replicas
+/// );
+/// ```
+pub fn cluster_slots(reply: RESPType) -> Result<Vec<SlotRange>> {
+    array(reply)?
+        .into_iter()
+        .map(|range| {
+            let mut fields = array(range)?.into_iter();
+            let (start, end, master) = match (fields.next(), fields.next(), fields.next()) {
+                (Some(start), Some(end), Some(master)) => (start, end, master),
+                _ => {
+                    return Err(Error::Message(
+                        "slot range is missing its start, end or master".to_owned(),
+                    ))
+                }
+            };
+            Ok(SlotRange {
+                start: number(start)?,
+                end: number(end)?,
+                master: slots_node(master)?,
+                replicas: fields.map(slots_node).collect::<Result<_>>()?,
+            })
+        })
+        .collect()
+}
+
+// Node of a CLUSTER SLOTS reply: host, port, then optionally ID and more.
+fn slots_node(node: RESPType) -> Result<ClusterNode> {
+    let mut fields = array(node)?.into_iter();
+    let (host, port) = match (fields.next(), fields.next()) {
+        (Some(host), Some(port)) => (host, port),
+        _ => {
+            return Err(Error::Message(
+                "node is missing its host or port".to_owned(),
+            ))
+        }
+    };
+    Ok(ClusterNode {
+        host: string(host)?,
+        port: number(port)?,
+        id: fields.next().map(string).transpose()?,
+    })
+}
+
+/// Parse the reply to `CLUSTER SHARDS`. The node with role `master` is the master, the others are replicas.
+/// The host of a node is its `endpoint`, or its `ip` if it has none.
+///
+/// # Errors
+/// Fails if the reply does not have the shape of a `CLUSTER SHARDS` reply.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, bulk, int, replies, RESP};
+///
+/// let reply = array![array![
+///     bulk!(b"slots".to_vec()),
+///     array![int!(0), int!(5460), int!(10923), int!(10923)],
+///     bulk!(b"nodes".to_vec()),
+///     array![array![
+///         bulk!(b"id".to_vec()),
+///         bulk!(b"09dbe9".to_vec()),
+///         bulk!(b"port".to_vec()),
+///         int!(6379),
+///         bulk!(b"ip".to_vec()),
+///         bulk!(b"10.0.0.1".to_vec()),
+///         bulk!(b"endpoint".to_vec()),
+///         bulk!(b"10.0.0.1".to_vec()),
+///         bulk!(b"role".to_vec()),
+///         bulk!(b"master".to_vec()),
+///     ]],
+/// ]];
+/// let shards = replies::cluster_shards(reply).unwrap();
+/// assert_eq!(vec![(0, 5460), (10923, 10923)], shards[0].slots);
+/// assert_eq!(6379, shards[0].master.as_ref().unwrap().port);
+/// assert!(shards[0].replicas.is_empty());
+/// ```
+pub fn cluster_shards(reply: RESPType) -> Result<Vec<Shard>> {
+    array(reply)?
+        .into_iter()
+        .map(|shard| {
+            let mut fields = pairs(shard)?;
+            let bounds = array(take_field(&mut fields, "slots")?)?;
+            if bounds.len() % 2 != 0 {
+                return Err(Error::Message(format!(
+                    "odd number of slot bounds: {}",
+                    bounds.len()
+                )));
+            }
+            let mut bounds = bounds.into_iter();
+            let mut slots = Vec::new();
+            while let (Some(start), Some(end)) = (bounds.next(), bounds.next()) {
+                slots.push((number(start)?, number(end)?));
+            }
+            let mut master = None;
+            let mut replicas = Vec::new();
+            for node in array(take_field(&mut fields, "nodes")?)? {
+                let mut fields = pairs(node)?;
+                let is_master = match take_field(&mut fields, "role") {
+                    Ok(role) => string(role)? == "master",
+                    Err(_) => false,
+                };
+                let host = match take_field(&mut fields, "endpoint") {
+                    Ok(endpoint) => endpoint,
+                    Err(_) => take_field(&mut fields, "ip")?,
+                };
+                let port = match take_field(&mut fields, "port") {
+                    Ok(port) => port,
+                    Err(_) => take_field(&mut fields, "tls-port")?,
+                };
+                let node = ClusterNode {
+                    host: string(host)?,
+                    port: number(port)?,
+                    id: take_field(&mut fields, "id").ok().map(string).transpose()?,
+                };
+                if is_master && master.is_none() {
+                    master = Some(node);
+                } else {
+                    replicas.push(node);
+                }
+            }
+            Ok(Shard {
+                slots,
+                master,
+                replicas,
+            })
+        })
+        .collect()
+}
+
+/// Longitude and latitude of a member of a geospatial index, in degrees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Coordinates {
+    /// Longitude, from -180 to 180.
+    pub longitude: f64,
+    /// Latitude, from about -85.05 to 85.05.
+    pub latitude: f64,
+}
+
+/// A member found by `GEOSEARCH`, `GEORADIUS` and similar commands,
+/// with the fields their `WITHDIST`, `WITHHASH` and `WITHCOORD` options ask for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeoMember {
+    /// Name of the member.
+    pub member: Vec<u8>,
+    /// Distance from the center of the search, in the unit of the search, with `WITHDIST`.
+    pub distance: Option<f64>,
+    /// Raw geohash of the member, with `WITHHASH`.
+    pub hash: Option<i64>,
+    /// Position of the member, with `WITHCOORD`.
+    pub coordinates: Option<Coordinates>,
+}
+
+/// Parse the reply to `GEOPOS`, with `None` for members that do not exist.
+///
+/// # Errors
+/// Fails if the reply is not an array of coordinate pairs or nulls.
+///
+/// # Examples
+/// ```
+/// use serde_resp::replies::{self, Coordinates};
+/// use serde_resp::{array, array_null, bulk, RESP};
+///
+/// let reply = array![
+///     array![bulk!(b"13.361389".to_vec()), bulk!(b"38.115556".to_vec())],
+///     array_null!(),
+/// ];
+/// assert_eq!(
+///     vec![Some(Coordinates { longitude: 13.361389, latitude: 38.115556 }), None],
+///     replies::geopos(reply).unwrap()
+/// );
+/// ```
+pub fn geopos(reply: RESPType) -> Result<Vec<Option<Coordinates>>> {
+    array(reply)?
+        .into_iter()
+        .map(|position| match position {
+            RESPType::Array(None) | RESPType::BulkString(None) | RESPType::Null => Ok(None),
+            position => coordinates(position).map(Some),
+        })
+        .collect()
+}
+
+/// Parse the reply to `GEOSEARCH`, or to `GEORADIUS` and similar commands.
+/// Each member is a bulk string without any `WITH` option, or an array of the member followed by
+/// the distance, hash and coordinates asked for, in that order.
+///
+/// # Errors
+/// Fails if the reply does not have the shape of such a reply.
+///
+/// # Examples
+/// ```
+/// use serde_resp::replies::{self, Coordinates};
+/// use serde_resp::{array, bulk, RESP};
+///
+/// // GEOSEARCH Sicily FROMLONLAT 15 37 BYRADIUS 200 km WITHCOORD WITHDIST
+/// let reply = array![array![
+///     bulk!(b"Palermo".to_vec()),
+///     bulk!(b"190.4424".to_vec()),
+///     array![bulk!(b"13.361389".to_vec()), bulk!(b"38.115556".to_vec())],
+/// ]];
+/// let found = replies::geo_search(reply).unwrap();
+/// assert_eq!(b"Palermo", &found[0].member[..]);
+/// assert_eq!(Some(190.4424), found[0].distance);
+/// assert_eq!(None, found[0].hash);
+/// assert_eq!(Some(Coordinates { longitude: 13.361389, latitude: 38.115556 }), found[0].coordinates);
+///
+/// let found = replies::geo_search(array![bulk!(b"Catania".to_vec())]).unwrap();
+/// assert_eq!((b"Catania".to_vec(), None), (found[0].member.clone(), found[0].distance));
+/// ```
+pub fn geo_search(reply: RESPType) -> Result<Vec<GeoMember>> {
+    array(reply)?
+        .into_iter()
+        .map(|found| {
+            let fields = match found {
+                RESPType::Array(Some(fields)) => fields,
+                member => vec![member],
+            };
+            let mut fields = fields.into_iter();
+            let member = match fields.next() {
+                Some(member) => bytes(member)?,
+                None => return Err(Error::Message("empty geo search result".to_owned())),
+            };
+            let mut found = GeoMember {
+                member,
+                distance: None,
+                hash: None,
+                coordinates: None,
+            };
+            for field in fields {
+                match field {
+                    RESPType::Integer(hash) => found.hash = Some(hash),
+                    RESPType::Array(Some(_)) => found.coordinates = Some(coordinates(field)?),
+                    distance => found.distance = Some(number(distance)?),
+                }
+            }
+            Ok(found)
+        })
+        .collect()
+}
+
+// Longitude and latitude as a pair of numbers.
+fn coordinates(position: RESPType) -> Result<Coordinates> {
+    let position = array(position)?;
+    match <[RESPType; 2]>::try_from(position) {
+        Ok([longitude, latitude]) => Ok(Coordinates {
+            longitude: number(longitude)?,
+            latitude: number(latitude)?,
+        }),
+        Err(position) => Err(Error::Message(format!(
+            "expected longitude and latitude, got {} elements",
+            position.len()
+        ))),
+    }
+}
+
+/// A connection to a Redis server, from `CLIENT LIST` or `CLIENT INFO`.
+///
+/// The fields most tools need are parsed, and [fields](ClientInfo::fields) keeps every field as text,
+/// including those added by newer versions of Redis. Fields missing from the line are left empty or zero.
+///
+/// # Examples
+/// ```
+/// use serde_resp::replies::ClientInfo;
+///
+/// let line = "id=3 addr=127.0.0.1:52555 laddr=127.0.0.1:6379 fd=8 name=worker age=42 idle=0 \
+///             flags=N db=2 sub=0 psub=0 multi=-1 cmd=client|info user=default resp=3";
+/// let client: ClientInfo = line.parse().unwrap();
+/// assert_eq!(3, client.id);
+/// assert_eq!("127.0.0.1:52555", client.addr);
+/// assert_eq!("worker", client.name);
+/// assert_eq!(2, client.db);
+/// assert_eq!(Some(3), client.resp);
+/// assert_eq!(Some("8"), client.field("fd"));
+/// assert!("addr=127.0.0.1:52555".parse::<ClientInfo>().is_err());
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct ClientInfo {
+    /// Unique ID of the connection.
+    pub id: u64,
+    /// Address and port of the client.
+    pub addr: String,
+    /// Name set with `CLIENT SETNAME`.
+    pub name: String,
+    /// Seconds since the connection was made.
+    pub age: u64,
+    /// Seconds since the last command.
+    pub idle: u64,
+    /// Flags of the connection, one letter each, such as `N` for a normal client or `S` for a replica.
+    pub flags: String,
+    /// Selected database.
+    pub db: u64,
+    /// Last command run, such as `client|list`.
+    pub cmd: String,
+    /// Authenticated user, since Redis 6.
+    pub user: Option<String>,
+    /// Protocol version, since Redis 7.
+    pub resp: Option<u8>,
+    /// Every field of the line in order, as text.
+    pub fields: Vec<(String, String)>,
+}
+
+impl ClientInfo {
+    /// Value of field `key` as text.
+    pub fn field(&self, key: &str) -> Option<&str> {
+        field(&self.fields, key)
+    }
+}
+
+/// Parse one line of `CLIENT LIST`, or the reply to `CLIENT INFO`: space-separated `key=value` fields.
+///
+/// # Errors
+/// Fails if a field has no `=`, if the `id` or `addr` field is missing, or if a numeric field is not a number.
+impl FromStr for ClientInfo {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let fields = line
+            .split_whitespace()
+            .map(|field| match field.split_once('=') {
+                Some((key, value)) => Ok((key.to_owned(), value.to_owned())),
+                None => Err(Error::Message(format!(
+                    "expected key=value, got {:?}",
+                    field
+                ))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let text = |key: &str| field(&fields, key).map(str::to_owned);
+        Ok(ClientInfo {
+            id: field_number(&fields, "id")?.ok_or_else(|| missing_field("id"))?,
+            addr: text("addr").ok_or_else(|| missing_field("addr"))?,
+            name: text("name").unwrap_or_default(),
+            age: field_number(&fields, "age")?.unwrap_or_default(),
+            idle: field_number(&fields, "idle")?.unwrap_or_default(),
+            flags: text("flags").unwrap_or_default(),
+            db: field_number(&fields, "db")?.unwrap_or_default(),
+            cmd: text("cmd").unwrap_or_default(),
+            user: text("user"),
+            resp: field_number(&fields, "resp")?,
+            fields,
+        })
+    }
+}
+
+/// Parse the reply to `CLIENT LIST`, a string with one line per connection.
+///
+/// # Errors
+/// Fails if the reply is not a string, or if a line fails to parse as a [ClientInfo](ClientInfo).
+///
+/// # Examples
+/// ```
+/// use serde_resp::{bulk, replies, RESP};
+///
+/// let reply = bulk!(b"id=3 addr=127.0.0.1:52555 flags=N\nid=4 addr=127.0.0.1:52556 flags=P\n".to_vec());
+/// let clients = replies::client_list(reply).unwrap();
+/// assert_eq!(vec![3, 4], clients.iter().map(|client| client.id).collect::<Vec<_>>());
+/// assert_eq!("P", clients[1].flags);
+/// ```
+pub fn client_list(reply: RESPType) -> Result<Vec<ClientInfo>> {
+    string(reply)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Parse the reply to `CLIENT INFO`. Refer to [ClientInfo](ClientInfo).
+///
+/// # Errors
+/// Fails if the reply is not a string, or if it fails to parse as a [ClientInfo](ClientInfo).
+pub fn client_info(reply: RESPType) -> Result<ClientInfo> {
+    string(reply)?.parse()
+}
+
+fn field<'a>(fields: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    fields
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.as_str())
+}
+
+fn field_number<T: FromStr>(fields: &[(String, String)], key: &str) -> Result<Option<T>> {
+    field(fields, key)
+        .map(|value| {
+            value.parse().map_err(|_| {
+                Error::Message(format!("expected a number for {}, got {:?}", key, value))
+            })
+        })
+        .transpose()
+}
+
+fn missing_field(key: &str) -> Error {
+    Error::Message(format!("missing field {:?}", key))
+}
+
+// Pairs of a RESP3 map, or alternating keys and values of a non-null array, keeping the values as they are.
+fn pairs(reply: RESPType) -> Result<Vec<(String, RESPType)>> {
+    let elements = match reply {
+        RESPType::Map(pairs) => {
+            return pairs
+                .into_iter()
+                .map(|(key, value)| Ok((string(key)?, value)))
+                .collect()
+        }
+        other => array(other)?,
+    };
+    if elements.len() % 2 != 0 {
+        return Err(Error::Message(format!(
+            "odd number of elements in key-value pairs: {}",
+            elements.len()
+        )));
+    }
+    let mut pairs = Vec::with_capacity(elements.len() / 2);
+    let mut elements = elements.into_iter();
+    while let (Some(key), Some(value)) = (elements.next(), elements.next()) {
+        pairs.push((string(key)?, value));
+    }
+    Ok(pairs)
+}
+
+// Remove the value of the first pair with `key`.
+fn take_field(pairs: &mut Vec<(String, RESPType)>, key: &str) -> Result<RESPType> {
+    match pairs.iter().position(|(k, _)| k == key) {
+        Some(index) => Ok(pairs.swap_remove(index).1),
+        None => Err(missing_field(key)),
+    }
+}
+
+// Elements of a non-null array, or of a RESP3 set or push.
+fn array(reply: RESPType) -> Result<Vec<RESPType>> {
+    match reply {
+        RESPType::Array(Some(elements)) | RESPType::Set(elements) | RESPType::Push(elements) => {
+            Ok(elements)
+        }
+        other => Err(unexpected("an array", &other)),
+    }
+}
+
+// Bytes of a non-null bulk string or a simple string.
+fn bytes(value: RESPType) -> Result<Vec<u8>> {
+    match value {
+        RESPType::BulkString(Some(bytes)) => Ok(bytes),
+        RESPType::SimpleString(s) => Ok(s.into_bytes()),
+        other => Err(unexpected("a string", &other)),
+    }
+}
+
+// Text of a non-null bulk string or a simple string.
+fn string(value: RESPType) -> Result<String> {
+    Ok(String::from_utf8(bytes(value)?)?)
+}
+
+// Integer, or number written as a bulk string or a simple string, as Redis replies often do.
+fn number<T: FromStr>(value: RESPType) -> Result<T> {
+    let text = match value {
+        RESPType::Integer(i) => i.to_string(),
+        other => string(other)?,
+    };
+    text.parse()
+        .map_err(|_| Error::Message(format!("expected a number, got {:?}", text)))
+}
+
+fn unexpected(expected: &str, value: &RESPType) -> Error {
+    Error::Message(format!("expected {}, got {:?}", expected, value))
+}
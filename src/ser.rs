@@ -1,18 +1,328 @@
 use serde::{ser, Serialize};
 
-use crate::{Error, RESPType, Result};
+use crate::metrics::Metrics;
+use crate::{Error, RESPKind, RESPType, Result};
 use serde::ser::SerializeSeq;
-use std::io::Write;
-use std::result;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::{fmt, result, str};
+
+// Newtype struct names `RESPType` uses to mark simple strings, errors and RESP3 types.
+pub(crate) const SIMPLE_STRING_TOKEN: &str = "$serde_resp::SimpleString";
+pub(crate) const ERROR_TOKEN: &str = "$serde_resp::Error";
+pub(crate) const DOUBLE_TOKEN: &str = "$serde_resp::Double";
+pub(crate) const BOOLEAN_TOKEN: &str = "$serde_resp::Boolean";
+pub(crate) const BIG_NUMBER_TOKEN: &str = "$serde_resp::BigNumber";
+pub(crate) const VERBATIM_TOKEN: &str = "$serde_resp::Verbatim";
+pub(crate) const PUSH_TOKEN: &str = "$serde_resp::Push";
+pub(crate) const ATTRIBUTE_TOKEN: &str = "$serde_resp::Attribute";
+pub(crate) const MAP_TOKEN: &str = "$serde_resp::Map";
+pub(crate) const SET_TOKEN: &str = "$serde_resp::Set";
+// Unit struct name `RESPType` uses to mark the RESP3 null.
+pub(crate) const NULL_TOKEN: &str = "$serde_resp::Null";
+// Newtype struct name `SharedResp` uses to mark bytes that are already encoded.
+pub(crate) const ENCODED_TOKEN: &str = "$serde_resp::Encoded";
+
+// Longest header line: a type byte, the 20 characters of i64::MIN or u64::MAX, and CRLF.
+pub(crate) const HEADER_MAX_LEN: usize = 1 + 20 + 2;
+
+// Encode a header line such as `$5\r\n` into `buf`, so it can be written at once.
+pub(crate) fn encode_header<I: itoa::Integer>(
+    buf: &mut [u8; HEADER_MAX_LEN],
+    type_byte: u8,
+    v: I,
+) -> &[u8] {
+    let mut digits = itoa::Buffer::new();
+    let digits = digits.format(v).as_bytes();
+    let len = digits.len() + 3;
+    buf[0] = type_byte;
+    buf[1..len - 2].copy_from_slice(digits);
+    buf[len - 2..len].copy_from_slice(b"\r\n");
+    &buf[..len]
+}
+
+/// Version of the protocol to write values for, see [Serializer::with_protocol](Serializer::with_protocol).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Protocol {
+    /// RESP2, which every version of Redis speaks.
+    Resp2,
+    /// RESP3, which clients switch to with `HELLO 3`.
+    Resp3,
+}
 
 /// Serializer for RESP format
-pub struct Serializer<W: Write> {
+pub struct Serializer<W: Write, M: Metrics = ()> {
+    writer: W,
+    metrics: M,
+    protocol: Option<Protocol>,
+    // Arrays currently being serialized.
+    depth: usize,
+    // Set while serializing the content of a simple string, error, big number, verbatim string or push.
+    marked: Option<RESPKind>,
+    // Set while serializing an already encoded frame.
+    encoded: bool,
+    // Bytes written so far, and where the current top-level frame started.
+    written: usize,
+    frame_start: usize,
+    // Length of the last top-level frame written.
+    last_frame_len: Option<usize>,
+}
+
+impl<W: Write> Serializer<W> {
+    /// Method for building Serializer
+    pub fn new(writer: W) -> Serializer<W> {
+        Serializer {
+            writer,
+            metrics: (),
+            protocol: None,
+            depth: 0,
+            marked: None,
+            encoded: false,
+            written: 0,
+            frame_start: 0,
+            last_frame_len: None,
+        }
+    }
+}
+
+impl<W: Write, M: Metrics> Serializer<W, M> {
+    /// Report bytes and frames written to `metrics`. Refer to [Metrics](Metrics).
+    pub fn with_metrics<N: Metrics>(self, metrics: N) -> Serializer<W, N> {
+        Serializer {
+            writer: self.writer,
+            metrics,
+            protocol: self.protocol,
+            depth: self.depth,
+            marked: self.marked,
+            encoded: self.encoded,
+            written: self.written,
+            frame_start: self.frame_start,
+            last_frame_len: self.last_frame_len,
+        }
+    }
+
+    /// Write values for `protocol`. By default, RESP3 types are written as they are,
+    /// and the elements of sequences of unknown length are buffered to write their number first.
+    ///
+    /// - [Resp2](Protocol::Resp2): RESP3 types are downgraded the way Redis replies to RESP2 clients:
+    ///   doubles and big numbers become bulk strings, booleans integers, verbatim strings bulk strings of their data,
+    ///   nulls null bulk strings, maps flat arrays of their keys and values, and sets and pushes arrays.
+    ///   Attributes are dropped, keeping the value they are about.
+    /// - [Resp3](Protocol::Resp3): RESP3 types are written as they are, and arrays, maps and sets of unknown
+    ///   length are streamed, such as `*?` followed by their elements and `.`, instead of buffered.
+    ///
+    /// Frames that are already encoded, such as [LazyFrame](crate::LazyFrame)s, are written as they are.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde::{Serialize, Serializer as _};
+    /// use serde_resp::ser::{Protocol, Serializer};
+    /// use serde_resp::{array, RESP};
+    ///
+    /// let reply = array![RESP::boolean(true), RESP::double(1.5), RESP::Null];
+    /// let mut buf = Vec::new();
+    /// reply.serialize(&mut Serializer::new(&mut buf).with_protocol(Protocol::Resp2)).unwrap();
+    /// assert_eq!(b"*3\r\n:1\r\n$3\r\n1.5\r\n$-1\r\n", &buf[..]);
+    ///
+    /// let mut buf = Vec::new();
+    /// let mut serializer = Serializer::new(&mut buf).with_protocol(Protocol::Resp3);
+    /// let odd = (1..=3).filter(|i| i % 2 == 1).map(RESP::int);
+    /// (&mut serializer).collect_seq(odd).unwrap();
+    /// assert_eq!(b"*?\r\n:1\r\n:3\r\n.\r\n", &buf[..]);
+    /// ```
+    pub fn with_protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    /// Length in bytes of the last complete top-level frame written, or `None` if none was written yet.
+    /// A frame that fails to serialize is not complete, and the next frame is counted from where it stopped.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_resp::ser::Serializer;
+    /// use serde_resp::{array, bulk, int, simple, RESP};
+    ///
+    /// let mut serializer = Serializer::new(Vec::new());
+    /// array![bulk!(b"foo".to_vec())].serialize(&mut serializer).unwrap();
+    /// assert_eq!(Some(13), serializer.last_frame_len());
+    /// int!(1).serialize(&mut serializer).unwrap();
+    /// assert_eq!(Some(4), serializer.last_frame_len());
+    ///
+    /// let bad = array![int!(2), array![simple!("a\r\nb".to_owned())]];
+    /// assert!(bad.serialize(&mut serializer).is_err());
+    /// assert_eq!(Some(4), serializer.last_frame_len());
+    /// array![int!(1)].serialize(&mut serializer).unwrap();
+    /// assert_eq!(Some(8), serializer.last_frame_len());
+    /// ```
+    pub fn last_frame_len(&self) -> Option<usize> {
+        self.last_frame_len
+    }
+
+    // write all bytes, reporting them to metrics.
+    fn write(&mut self, buf: &[u8]) -> Result<()> {
+        match self.writer.write_all(buf) {
+            Ok(()) => {
+                self.written += buf.len();
+                self.metrics.bytes_written(buf.len());
+                self.metrics.data_written(buf);
+                Ok(())
+            }
+            Err(e) => {
+                // The frame is broken, count the next one from here.
+                self.frame_start = self.written;
+                let e = Error::from(e);
+                self.metrics.error(e.kind());
+                Err(e)
+            }
+        }
+    }
+
+    // write a header line such as `:42\r\n` in a single write, reporting it to metrics.
+    fn write_header<I: itoa::Integer>(&mut self, type_byte: u8, v: I) -> Result<()> {
+        let mut buf = [0; HEADER_MAX_LEN];
+        self.write(encode_header(&mut buf, type_byte, v))
+    }
+
+    fn is_resp2(&self) -> bool {
+        self.protocol == Some(Protocol::Resp2)
+    }
+
+    // leave an array whose element failed, counting the next frame from here once out of the top-level one.
+    fn element_failed(&mut self) {
+        self.depth -= 1;
+        if self.depth == 0 {
+            self.frame_start = self.written;
+        }
+    }
+
+    // report a frame written if it is not nested in an array.
+    fn frame_written(&mut self, kind: RESPKind) {
+        if self.depth == 0 {
+            self.last_frame_len = Some(self.written - self.frame_start);
+            self.frame_start = self.written;
+            self.metrics.frame_written(kind);
+        }
+    }
+}
+
+/// Writer of frames that counts what it writes.
+///
+/// Proxies and servers often report how many frames and bytes they sent. `RespWriter` keeps these
+/// counters, including the number of frames of each type, without a [Metrics](Metrics) implementation
+/// of their own. Elements of arrays are not counted as frames.
+///
+/// # Examples
+/// ```
+/// use serde_resp::ser::RespWriter;
+/// use serde_resp::{array, bulk, int, simple, RESPKind, RESP};
+///
+/// let mut writer = RespWriter::new(Vec::new());
+/// writer.write(&simple!("OK".to_owned())).unwrap();
+/// writer.write(&int!(1)).unwrap();
+/// writer.write(&array![int!(2), int!(3)]).unwrap();
+/// assert_eq!(3, writer.frames_written());
+/// assert_eq!(21, writer.bytes_written());
+/// assert_eq!(1, writer.count(RESPKind::Integer));
+/// assert_eq!(1, writer.count(RESPKind::Array));
+/// assert_eq!(0, writer.count(RESPKind::BulkString));
+/// assert_eq!(b"+OK\r\n:1\r\n*2\r\n:2\r\n:3\r\n", &writer.into_inner()[..]);
+/// ```
+#[derive(Debug)]
+pub struct RespWriter<W: Write> {
     writer: W,
+    frames: usize,
+    bytes: usize,
+    counts: HashMap<RESPKind, usize>,
+}
+
+impl<W: Write> RespWriter<W> {
+    /// Write frames to `writer`.
+    pub fn new(writer: W) -> Self {
+        RespWriter {
+            writer,
+            frames: 0,
+            bytes: 0,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Serialize `value` as a frame, usually a [RESPType](RESPType).
+    ///
+    /// # Errors
+    /// Fails like [to_writer](to_writer). The bytes written before the failure are counted,
+    /// but not the frame.
+    pub fn write<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let last_kind = LastKind::default();
+        let mut serializer = Serializer::new(&mut self.writer).with_metrics(&last_kind);
+        let result = value.serialize(&mut serializer);
+        self.bytes += serializer.written;
+        if let Some(kind) = last_kind.0.get() {
+            self.frames += 1;
+            *self.counts.entry(kind).or_insert(0) += 1;
+        }
+        result
+    }
+
+    /// Flush the writer.
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    /// Number of frames written.
+    pub fn frames_written(&self) -> usize {
+        self.frames
+    }
+
+    /// Number of bytes written.
+    pub fn bytes_written(&self) -> usize {
+        self.bytes
+    }
+
+    /// Number of frames of type `kind` written.
+    pub fn count(&self, kind: RESPKind) -> usize {
+        self.counts.get(&kind).copied().unwrap_or(0)
+    }
+
+    /// Set all counters back to zero.
+    pub fn reset_counts(&mut self) {
+        self.frames = 0;
+        self.bytes = 0;
+        self.counts.clear();
+    }
+
+    /// Get a reference to the writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Get a mutable reference to the writer. Bytes written to it directly are not counted.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Get the writer back.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+// Kind of the last frame written.
+#[derive(Default)]
+struct LastKind(Cell<Option<RESPKind>>);
+
+impl Metrics for LastKind {
+    fn frame_written(&self, kind: RESPKind) {
+        self.0.set(Some(kind));
+    }
 }
 
 /// Serialize to string.
 ///
 /// Please do not use this method with [RESPType::BulkString](RESPType::BulkString) that contains non-UTF8 data.
+/// Use [to_vec](to_vec) for binary data instead.
 ///
 /// # Errors
 /// Please refer to [Error](Error)
@@ -20,9 +330,178 @@ pub fn to_string<T>(value: &T) -> Result<String>
 where
     T: Serialize,
 {
-    let mut buf: Vec<u8> = Vec::new();
-    to_writer(value, &mut buf)?;
-    Ok(String::from_utf8(buf)?)
+    let mut string = String::new();
+    to_fmt_writer(value, &mut string)?;
+    Ok(string)
+}
+
+/// Serialize to bytes.
+///
+/// Unlike [to_string](to_string), bulk strings may hold any bytes, as they do on the wire.
+///
+/// # Errors
+/// Please refer to [Error](Error). Simple strings, errors and big numbers that contain CR or LF fail with
+/// [Error::LineBreak](Error::LineBreak), as they would corrupt the stream.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, bulk, ser, simple, ErrorKind, RESP};
+///
+/// let bytes = ser::to_vec(&array![simple!("OK".to_owned()), bulk!(vec![0xff, 0x00])]).unwrap();
+/// assert_eq!(b"*2\r\n+OK\r\n$2\r\n\xff\x00\r\n", &bytes[..]);
+///
+/// let err = ser::to_vec(&array![simple!("OK\r\n+INJECTED".to_owned())]).unwrap_err();
+/// assert_eq!(ErrorKind::InvalidValue, err.kind());
+/// ```
+pub fn to_vec<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize,
+{
+    to_vec_with_capacity(value, 0)
+}
+
+/// Serialize to bytes, into a buffer allocated with room for `capacity` bytes.
+///
+/// With the [serialized_size](serialized_size) of a value, the buffer is allocated once,
+/// instead of growing as [to_vec](to_vec) writes large values.
+///
+/// # Errors
+/// Please refer to [Error](Error)
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, bulk, ser, RESP};
+///
+/// let pipeline = RESP::array((0..1000).map(|i| array![bulk!(b"GET".to_vec()), bulk!(i.to_string().into_bytes())]));
+/// let bytes = ser::to_vec_with_capacity(&pipeline, ser::serialized_size(&pipeline)).unwrap();
+/// assert_eq!(bytes.len(), bytes.capacity());
+/// ```
+pub fn to_vec_with_capacity<T>(value: &T, capacity: usize) -> Result<Vec<u8>>
+where
+    T: Serialize,
+{
+    let mut bytes = Vec::with_capacity(capacity);
+    to_writer(value, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Number of bytes [to_writer](to_writer) writes for `value`, computed without serializing it,
+/// such as to allocate a send buffer up front, see [to_vec_with_capacity](to_vec_with_capacity),
+/// or to prefix the value with its length.
+///
+/// The size of a value that fails to serialize, such as a verbatim string whose format is not
+/// 3 bytes long, is meaningless.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, bulk, int, ser, RESP};
+///
+/// let value = RESP::map(vec![(bulk!(b"foo".to_vec()), array![int!(-42), RESP::double(1.5)])]);
+/// assert_eq!(ser::to_vec(&value).unwrap().len(), ser::serialized_size(&value));
+/// assert_eq!(4, ser::serialized_size(&int!(1)));
+/// ```
+pub fn serialized_size(value: &RESPType) -> usize {
+    let mut size = 0;
+    value.walk(|_, value| {
+        size += match value {
+            RESPType::SimpleString(s) | RESPType::Error(s) | RESPType::BigNumber(s) => s.len() + 3,
+            RESPType::Integer(i) => header_size(*i),
+            RESPType::BulkString(Some(b)) => header_size(b.len()) + b.len() + 2,
+            RESPType::BulkString(None) | RESPType::Array(None) => 5,
+            RESPType::Array(Some(arr)) | RESPType::Push(arr) | RESPType::Set(arr) => {
+                header_size(arr.len())
+            }
+            RESPType::Map(pairs) => header_size(pairs.len()),
+            RESPType::Attribute { attributes, .. } => header_size(attributes.len()),
+            RESPType::Double(v) => format_double(*v).len() + 3,
+            RESPType::Boolean(_) => 4,
+            RESPType::Null => 3,
+            RESPType::Verbatim { format, data } => {
+                let len = format.len() + 1 + data.len();
+                header_size(len) + len + 2
+            }
+        }
+    });
+    size
+}
+
+// Length of a header line such as `$5\r\n`, see `encode_header`.
+fn header_size<I: itoa::Integer>(v: I) -> usize {
+    itoa::Buffer::new().format(v).len() + 3
+}
+
+/// Serialize to writer with `fmt::Write` trait, such as a `String` or a `fmt::Formatter`.
+///
+/// Text is written as it is serialized, without buffering the whole value.
+///
+/// # Errors
+/// Fails with [Error::FromUtf8](Error::FromUtf8) if a bulk string is not valid utf-8,
+/// where `valid_up_to` counts from the start of the value. Everything before the bulk string has already been written.
+/// Please refer to [Error](Error)
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, bulk, int, ser, Error, RESP};
+///
+/// let mut text = String::from("reply: ");
+/// ser::to_fmt_writer(&array![int!(1), bulk!(b"foo".to_vec())], &mut text).unwrap();
+/// assert_eq!("reply: *2\r\n:1\r\n$3\r\nfoo\r\n", text);
+///
+/// let mut text = String::new();
+/// match ser::to_fmt_writer(&array![bulk!(vec![b'a', 0xff])], &mut text).unwrap_err() {
+///     Error::FromUtf8 { valid_up_to, .. } => assert_eq!(9, valid_up_to),
+///     e => panic!("unexpected error {}", e),
+/// }
+/// ```
+pub fn to_fmt_writer<T, W>(value: &T, writer: &mut W) -> Result<()>
+where
+    T: Serialize,
+    W: fmt::Write + ?Sized,
+{
+    let mut writer = FmtWriter {
+        writer,
+        written: 0,
+        error: None,
+    };
+    let result = to_writer(value, &mut writer);
+    match writer.error {
+        Some(e) => Err(e),
+        None => result,
+    }
+}
+
+// `io::Write` over a `fmt::Write`, refusing bytes that are not utf-8.
+// The serializer writes every string and bulk string in a single call, so a character never spans two calls.
+struct FmtWriter<'a, W: ?Sized> {
+    writer: &'a mut W,
+    // Bytes written so far.
+    written: usize,
+    // Set when the bytes were not utf-8, to be returned instead of the io error.
+    error: Option<Error>,
+}
+
+impl<'a, W: fmt::Write + ?Sized> Write for FmtWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = match str::from_utf8(buf) {
+            Ok(text) => text,
+            Err(e) => {
+                self.error = Some(Error::FromUtf8 {
+                    valid_up_to: self.written + e.valid_up_to(),
+                    error_len: e.error_len(),
+                });
+                return Err(io::ErrorKind::InvalidData.into());
+            }
+        };
+        self.writer
+            .write_str(text)
+            .map_err(|_| io::Error::other("formatter error"))?;
+        self.written += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 /// Serialize to writer with `Write` trait.
@@ -34,18 +513,77 @@ where
     T: Serialize,
     W: Write,
 {
-    let mut serializer = Serializer { writer };
+    let mut serializer = Serializer::new(writer);
     value.serialize(&mut serializer)?;
     Ok(())
 }
 
-impl<'a, W> ser::Serializer for &'a mut Serializer<W>
+/// Serialize to an async writer with tokio's `AsyncWrite` trait, such as the writing half of a socket.
+/// Requires feature `tokio`.
+///
+/// Long bulk strings are written straight from `value` rather than copied, the rest of the value is encoded
+/// into a buffer first. The writer is not flushed.
+///
+/// # Errors
+/// Please refer to [Error](Error). Nothing is written if `value` fails to serialize.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, bulk, int, ser, RESP};
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let mut writer = Vec::new();
+/// ser::to_async_writer(&array![int!(1), bulk!(b"foo".to_vec())], &mut writer).await.unwrap();
+/// assert_eq!(b"*2\r\n:1\r\n$3\r\nfoo\r\n", &writer[..]);
+///
+/// let reply = bulk!(vec![b'x'; 1 << 20]);
+/// let mut writer = Vec::new();
+/// ser::to_async_writer(&reply, &mut writer).await.unwrap();
+/// assert_eq!(ser::to_vec(&reply).unwrap(), writer);
+/// # });
+/// ```
+#[cfg(feature = "tokio")]
+pub async fn to_async_writer<W>(value: &RESPType, writer: &mut W) -> Result<()>
+where
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    crate::async_io::write_value(value, &mut crate::async_io::Tokio(writer)).await
+}
+
+/// Serialize to an async writer with the `AsyncWrite` trait of futures-io, such as those of async-std and smol.
+/// Requires feature `futures-io`.
+///
+/// Writes like [to_async_writer](to_async_writer) does with tokio's traits. The writer is not flushed.
+///
+/// # Errors
+/// Please refer to [Error](Error). Nothing is written if `value` fails to serialize.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, bulk, ser, RESP};
+///
+/// futures::executor::block_on(async {
+///     let mut writer = Vec::new();
+///     ser::to_futures_writer(&array![bulk!(b"PING".to_vec())], &mut writer).await.unwrap();
+///     assert_eq!(b"*1\r\n$4\r\nPING\r\n", &writer[..]);
+/// });
+/// ```
+#[cfg(feature = "futures-io")]
+pub async fn to_futures_writer<W>(value: &RESPType, writer: &mut W) -> Result<()>
+where
+    W: futures_io::AsyncWrite + Unpin,
+{
+    crate::async_io::write_value(value, &mut crate::async_io::Futures(writer)).await
+}
+
+impl<'a, W, M> ser::Serializer for &'a mut Serializer<W, M>
 where
     W: Write,
+    M: Metrics,
 {
     type Ok = ();
     type Error = Error;
-    type SerializeSeq = Self;
+    type SerializeSeq = SeqSerializer<'a, W, M>;
     type SerializeTuple = Self;
     type SerializeTupleStruct = Self;
     type SerializeTupleVariant = Self;
@@ -53,8 +591,14 @@ where
     type SerializeStruct = Self;
     type SerializeStructVariant = Self;
 
-    fn serialize_bool(self, _v: bool) -> Result<()> {
-        unimplemented!()
+    // RESPType::Boolean, an integer in RESP2
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        if self.is_resp2() {
+            return self.serialize_i64(v as i64);
+        }
+        self.write(if v { b"#t\r\n" } else { b"#f\r\n" })?;
+        self.frame_written(RESPKind::Boolean);
+        Ok(())
     }
 
     fn serialize_i8(self, _v: i8) -> Result<()> {
@@ -70,9 +614,8 @@ where
     }
 
     fn serialize_i64(self, v: i64) -> Result<()> {
-        self.writer.write_all(b":")?;
-        itoa::write(&mut self.writer, v)?;
-        self.writer.write_all(b"\r\n")?;
+        self.write_header(b':', v)?;
+        self.frame_written(RESPKind::Integer);
         Ok(())
     }
 
@@ -96,8 +639,16 @@ where
         unimplemented!()
     }
 
-    fn serialize_f64(self, _v: f64) -> Result<()> {
-        unimplemented!()
+    // RESPType::Double, a bulk string in RESP2
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        if self.is_resp2() {
+            return self.serialize_bytes(format_double(v).as_bytes());
+        }
+        self.write(b",")?;
+        self.write(format_double(v).as_bytes())?;
+        self.write(b"\r\n")?;
+        self.frame_written(RESPKind::Double);
+        Ok(())
     }
 
     // Serialize a char as a single-character string.
@@ -105,26 +656,48 @@ where
         self.serialize_str(&v.to_string())
     }
 
-    // Used by `RESPType::SimpleString` and `RESPType::Error`. Do not use directly!
+    // Content of `RESPType::SimpleString`, `RESPType::Error` and `RESPType::BigNumber`, bulk string otherwise.
     fn serialize_str(self, v: &str) -> Result<()> {
-        self.writer.write_all(v.as_bytes())?;
-        self.writer.write_all(b"\r\n")?;
-        Ok(())
+        match self.marked.take() {
+            Some(RESPKind::BigNumber) if self.is_resp2() => self.serialize_bytes(v.as_bytes()),
+            Some(kind) => {
+                if v.contains(['\r', '\n']) {
+                    return Err(Error::LineBreak { kind });
+                }
+                self.write(&[kind.type_byte()])?;
+                self.write(v.as_bytes())?;
+                self.write(b"\r\n")?;
+                self.frame_written(kind);
+                Ok(())
+            }
+            None => self.serialize_bytes(v.as_bytes()),
+        }
     }
 
-    // Bulk string (Not null)
+    // Bulk string (Not null), the content of `RESPType::Verbatim`, or the content of `SharedResp`.
     fn serialize_bytes(self, v: &[u8]) -> Result<()> {
-        self.writer.write_all(b"$")?;
-        itoa::write(&mut self.writer, v.len() as u64)?;
-        self.writer.write_all(b"\r\n")?;
-        self.writer.write_all(v)?;
-        self.writer.write_all(b"\r\n")?;
+        if std::mem::take(&mut self.encoded) {
+            self.write(v)?;
+            if let Some(kind) = v.first().copied().and_then(RESPKind::from_type_byte) {
+                self.frame_written(kind);
+            }
+            return Ok(());
+        }
+        let (kind, v) = match self.marked.take() {
+            Some(RESPKind::Verbatim) if self.is_resp2() => (RESPKind::BulkString, &v[4..]), // after `fmt:`
+            kind => (kind.unwrap_or(RESPKind::BulkString), v),
+        };
+        self.write_header(kind.type_byte(), v.len())?;
+        self.write(v)?;
+        self.write(b"\r\n")?;
+        self.frame_written(kind);
         Ok(())
     }
 
     // RESPType::BulkString::Null
     fn serialize_none(self) -> Result<()> {
-        self.writer.write_all(b"$-1\r\n")?;
+        self.write(b"$-1\r\n")?;
+        self.frame_written(RESPKind::BulkString);
         Ok(())
     }
 
@@ -137,12 +710,22 @@ where
 
     // RESPType::Array::Null
     fn serialize_unit(self) -> Result<()> {
-        self.writer.write_all(b"*-1\r\n")?;
+        self.write(b"*-1\r\n")?;
+        self.frame_written(RESPKind::Array);
         Ok(())
     }
 
-    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
-        unimplemented!()
+    // RESPType::Null, a null bulk string in RESP2
+    fn serialize_unit_struct(self, name: &'static str) -> Result<()> {
+        if name != NULL_TOKEN {
+            unimplemented!()
+        }
+        if self.is_resp2() {
+            return self.serialize_none();
+        }
+        self.write(b"_\r\n")?;
+        self.frame_written(RESPKind::Null);
+        Ok(())
     }
 
     fn serialize_unit_variant(
@@ -154,11 +737,24 @@ where
         unimplemented!()
     }
 
-    fn serialize_newtype_struct<T>(self, _name: &'static str, _value: &T) -> Result<()>
+    // Simple strings, errors and RESP3 types are marked by name, other newtypes are transparent.
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        unimplemented!()
+        self.marked = match name {
+            SIMPLE_STRING_TOKEN => Some(RESPKind::SimpleString),
+            ERROR_TOKEN => Some(RESPKind::Error),
+            BIG_NUMBER_TOKEN => Some(RESPKind::BigNumber),
+            VERBATIM_TOKEN => Some(RESPKind::Verbatim),
+            PUSH_TOKEN => Some(RESPKind::Push),
+            ATTRIBUTE_TOKEN => Some(RESPKind::Attribute),
+            MAP_TOKEN => Some(RESPKind::Map),
+            SET_TOKEN => Some(RESPKind::Set),
+            _ => None,
+        };
+        self.encoded = name == ENCODED_TOKEN;
+        value.serialize(self)
     }
 
     fn serialize_newtype_variant<T>(
@@ -174,17 +770,48 @@ where
         unimplemented!()
     }
 
-    // Write beginning of array
+    // Write beginning of array, push, attribute, map or set, or buffer the elements if their number is unknown.
+    // The elements of a map are its keys and values, and those of an attribute are followed by the value it is about.
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
-        match len {
-            None => unimplemented!(),
-            Some(len) => {
-                self.writer.write_all(b"*")?;
-                itoa::write(&mut self.writer, len as u64)?;
-                self.writer.write_all(b"\r\n")?;
+        let mut kind = self.marked.take().unwrap_or(RESPKind::Array);
+        if self.is_resp2() {
+            match kind {
+                RESPKind::Push | RESPKind::Map | RESPKind::Set => kind = RESPKind::Array,
+                RESPKind::Attribute => {
+                    let len = len
+                        .ok_or_else(|| Error::Message("attribute of unknown length".to_owned()))?;
+                    return Ok(SeqSerializer {
+                        serializer: self,
+                        kind,
+                        elements: Elements::Dropped(len.saturating_sub(1)),
+                    });
+                }
+                _ => {}
             }
         }
-        Ok(self)
+        let elements = match len {
+            None if kind.can_stream() && self.protocol == Some(Protocol::Resp3) => {
+                self.write(&[kind.type_byte(), b'?', b'\r', b'\n'])?;
+                self.depth += 1;
+                Elements::Streamed
+            }
+            None => {
+                let mut elements = Serializer::new(Vec::new());
+                elements.protocol = self.protocol;
+                elements.depth = 1; // The elements are not top-level frames.
+                Elements::Buffered(elements, 0)
+            }
+            Some(len) => {
+                self.write_header(kind.type_byte(), header_len(kind, len))?;
+                self.depth += 1;
+                Elements::Written
+            }
+        };
+        Ok(SeqSerializer {
+            serializer: self,
+            kind,
+            elements,
+        })
     }
 
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
@@ -228,6 +855,58 @@ where
     }
 }
 
+// Text of a double as RESP3 writes it, which parses back to the same number.
+// Display writes `inf` and `-inf` already, but spells out every digit, so very large and very small
+// numbers use an exponent instead.
+pub(crate) fn format_double(v: f64) -> String {
+    if v.is_nan() {
+        "nan".to_owned()
+    } else if v != 0.0 && v.is_finite() && !(1e-5..1e17).contains(&v.abs()) {
+        format!("{:e}", v)
+    } else {
+        v.to_string()
+    }
+}
+
+/// Serializer of the elements of an array, returned by `serialize_seq`.
+///
+/// Sequences of unknown length, such as those of iterators serialized with
+/// [Serializer::collect_seq](serde::Serializer::collect_seq), are buffered until they end,
+/// since the array header comes first and holds the number of elements.
+/// In [RESP3](Protocol::Resp3) they are streamed instead, see [Serializer::with_protocol](Serializer::with_protocol).
+///
+/// # Examples
+/// ```
+/// use serde::Serializer as _;
+/// use serde_resp::ser::Serializer;
+/// use serde_resp::RESP;
+///
+/// let mut buf = Vec::new();
+/// let mut serializer = Serializer::new(&mut buf);
+/// let odd = (1..=3).filter(|i| i % 2 == 1).map(RESP::int);
+/// (&mut serializer).collect_seq(odd).unwrap();
+/// assert_eq!(Some(12), serializer.last_frame_len());
+/// assert_eq!(b"*2\r\n:1\r\n:3\r\n", &buf[..]);
+/// ```
+pub struct SeqSerializer<'a, W: Write, M: Metrics = ()> {
+    serializer: &'a mut Serializer<W, M>,
+    // Array, push, attribute, map or set.
+    kind: RESPKind,
+    elements: Elements,
+}
+
+// How the elements of a sequence are written.
+enum Elements {
+    // Right away, after a header with their number.
+    Written,
+    // To a buffer, counting them, when their number is unknown.
+    Buffered(Serializer<Vec<u8>>, usize),
+    // Right away, after a header such as `*?` and followed by `.`, when their number is unknown in RESP3.
+    Streamed,
+    // Not at all, except the last one: the value of an attribute in RESP2, after this many keys and values.
+    Dropped(usize),
+}
+
 // The following impls deal with the serialization of compound types like
 // sequences. Serialization of such types is begun by a Serializer
 // method and followed by zero or more calls to serialize individual elements of
@@ -235,9 +914,10 @@ where
 //
 // This impl is SerializeSeq so these methods are called after `serialize_seq`
 // is called on the Serializer.
-impl<'a, W> ser::SerializeSeq for &'a mut Serializer<W>
+impl<'a, W, M> ser::SerializeSeq for SeqSerializer<'a, W, M>
 where
     W: Write,
+    M: Metrics,
 {
     type Ok = ();
     type Error = Error;
@@ -247,18 +927,53 @@ where
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        match &mut self.elements {
+            Elements::Buffered(elements, len) => {
+                value.serialize(&mut *elements)?;
+                *len += 1;
+                Ok(())
+            }
+            Elements::Dropped(left) if *left > 0 => {
+                *left -= 1;
+                Ok(())
+            }
+            Elements::Written | Elements::Streamed => {
+                let serialized = value.serialize(&mut *self.serializer);
+                if serialized.is_err() {
+                    self.serializer.element_failed();
+                }
+                serialized
+            }
+            Elements::Dropped(_) => value.serialize(&mut *self.serializer),
+        }
     }
 
-    // Close the sequence.
+    // Close the sequence, writing it if it was buffered.
     fn end(self) -> Result<()> {
+        match self.elements {
+            Elements::Written => self.serializer.depth -= 1,
+            Elements::Buffered(elements, len) => {
+                let header_len = header_len(self.kind, len);
+                self.serializer
+                    .write_header(self.kind.type_byte(), header_len)?;
+                self.serializer.write(&elements.writer)?;
+            }
+            Elements::Streamed => {
+                self.serializer.depth -= 1;
+                self.serializer.write(b".\r\n")?;
+            }
+            // The value was reported as the frame.
+            Elements::Dropped(_) => return Ok(()),
+        }
+        self.serializer.frame_written(self.kind);
         Ok(())
     }
 }
 
-impl<'a, W> ser::SerializeTuple for &'a mut Serializer<W>
+impl<W, M> ser::SerializeTuple for &mut Serializer<W, M>
 where
     W: Write,
+    M: Metrics,
 {
     type Ok = ();
     type Error = Error;
@@ -275,9 +990,10 @@ where
     }
 }
 
-impl<'a, W> ser::SerializeTupleStruct for &'a mut Serializer<W>
+impl<W, M> ser::SerializeTupleStruct for &mut Serializer<W, M>
 where
     W: Write,
+    M: Metrics,
 {
     type Ok = ();
     type Error = Error;
@@ -294,9 +1010,10 @@ where
     }
 }
 
-impl<'a, W> ser::SerializeTupleVariant for &'a mut Serializer<W>
+impl<W, M> ser::SerializeTupleVariant for &mut Serializer<W, M>
 where
     W: Write,
+    M: Metrics,
 {
     type Ok = ();
     type Error = Error;
@@ -313,9 +1030,10 @@ where
     }
 }
 
-impl<'a, W> ser::SerializeMap for &'a mut Serializer<W>
+impl<W, M> ser::SerializeMap for &mut Serializer<W, M>
 where
     W: Write,
+    M: Metrics,
 {
     type Ok = ();
     type Error = Error;
@@ -336,9 +1054,10 @@ where
     }
 }
 
-impl<'a, W> ser::SerializeStruct for &'a mut Serializer<W>
+impl<W, M> ser::SerializeStruct for &mut Serializer<W, M>
 where
     W: Write,
+    M: Metrics,
 {
     type Ok = ();
     type Error = Error;
@@ -353,9 +1072,10 @@ where
     }
 }
 
-impl<'a, W> ser::SerializeStructVariant for &'a mut Serializer<W>
+impl<W, M> ser::SerializeStructVariant for &mut Serializer<W, M>
 where
     W: Write,
+    M: Metrics,
 {
     type Ok = ();
     type Error = Error;
@@ -380,8 +1100,8 @@ impl serde::Serialize for RESPType {
         S: serde::Serializer,
     {
         match self {
-            RESPType::SimpleString(str) => s.serialize_str(&("+".to_owned() + str)),
-            RESPType::Error(str) => s.serialize_str(&("-".to_owned() + str)),
+            RESPType::SimpleString(str) => s.serialize_newtype_struct(SIMPLE_STRING_TOKEN, str),
+            RESPType::Error(str) => s.serialize_newtype_struct(ERROR_TOKEN, str),
             RESPType::Integer(i) => s.serialize_i64(*i),
             RESPType::BulkString(bulk_str) => match bulk_str {
                 None => s.serialize_none(),
@@ -397,6 +1117,89 @@ impl serde::Serialize for RESPType {
                     s.end()
                 }
             },
+            RESPType::Double(v) => s.serialize_newtype_struct(DOUBLE_TOKEN, v),
+            RESPType::Boolean(v) => s.serialize_newtype_struct(BOOLEAN_TOKEN, v),
+            RESPType::BigNumber(digits) => s.serialize_newtype_struct(BIG_NUMBER_TOKEN, digits),
+            RESPType::Verbatim { format, data } => {
+                if format.len() != 3 {
+                    return Err(ser::Error::custom(format!(
+                        "verbatim string format {:?} is not 3 bytes long",
+                        format
+                    )));
+                }
+                s.serialize_newtype_struct(VERBATIM_TOKEN, &VerbatimContent { format, data })
+            }
+            RESPType::Null => s.serialize_unit_struct(NULL_TOKEN),
+            RESPType::Push(elements) => s.serialize_newtype_struct(PUSH_TOKEN, elements),
+            RESPType::Attribute { attributes, value } => {
+                s.serialize_newtype_struct(ATTRIBUTE_TOKEN, &AttributeContent { attributes, value })
+            }
+            RESPType::Map(pairs) => s.serialize_newtype_struct(MAP_TOKEN, &MapContent(pairs)),
+            RESPType::Set(elements) => s.serialize_newtype_struct(SET_TOKEN, elements),
         }
     }
 }
+
+// Number in the header of an aggregate of `len` elements: maps and attributes count their pairs,
+// not counting the value of an attribute.
+fn header_len(kind: RESPKind, len: usize) -> usize {
+    match kind {
+        RESPKind::Attribute | RESPKind::Map => len / 2,
+        _ => len,
+    }
+}
+
+impl RESPKind {
+    // Whether aggregates of this type can be streamed, with `?` for their length.
+    fn can_stream(self) -> bool {
+        matches!(self, RESPKind::Array | RESPKind::Map | RESPKind::Set)
+    }
+}
+
+// Content of a map as written on the wire: its keys and values. Also used for those of a RESPRef.
+pub(crate) struct MapContent<'a, T>(pub(crate) &'a [(T, T)]);
+
+impl<'a, T: Serialize> Serialize for MapContent<'a, T> {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> result::Result<S::Ok, S::Error> {
+        let mut s = s.serialize_seq(Some(self.0.len() * 2))?;
+        for (k, v) in self.0 {
+            s.serialize_element(k)?;
+            s.serialize_element(v)?;
+        }
+        s.end()
+    }
+}
+
+// Content of an attribute as written on the wire: its keys and values, then the value it is about.
+pub(crate) struct AttributeContent<'a, T> {
+    pub(crate) attributes: &'a [(T, T)],
+    pub(crate) value: &'a T,
+}
+
+impl<'a, T: Serialize> Serialize for AttributeContent<'a, T> {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> result::Result<S::Ok, S::Error> {
+        let mut s = s.serialize_seq(Some(self.attributes.len() * 2 + 1))?;
+        for (k, v) in self.attributes {
+            s.serialize_element(k)?;
+            s.serialize_element(v)?;
+        }
+        s.serialize_element(self.value)?;
+        s.end()
+    }
+}
+
+// Content of a verbatim string as written on the wire, `format:data`.
+pub(crate) struct VerbatimContent<'a> {
+    pub(crate) format: &'a str,
+    pub(crate) data: &'a [u8],
+}
+
+impl<'a> Serialize for VerbatimContent<'a> {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> result::Result<S::Ok, S::Error> {
+        let mut content = Vec::with_capacity(self.format.len() + 1 + self.data.len());
+        content.extend_from_slice(self.format.as_bytes());
+        content.push(b':');
+        content.extend_from_slice(self.data);
+        s.serialize_bytes(&content)
+    }
+}
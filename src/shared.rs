@@ -0,0 +1,110 @@
+use crate::ser::{self, ENCODED_TOKEN};
+use crate::{RESPType, Result};
+
+use serde::{Serialize, Serializer};
+use std::io::Write;
+use std::sync::{Arc, OnceLock};
+
+/// A [RESPType](RESPType) shared behind an `Arc` and serialized at most once, for sending the same value many times.
+/// Cloning it is cheap.
+///
+/// Serializing it with [ser::to_writer](crate::ser::to_writer) writes its cached encoding,
+/// also when it is nested in other values, such as the elements of a `Vec<SharedResp>`.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{bulk, ser, value, RESP};
+///
+/// let message = bulk!(b"hello subscribers".to_vec()).into_shared();
+/// let mut subscribers = vec![Vec::new(); 3];
+/// for subscriber in &mut subscribers {
+///     // serialized on the first write, copied afterwards
+///     message.clone().write_to(subscriber).unwrap();
+/// }
+/// assert_eq!(b"$17\r\nhello subscribers\r\n", &subscribers[2][..]);
+/// assert_eq!(message.value(), &value::to_value(&message).unwrap());
+///
+/// let batch = vec![message.clone(), message];
+/// assert_eq!(
+///     "*2\r\n$17\r\nhello subscribers\r\n$17\r\nhello subscribers\r\n",
+///     ser::to_string(&batch).unwrap()
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct SharedResp {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    value: RESPType,
+    encoded: OnceLock<Result<Vec<u8>>>,
+}
+
+impl SharedResp {
+    /// Share `value`.
+    pub fn new(value: RESPType) -> Self {
+        SharedResp {
+            inner: Arc::new(Inner {
+                value,
+                encoded: OnceLock::new(),
+            }),
+        }
+    }
+
+    /// The shared value.
+    pub fn value(&self) -> &RESPType {
+        &self.inner.value
+    }
+
+    /// The serialized value, serializing it if this is the first time it is needed.
+    pub fn encoded(&self) -> Result<&[u8]> {
+        let Inner { value, encoded } = &*self.inner;
+        let encode = || ser::to_vec_with_capacity(value, ser::serialized_size(value));
+        match encoded.get_or_init(encode) {
+            Ok(encoded) => Ok(encoded),
+            Err(e) => Err(e.clone()),
+        }
+    }
+
+    /// Write the serialized value to `writer`.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(self.encoded()?)?;
+        Ok(())
+    }
+}
+
+impl RESPType {
+    /// Share this value, see [SharedResp](SharedResp).
+    pub fn into_shared(self) -> SharedResp {
+        SharedResp::new(self)
+    }
+}
+
+impl From<RESPType> for SharedResp {
+    fn from(value: RESPType) -> Self {
+        SharedResp::new(value)
+    }
+}
+
+impl PartialEq for SharedResp {
+    fn eq(&self, other: &Self) -> bool {
+        self.value() == other.value()
+    }
+}
+
+// Already encoded bytes, written as they are by `ser::Serializer`.
+pub(crate) struct Encoded<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Serialize for Encoded<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> {
+        s.serialize_bytes(self.0)
+    }
+}
+
+impl Serialize for SharedResp {
+    fn serialize<S: Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> {
+        let encoded = self.encoded().map_err(serde::ser::Error::custom)?;
+        s.serialize_newtype_struct(ENCODED_TOKEN, &Encoded(encoded))
+    }
+}
@@ -0,0 +1,171 @@
+//! `Sink` of the frames written to an async writer. Requires feature `sink`.
+//!
+//! [RespSink](RespSink) wraps a tokio `AsyncWrite`, such as the writing half of a `TcpStream`, and takes
+//! [RESPType](crate::RESPType)s. Frames are serialized into a buffer,
+//! which is written out once it grows past [a limit](RespSink::with_max_buffered), or when the sink is flushed.
+//! While that much is buffered and the writer does not take it, the sink is not ready for more frames,
+//! so a slow peer slows down whoever sends to it rather than making the buffer grow.
+//!
+//! # Examples
+//! ```
+//! use futures::SinkExt;
+//! use serde_resp::sink::RespSink;
+//! use serde_resp::{array, bulk, int, RESP};
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let mut sink = RespSink::new(Vec::new());
+//! sink.feed(array![bulk!(b"INCR".to_vec()), bulk!(b"a".to_vec())]).await.unwrap();
+//! assert!(sink.get_ref().is_empty());
+//! sink.feed(int!(1)).await.unwrap();
+//! sink.flush().await.unwrap();
+//! assert_eq!(b"*2\r\n$4\r\nINCR\r\n$1\r\na\r\n:1\r\n", &sink.get_ref()[..]);
+//! # });
+//! ```
+
+use crate::ser::{Protocol, Serializer};
+use crate::{Error, RESPType, Result};
+
+use futures_sink::Sink;
+use serde::Serialize;
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::io::AsyncWrite;
+
+/// Sink of the frames written to `W`. Refer to [sink](self).
+#[derive(Debug)]
+pub struct RespSink<W> {
+    writer: W,
+    protocol: Option<Protocol>,
+    buf: Vec<u8>,
+    // Frames in `buf`, counting those partly written out.
+    frames: usize,
+    max_buffered: usize,
+    max_buffered_frames: usize,
+}
+
+impl<W: AsyncWrite + Unpin> RespSink<W> {
+    /// Write frames to `writer`, buffering up to 8 KiB of them.
+    pub fn new(writer: W) -> Self {
+        RespSink {
+            writer,
+            protocol: None,
+            buf: Vec::new(),
+            frames: 0,
+            max_buffered: 8 * 1024,
+            max_buffered_frames: usize::MAX,
+        }
+    }
+
+    /// Once `max` bytes of frames are buffered, write them out, and wait for the writer to take them all
+    /// before taking more. A frame is buffered whole, so the buffer may grow past `max` by one frame.
+    pub fn with_max_buffered(mut self, max: usize) -> Self {
+        self.max_buffered = max;
+        self
+    }
+
+    /// Once `max` frames are buffered, write them out, and wait for the writer to take them all
+    /// before taking more, like [with_max_buffered](RespSink::with_max_buffered).
+    pub fn with_max_buffered_frames(mut self, max: usize) -> Self {
+        self.max_buffered_frames = max;
+        self
+    }
+
+    /// Write frames for `protocol`. Refer to [Serializer::with_protocol](Serializer::with_protocol).
+    pub fn with_protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    /// Switch the protocol frames are written for, such as after a client sent `HELLO 3`.
+    pub fn set_protocol(&mut self, protocol: Protocol) {
+        self.protocol = Some(protocol);
+    }
+
+    /// The bytes of the frames buffered and not written out yet.
+    pub fn write_buffer(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Get the writer back. The frames buffered, see [write_buffer](RespSink::write_buffer), are lost.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Get a reference to the writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Get a mutable reference to the writer. Writing to it directly may interleave with the frames buffered.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    // Write out the whole buffer, keeping what the writer did not take yet if it fails or is not ready.
+    fn poll_write_buf(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let mut written = 0;
+        let polled = loop {
+            if written == self.buf.len() {
+                break Poll::Ready(Ok(()));
+            }
+            match Pin::new(&mut self.writer).poll_write(cx, &self.buf[written..]) {
+                Poll::Ready(Ok(0)) => {
+                    let e = io::Error::from(io::ErrorKind::WriteZero);
+                    break Poll::Ready(Err(Error::from(e)));
+                }
+                Poll::Ready(Ok(n)) => written += n,
+                Poll::Ready(Err(e)) => break Poll::Ready(Err(Error::from(e))),
+                Poll::Pending => break Poll::Pending,
+            }
+        };
+        self.buf.drain(..written);
+        if self.buf.is_empty() {
+            self.frames = 0;
+        }
+        polled
+    }
+}
+
+impl<W: AsyncWrite + Unpin> Sink<RESPType> for RespSink<W> {
+    type Error = Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        if this.buf.len() < this.max_buffered && this.frames < this.max_buffered_frames {
+            return Poll::Ready(Ok(()));
+        }
+        this.poll_write_buf(cx)
+    }
+
+    /// Serialize `item` into the buffer. If it fails to serialize, nothing is buffered.
+    fn start_send(self: Pin<&mut Self>, item: RESPType) -> Result<()> {
+        let this = self.get_mut();
+        let len = this.buf.len();
+        let mut serializer = Serializer::new(&mut this.buf);
+        let serialized = match this.protocol {
+            Some(protocol) => item.serialize(&mut serializer.with_protocol(protocol)),
+            None => item.serialize(&mut serializer),
+        };
+        match serialized {
+            Ok(()) => this.frames += 1,
+            Err(_) => this.buf.truncate(len),
+        }
+        serialized
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_buf(cx))?;
+        Pin::new(&mut this.writer)
+            .poll_flush(cx)
+            .map_err(Error::from)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        Pin::new(&mut self.get_mut().writer)
+            .poll_shutdown(cx)
+            .map_err(Error::from)
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{RESPKind, RESPType};
+
+use std::collections::HashMap;
+
+/// Size and shape of a value, returned by [RESPType::stats](RESPType::stats).
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Stats {
+    /// Number of values, counting the value itself and everything nested in it.
+    pub values: usize,
+    /// Nesting depth of arrays, pushes and attributes, as limited by [Limits::max_depth](crate::de::Limits::max_depth).
+    /// A top-level array has depth 1, null arrays do not count.
+    pub max_depth: usize,
+    /// Total length of all bulk strings.
+    pub bulk_bytes: usize,
+    /// Number of values of each type, including nulls.
+    pub counts: HashMap<RESPKind, usize>,
+}
+
+impl Stats {
+    /// Number of values of type `kind`.
+    pub fn count(&self, kind: RESPKind) -> usize {
+        self.counts.get(&kind).copied().unwrap_or(0)
+    }
+}
+
+impl RESPType {
+    /// Count the values in this value, and measure it.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{array, bulk, bulk_null, int, RESPKind, RESP};
+    ///
+    /// let reply = array![int!(1), array![bulk!(b"foo".to_vec()), bulk_null!()]];
+    /// let stats = reply.stats();
+    /// assert_eq!(5, stats.values);
+    /// assert_eq!(2, stats.max_depth);
+    /// assert_eq!(3, stats.bulk_bytes);
+    /// assert_eq!(2, stats.count(RESPKind::BulkString));
+    /// assert_eq!(0, stats.count(RESPKind::Error));
+    /// ```
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        self.walk(|path, value| {
+            stats.values += 1;
+            *stats.counts.entry(value.kind()).or_insert(0) += 1;
+            match value {
+                RESPType::BulkString(Some(b)) => stats.bulk_bytes += b.len(),
+                RESPType::Array(Some(_))
+                | RESPType::Push(_)
+                | RESPType::Attribute { .. }
+                | RESPType::Map(_)
+                | RESPType::Set(_) => stats.max_depth = stats.max_depth.max(path.depth() + 1),
+                _ => {}
+            }
+        });
+        stats
+    }
+}
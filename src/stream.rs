@@ -0,0 +1,127 @@
+//! `Stream` of the frames read from an async reader. Requires feature `stream`.
+//!
+//! [RespStream](RespStream) wraps a tokio `AsyncRead`, such as the reading half of a `TcpStream`, and yields
+//! each frame as soon as it is complete, so a connection can be handled with
+//! `while let Some(frame) = stream.next().await`. Bytes are parsed as they arrive, like with a
+//! [RespParser](crate::parser::RespParser), and a frame is decoded only once all of it has been received.
+//!
+//! # Examples
+//! ```
+//! use futures::StreamExt;
+//! use serde_resp::stream::RespStream;
+//! use serde_resp::{array, bulk, int, RESP};
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let reader = "*2\r\n$3\r\nGET\r\n$1\r\na\r\n:1\r\n".as_bytes();
+//! let mut stream = RespStream::new(reader);
+//! assert_eq!(array![bulk!(b"GET".to_vec()), bulk!(b"a".to_vec())], stream.next().await.unwrap().unwrap());
+//! assert_eq!(int!(1), stream.next().await.unwrap().unwrap());
+//! assert!(stream.next().await.is_none());
+//!
+//! let mut stream = RespStream::new("+OK\r\n:1".as_bytes());
+//! assert!(stream.next().await.unwrap().is_ok());
+//! assert!(stream.next().await.unwrap().unwrap_err().is_eof());
+//! assert!(stream.next().await.is_none());
+//! # });
+//! ```
+
+use crate::de::Limits;
+use crate::parser::RespParser;
+use crate::{Error, RESPType, Result};
+
+use futures_core::Stream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, ReadBuf};
+
+// Bytes asked from the reader at once.
+const READ_SIZE: usize = 8 * 1024;
+
+/// Stream of the frames read from `R`. Refer to [stream](self).
+///
+/// The stream ends at the end of the reader, or after the first failure.
+#[derive(Debug)]
+pub struct RespStream<R> {
+    reader: R,
+    parser: RespParser,
+    done: bool,
+}
+
+impl<R: AsyncRead + Unpin> RespStream<R> {
+    /// Read frames from `reader`.
+    pub fn new(reader: R) -> Self {
+        RespStream {
+            reader,
+            parser: RespParser::new(),
+            done: false,
+        }
+    }
+
+    /// Set the limits on frames read. Refer to [Limits](Limits).
+    ///
+    /// Lines and bulk strings over the limits fail as soon as they are received, without waiting for the rest
+    /// of the frame.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.parser = self.parser.with_limits(limits);
+        self
+    }
+
+    /// The bytes read after the frames returned so far.
+    pub fn read_buffer(&self) -> &[u8] {
+        self.parser.buffer()
+    }
+
+    /// Get the reader back. The bytes read after the frames returned so far, see [read_buffer](RespStream::read_buffer),
+    /// are lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Get a reference to the reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Get a mutable reference to the reader. Reading from it directly corrupts the stream.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    // Decode the next frame, reading until it is complete.
+    fn poll_frame(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<RESPType>>> {
+        loop {
+            if let Some(frame) = self.parser.next_frame()? {
+                return Poll::Ready(Some(Ok(frame)));
+            }
+            let buf = self.parser.buf_mut();
+            let start = buf.len();
+            buf.resize(start + READ_SIZE, 0);
+            let mut read_buf = ReadBuf::new(&mut buf[start..]);
+            let polled = Pin::new(&mut self.reader).poll_read(cx, &mut read_buf);
+            let read = read_buf.filled().len();
+            buf.truncate(start + read);
+            match polled {
+                Poll::Ready(Ok(())) if read == 0 && start == 0 => return Poll::Ready(None),
+                Poll::Ready(Ok(())) if read == 0 => return Poll::Ready(Some(Err(Error::Eof))),
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(e)) => return Poll::Ready(Some(Err(Error::from(e)))),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for RespStream<R> {
+    type Item = Result<RESPType>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        let polled = self.poll_frame(cx);
+        if let Poll::Ready(Some(Err(_))) = polled {
+            self.done = true;
+        }
+        polled
+    }
+}
@@ -0,0 +1,864 @@
+//! Generating RESP values for fuzzing, property tests and load tests, and checking that they round-trip.
+//!
+//! [verify_roundtrip](verify_roundtrip) and [verify_bytes_roundtrip](verify_bytes_roundtrip) check that
+//! serializing and deserializing gives back the same value or bytes.
+//!
+//! [GenConfig](GenConfig) bounds the size of generated values and sets the mix of their types.
+//! [Generator](Generator) generates random values from a seed, deterministically.
+//! The integrations with generator crates are behind features:
+//! - `arbitrary`: [arbitrary::Arbitrary](https://docs.rs/arbitrary) for [RESPType](crate::RESPType),
+//!   and [GenConfig::arbitrary](GenConfig::arbitrary) for custom bounds.
+//! - `proptest`: [proptest::arbitrary::Arbitrary](https://docs.rs/proptest) for [RESPType](crate::RESPType) with
+//!   [GenConfig](GenConfig) as parameters, and strategies for wire bytes in [GenConfig::wire_strategy](GenConfig::wire_strategy)
+//!   and [GenConfig::invalid_wire_strategy](GenConfig::invalid_wire_strategy).
+//! - `quickcheck`: [quickcheck::Arbitrary](https://docs.rs/quickcheck) for [RESPType](crate::RESPType),
+//!   and [GenConfig::quickcheck](GenConfig::quickcheck) for custom bounds.
+
+use crate::{de, diff, ser, text, Error, RESPKind, RESPType, Result};
+
+// Formats of generated verbatim strings.
+const VERBATIM_FORMATS: [&str; 2] = ["txt", "mkd"];
+
+/// Bounds on the size of generated values, and the mix of their types.
+///
+/// Generated values always serialize to valid RESP: simple strings and errors never contain CR or LF.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GenConfig {
+    /// Maximum nesting depth of arrays. A top-level array has depth 1, so 0 generates no arrays.
+    pub max_depth: usize,
+    /// Maximum number of elements in an array.
+    pub max_array_len: usize,
+    /// Maximum length of a bulk string.
+    pub max_bulk_len: usize,
+    /// Maximum length in bytes of a simple string or error.
+    pub max_line_len: usize,
+    /// How often each type is generated.
+    pub weights: Weights,
+}
+
+impl Default for GenConfig {
+    fn default() -> Self {
+        GenConfig {
+            max_depth: 4,
+            max_array_len: 8,
+            max_bulk_len: 64,
+            max_line_len: 32,
+            weights: Weights::default(),
+        }
+    }
+}
+
+/// Relative frequencies of the types of generated values. A type with weight 0 is never generated.
+///
+/// Arrays are not generated at [max_depth](GenConfig::max_depth), so one of the other weights must not be 0.
+/// Null bulk strings and null arrays are generated with 1 in 8 of their type.
+/// RESP3 types have weight 0 by default, so only RESP2 values are generated unless they are given a weight.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Weights {
+    /// Weight of simple strings.
+    pub simple_string: u32,
+    /// Weight of errors.
+    pub error: u32,
+    /// Weight of integers.
+    pub integer: u32,
+    /// Weight of bulk strings.
+    pub bulk_string: u32,
+    /// Weight of arrays.
+    pub array: u32,
+    /// Weight of RESP3 doubles.
+    pub double: u32,
+    /// Weight of RESP3 booleans.
+    pub boolean: u32,
+    /// Weight of RESP3 big numbers.
+    pub big_number: u32,
+    /// Weight of RESP3 verbatim strings, in format `txt` or `mkd`, as long as bulk strings.
+    pub verbatim: u32,
+    /// Weight of RESP3 nulls.
+    pub null: u32,
+    /// Weight of RESP3 pushes, which are limited like arrays.
+    pub push: u32,
+    /// Weight of RESP3 attributes, which are limited like arrays, counting their keys, values and value as elements.
+    pub attribute: u32,
+    /// Weight of RESP3 maps, which are limited like arrays, counting their keys and values as elements.
+    pub map: u32,
+    /// Weight of RESP3 sets, which are limited like arrays.
+    pub set: u32,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            simple_string: 1,
+            error: 1,
+            integer: 1,
+            bulk_string: 1,
+            array: 1,
+            double: 0,
+            boolean: 0,
+            big_number: 0,
+            verbatim: 0,
+            null: 0,
+            push: 0,
+            attribute: 0,
+            map: 0,
+            set: 0,
+        }
+    }
+}
+
+impl GenConfig {
+    // Pick the type of a value at `depth` by weight, from a random number.
+    fn pick_kind(&self, depth: usize, roll: u32) -> RESPKind {
+        let w = self.weights;
+        let (array, push, attribute, map, set) = if depth < self.max_depth {
+            (w.array, w.push, w.attribute, w.map, w.set)
+        } else {
+            (0, 0, 0, 0, 0)
+        };
+        let weighted = [
+            (RESPKind::SimpleString, w.simple_string),
+            (RESPKind::Error, w.error),
+            (RESPKind::Integer, w.integer),
+            (RESPKind::BulkString, w.bulk_string),
+            (RESPKind::Array, array),
+            (RESPKind::Double, w.double),
+            (RESPKind::Boolean, w.boolean),
+            (RESPKind::BigNumber, w.big_number),
+            (RESPKind::Verbatim, w.verbatim),
+            (RESPKind::Null, w.null),
+            (RESPKind::Push, push),
+            (RESPKind::Attribute, attribute),
+            (RESPKind::Map, map),
+            (RESPKind::Set, set),
+        ];
+        let total: u64 = weighted.iter().map(|(_, w)| u64::from(*w)).sum();
+        assert!(
+            total > 0,
+            "all weights of types allowed at depth {} are 0",
+            depth
+        );
+        let mut roll = u64::from(roll) % total;
+        for (kind, w) in weighted.iter() {
+            let w = u64::from(*w);
+            if roll < w {
+                return *kind;
+            }
+            roll -= w;
+        }
+        unreachable!()
+    }
+
+    // Most pairs of an attribute, counting its keys, values and value as elements.
+    fn max_attribute_len(&self) -> usize {
+        self.max_array_len.saturating_sub(1) / 2
+    }
+
+    // Most pairs of a map, counting its keys and values as elements.
+    fn max_map_len(&self) -> usize {
+        self.max_array_len / 2
+    }
+
+    // A string of `chars` with CR and LF replaced, cut to at most max_line_len bytes.
+    fn line(&self, chars: Vec<char>) -> String {
+        let mut s = String::new();
+        for c in chars {
+            let c = match c {
+                '\r' | '\n' => ' ',
+                c => c,
+            };
+            if s.len() + c.len_utf8() > self.max_line_len {
+                break;
+            }
+            s.push(c);
+        }
+        s
+    }
+}
+
+/// Deterministic generator of random values within a [GenConfig](GenConfig), for load and stress tests.
+///
+/// The same seed and config generate the same values on every platform.
+/// The randomness is not suitable for anything security related.
+///
+/// # Examples
+/// ```
+/// use serde_resp::testing::{GenConfig, Generator};
+/// use serde_resp::{ser, RESP};
+///
+/// let config = GenConfig {
+///     max_depth: 2,
+///     max_bulk_len: 1024,
+///     ..GenConfig::default()
+/// };
+/// let values: Vec<RESP> = Generator::new(42).with_config(config).take(100).collect();
+/// assert_eq!(values, Generator::new(42).with_config(config).take(100).collect::<Vec<_>>());
+/// for value in &values {
+///     ser::to_writer(value, &mut Vec::new()).unwrap();
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Generator {
+    state: u64,
+    config: GenConfig,
+}
+
+impl Generator {
+    /// Generate values within [GenConfig::default](GenConfig::default) from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Generator {
+            state: seed,
+            config: GenConfig::default(),
+        }
+    }
+
+    /// Generate values within `config` instead.
+    pub fn with_config(mut self, config: GenConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Generate the next value.
+    pub fn next_value(&mut self) -> RESPType {
+        self.value_at(0)
+    }
+
+    fn value_at(&mut self, depth: usize) -> RESPType {
+        let roll = self.next_u64() as u32;
+        match self.config.pick_kind(depth, roll) {
+            RESPKind::SimpleString => RESPType::SimpleString(self.line()),
+            RESPKind::Error => RESPType::Error(self.line()),
+            RESPKind::Integer => RESPType::Integer(if self.below(2) == 0 {
+                self.below(1000) as i64
+            } else {
+                self.next_u64() as i64
+            }),
+            RESPKind::BulkString => RESPType::BulkString(if self.below(8) == 0 {
+                None
+            } else {
+                let len = self.below(self.config.max_bulk_len.saturating_add(1));
+                Some((0..len).map(|_| self.next_u64() as u8).collect())
+            }),
+            RESPKind::Array => RESPType::Array(if self.below(8) == 0 {
+                None
+            } else {
+                let len = self.below(self.config.max_array_len.saturating_add(1));
+                Some((0..len).map(|_| self.value_at(depth + 1)).collect())
+            }),
+            RESPKind::Push => {
+                let len = self.below(self.config.max_array_len.saturating_add(1));
+                RESPType::Push((0..len).map(|_| self.value_at(depth + 1)).collect())
+            }
+            RESPKind::Double => RESPType::Double(if self.below(2) == 0 {
+                self.below(1000) as f64 / 8.0
+            } else {
+                f64::from_bits(self.next_u64())
+            }),
+            RESPKind::Boolean => RESPType::Boolean(self.below(2) == 0),
+            RESPKind::BigNumber => {
+                let high = i128::from(self.next_u64() as i64) << 64;
+                RESPType::BigNumber((high | i128::from(self.next_u64())).to_string())
+            }
+            RESPKind::Verbatim => {
+                let format = VERBATIM_FORMATS[self.below(VERBATIM_FORMATS.len())];
+                let len = self.below(self.config.max_bulk_len.saturating_add(1));
+                RESPType::verbatim(
+                    format,
+                    (0..len).map(|_| self.next_u64() as u8).collect::<Vec<_>>(),
+                )
+            }
+            RESPKind::Null => RESPType::Null,
+            RESPKind::Attribute => {
+                let len = self.below(self.config.max_attribute_len() + 1);
+                let attributes = (0..len)
+                    .map(|_| (self.value_at(depth + 1), self.value_at(depth + 1)))
+                    .collect::<Vec<_>>();
+                RESPType::attribute(attributes, self.value_at(depth + 1))
+            }
+            RESPKind::Map => {
+                let len = self.below(self.config.max_map_len() + 1);
+                RESPType::Map(
+                    (0..len)
+                        .map(|_| (self.value_at(depth + 1), self.value_at(depth + 1)))
+                        .collect(),
+                )
+            }
+            RESPKind::Set => {
+                let len = self.below(self.config.max_array_len.saturating_add(1));
+                RESPType::Set((0..len).map(|_| self.value_at(depth + 1)).collect())
+            }
+        }
+    }
+
+    fn line(&mut self) -> String {
+        let len = self.below(self.config.max_line_len.saturating_add(1));
+        let chars = (0..len).map(|_| self.char()).collect();
+        self.config.line(chars)
+    }
+
+    // Mostly printable ASCII, sometimes any character.
+    fn char(&mut self) -> char {
+        if self.below(8) == 0 {
+            std::char::from_u32(self.below(0x11_0000) as u32).unwrap_or('\u{fffd}')
+        } else {
+            (b' ' + self.below(95) as u8) as char
+        }
+    }
+
+    // Uniform in 0..n.
+    fn below(&mut self, n: usize) -> usize {
+        ((u128::from(self.next_u64()) * n as u128) >> 64) as usize
+    }
+
+    // SplitMix64.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+impl Iterator for Generator {
+    type Item = RESPType;
+
+    fn next(&mut self) -> Option<RESPType> {
+        Some(self.next_value())
+    }
+}
+
+/// Check that serializing `value` and deserializing the result gives back `value`.
+///
+/// Fails with the error from either step, or with a message pointing at the first difference.
+///
+/// # Examples
+/// ```
+/// use serde_resp::testing::verify_roundtrip;
+/// use serde_resp::{array, bulk, int, simple, ErrorKind, RESP};
+///
+/// verify_roundtrip(&array![int!(1), bulk!(b"foo".to_vec())]).unwrap();
+///
+/// let err = verify_roundtrip(&array![simple!("a\r\nb".to_owned())]).unwrap_err();
+/// assert_eq!(ErrorKind::InvalidValue, err.kind());
+/// ```
+pub fn verify_roundtrip(value: &RESPType) -> Result<()> {
+    let bytes = ser::to_vec(value)?;
+    let mut rest = &bytes[..];
+    let decoded: RESPType = de::from_buf_reader(&mut rest)?;
+    if let Some(difference) = diff::diff(value, &decoded) {
+        return Err(Error::Message(format!(
+            "value changed in a round trip {}",
+            difference
+        )));
+    }
+    if !rest.is_empty() {
+        return Err(Error::Message(format!(
+            "value serialized to {}, which has {} bytes after the value",
+            text::escape(&bytes),
+            rest.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Check that deserializing every value in `bytes` and serializing them again gives back `bytes`.
+///
+/// Fails with the error from either step, or with a message pointing at the first differing byte
+/// and showing the bytes around it. Input that is accepted but not in canonical form, such as lines ending with a bare LF, fails.
+///
+/// # Examples
+/// ```
+/// use serde_resp::testing::verify_bytes_roundtrip;
+///
+/// verify_bytes_roundtrip(b"+OK\r\n*1\r\n:1\r\n").unwrap();
+///
+/// let err = verify_bytes_roundtrip(b"+OK\n").unwrap_err();
+/// assert_eq!(
+///     r"bytes changed in a round trip at byte 3: +OK\n became +OK\r\n",
+///     err.to_string()
+/// );
+/// ```
+pub fn verify_bytes_roundtrip(bytes: &[u8]) -> Result<()> {
+    let values: Vec<RESPType> = de::read_many(&mut &bytes[..])?;
+    let mut encoded = Vec::with_capacity(bytes.len());
+    for value in &values {
+        ser::to_writer(value, &mut encoded)?;
+    }
+    if encoded != bytes {
+        let at = bytes
+            .iter()
+            .zip(&encoded)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let around = |b: &[u8]| text::escape(&b[at.saturating_sub(16)..b.len().min(at + 16)]);
+        return Err(Error::Message(format!(
+            "bytes changed in a round trip at byte {}: {} became {}",
+            at,
+            around(bytes),
+            around(&encoded)
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "arbitrary")]
+impl GenConfig {
+    /// Generate a value within these bounds from fuzzer input.
+    ///
+    /// # Examples
+    /// ```
+    /// use arbitrary::Unstructured;
+    /// use serde_resp::testing::GenConfig;
+    /// use serde_resp::RESP;
+    ///
+    /// let config = GenConfig {
+    ///     max_depth: 0,
+    ///     ..GenConfig::default()
+    /// };
+    /// let mut u = Unstructured::new(&[4, 0, 1, 2, 3, 4, 5, 6, 7]);
+    /// let value = config.arbitrary(&mut u).unwrap();
+    /// assert!(!matches!(value, RESP::Array(_)));
+    /// ```
+    pub fn arbitrary(&self, u: &mut arbitrary::Unstructured) -> arbitrary::Result<RESPType> {
+        self.arbitrary_at(u, 0)
+    }
+
+    fn arbitrary_at(
+        &self,
+        u: &mut arbitrary::Unstructured,
+        depth: usize,
+    ) -> arbitrary::Result<RESPType> {
+        Ok(match self.pick_kind(depth, u.arbitrary()?) {
+            RESPKind::SimpleString => RESPType::SimpleString(self.arbitrary_line(u)?),
+            RESPKind::Error => RESPType::Error(self.arbitrary_line(u)?),
+            RESPKind::Integer => RESPType::Integer(u.arbitrary()?),
+            RESPKind::BulkString => RESPType::BulkString(if u.ratio(1, 8)? {
+                None
+            } else {
+                let len = u.int_in_range(0..=self.max_bulk_len)?.min(u.len());
+                Some(u.bytes(len)?.to_vec())
+            }),
+            RESPKind::Array => RESPType::Array(if u.ratio(1, 8)? {
+                None
+            } else {
+                let len = u.int_in_range(0..=self.max_array_len)?;
+                let mut arr = Vec::with_capacity(len);
+                for _ in 0..len {
+                    arr.push(self.arbitrary_at(u, depth + 1)?);
+                }
+                Some(arr)
+            }),
+            RESPKind::Push => {
+                let len = u.int_in_range(0..=self.max_array_len)?;
+                let mut arr = Vec::with_capacity(len);
+                for _ in 0..len {
+                    arr.push(self.arbitrary_at(u, depth + 1)?);
+                }
+                RESPType::Push(arr)
+            }
+            RESPKind::Double => RESPType::Double(u.arbitrary()?),
+            RESPKind::Boolean => RESPType::Boolean(u.arbitrary()?),
+            RESPKind::BigNumber => RESPType::BigNumber(u.arbitrary::<i128>()?.to_string()),
+            RESPKind::Verbatim => {
+                let format = *u.choose(&VERBATIM_FORMATS)?;
+                let len = u.int_in_range(0..=self.max_bulk_len)?.min(u.len());
+                RESPType::verbatim(format, u.bytes(len)?)
+            }
+            RESPKind::Null => RESPType::Null,
+            RESPKind::Attribute => {
+                let len = u.int_in_range(0..=self.max_attribute_len())?;
+                let mut attributes = Vec::with_capacity(len);
+                for _ in 0..len {
+                    attributes.push((
+                        self.arbitrary_at(u, depth + 1)?,
+                        self.arbitrary_at(u, depth + 1)?,
+                    ));
+                }
+                RESPType::attribute(attributes, self.arbitrary_at(u, depth + 1)?)
+            }
+            RESPKind::Map => {
+                let len = u.int_in_range(0..=self.max_map_len())?;
+                let mut pairs = Vec::with_capacity(len);
+                for _ in 0..len {
+                    pairs.push((
+                        self.arbitrary_at(u, depth + 1)?,
+                        self.arbitrary_at(u, depth + 1)?,
+                    ));
+                }
+                RESPType::Map(pairs)
+            }
+            RESPKind::Set => {
+                let len = u.int_in_range(0..=self.max_array_len)?;
+                let mut arr = Vec::with_capacity(len);
+                for _ in 0..len {
+                    arr.push(self.arbitrary_at(u, depth + 1)?);
+                }
+                RESPType::Set(arr)
+            }
+        })
+    }
+
+    fn arbitrary_line(&self, u: &mut arbitrary::Unstructured) -> arbitrary::Result<String> {
+        let len = u.int_in_range(0..=self.max_line_len)?;
+        let chars = (0..len)
+            .map(|_| u.arbitrary())
+            .collect::<arbitrary::Result<Vec<char>>>()?;
+        Ok(self.line(chars))
+    }
+}
+
+#[cfg(feature = "proptest")]
+impl GenConfig {
+    /// Strategy for values within these bounds. Same as `any_with::<RESP>(config)`.
+    ///
+    /// How often arrays are generated is up to proptest's recursion, the [array weight](Weights::array) is ignored.
+    /// If the [push](Weights::push), [attribute](Weights::attribute), [map](Weights::map) or [set](Weights::set)
+    /// weight is not 0, these are generated instead of arrays by their weights.
+    pub fn strategy(self) -> proptest::strategy::BoxedStrategy<RESPType> {
+        use proptest::prelude::*;
+
+        let config = self;
+        let line = move || {
+            proptest::collection::vec(any::<char>(), 0..=config.max_line_len)
+                .prop_map(move |chars| config.line(chars))
+        };
+        let w = config.weights;
+        let leaves = vec![
+            (
+                w.simple_string,
+                line().prop_map(RESPType::SimpleString).boxed(),
+            ),
+            (w.error, line().prop_map(RESPType::Error).boxed()),
+            (w.integer, any::<i64>().prop_map(RESPType::Integer).boxed()),
+            (w.double, any::<f64>().prop_map(RESPType::Double).boxed()),
+            (w.boolean, any::<bool>().prop_map(RESPType::Boolean).boxed()),
+            (
+                w.big_number,
+                any::<i128>()
+                    .prop_map(|n| RESPType::BigNumber(n.to_string()))
+                    .boxed(),
+            ),
+            (
+                w.verbatim,
+                (
+                    proptest::sample::select(&VERBATIM_FORMATS[..]),
+                    proptest::collection::vec(any::<u8>(), 0..=config.max_bulk_len),
+                )
+                    .prop_map(|(format, data)| RESPType::verbatim(format, data))
+                    .boxed(),
+            ),
+            (w.null, Just(RESPType::Null).boxed()),
+            (
+                w.bulk_string,
+                proptest::option::weighted(
+                    0.875,
+                    proptest::collection::vec(any::<u8>(), 0..=config.max_bulk_len),
+                )
+                .prop_map(RESPType::BulkString)
+                .boxed(),
+            ),
+        ];
+        let leaf = prop::strategy::Union::new_weighted(
+            leaves.into_iter().filter(|(w, _)| *w > 0).collect(),
+        );
+        leaf.prop_recursive(
+            config.max_depth as u32,
+            (config.max_array_len * config.max_depth) as u32,
+            config.max_array_len as u32,
+            move |inner| {
+                let elements = proptest::collection::vec(inner.clone(), 0..=config.max_array_len);
+                let arrays = proptest::option::weighted(0.875, elements.clone())
+                    .prop_map(RESPType::Array)
+                    .boxed();
+                if w.push == 0 && w.attribute == 0 && w.map == 0 && w.set == 0 {
+                    return arrays;
+                }
+                let maps = proptest::collection::vec(
+                    (inner.clone(), inner.clone()),
+                    0..=config.max_map_len(),
+                )
+                .prop_map(RESPType::Map)
+                .boxed();
+                let attributes = (
+                    proptest::collection::vec(
+                        (inner.clone(), inner.clone()),
+                        0..=config.max_attribute_len(),
+                    ),
+                    inner,
+                )
+                    .prop_map(|(attributes, value)| RESPType::attribute(attributes, value))
+                    .boxed();
+                let aggregates = vec![
+                    (w.array, arrays),
+                    (w.push, elements.clone().prop_map(RESPType::Push).boxed()),
+                    (w.attribute, attributes),
+                    (w.map, maps),
+                    (w.set, elements.prop_map(RESPType::Set).boxed()),
+                ];
+                prop::strategy::Union::new_weighted(
+                    aggregates.into_iter().filter(|(w, _)| *w > 0).collect(),
+                )
+                .boxed()
+            },
+        )
+        .boxed()
+    }
+
+    /// Strategy for the serialized bytes of values within these bounds.
+    pub fn wire_strategy(self) -> proptest::strategy::BoxedStrategy<Vec<u8>> {
+        use proptest::prelude::*;
+
+        self.strategy()
+            .prop_map(|value| {
+                let mut bytes = Vec::new();
+                crate::ser::to_writer(&value, &mut bytes).expect("generated values serialize");
+                bytes
+            })
+            .boxed()
+    }
+
+    /// Strategy for bytes that fail to decode as a single value:
+    /// serialized values cut short, or with their type byte replaced by an invalid one.
+    ///
+    /// # Examples
+    /// ```
+    /// use proptest::prelude::*;
+    /// use serde_resp::testing::GenConfig;
+    /// use serde_resp::{de, RESP};
+    ///
+    /// proptest!(|(bytes in GenConfig::default().invalid_wire_strategy())| {
+    ///     prop_assert!(de::from_reader::<RESP, _>(&mut &bytes[..]).is_err());
+    /// });
+    /// ```
+    pub fn invalid_wire_strategy(self) -> proptest::strategy::BoxedStrategy<Vec<u8>> {
+        use proptest::prelude::*;
+
+        let truncated =
+            (self.wire_strategy(), any::<prop::sample::Index>()).prop_map(|(mut bytes, index)| {
+                bytes.truncate(index.index(bytes.len()));
+                bytes
+            });
+        let bad_type = (
+            self.wire_strategy(),
+            any::<u8>().prop_filter("valid type byte", |b| {
+                RESPKind::from_type_byte(*b).is_none()
+            }),
+        )
+            .prop_map(|(mut bytes, b)| {
+                bytes[0] = b;
+                bytes
+            });
+        prop_oneof![truncated, bad_type].boxed()
+    }
+}
+
+/// Generates values within [GenConfig::default](GenConfig::default).
+///
+/// # Examples
+/// ```
+/// use arbitrary::{Arbitrary, Unstructured};
+/// use serde_resp::{de, ser, RESP};
+///
+/// let mut u = Unstructured::new(b"some fuzzer input to build a value from");
+/// let value = RESP::arbitrary(&mut u).unwrap();
+/// let serialized = ser::to_string(&value).unwrap();
+/// assert_eq!(value, de::from_str(&serialized).unwrap());
+/// ```
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for RESPType {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        GenConfig::default().arbitrary(u)
+    }
+}
+
+/// Generates values within the given [GenConfig](GenConfig).
+///
+/// # Examples
+/// ```
+/// use proptest::prelude::*;
+/// use serde_resp::{de, ser, RESP};
+///
+/// proptest!(|(value in any::<RESP>())| {
+///     let mut bytes = Vec::new();
+///     ser::to_writer(&value, &mut bytes).unwrap();
+///     prop_assert_eq!(value, de::from_reader(&mut &bytes[..]).unwrap());
+/// });
+/// ```
+#[cfg(feature = "proptest")]
+impl proptest::arbitrary::Arbitrary for RESPType {
+    type Parameters = GenConfig;
+    type Strategy = proptest::strategy::BoxedStrategy<RESPType>;
+
+    fn arbitrary_with(config: GenConfig) -> Self::Strategy {
+        config.strategy()
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl GenConfig {
+    /// Generate a value within these bounds with a quickcheck generator.
+    ///
+    /// # Examples
+    /// ```
+    /// use quickcheck::Gen;
+    /// use serde_resp::testing::GenConfig;
+    /// use serde_resp::RESP;
+    ///
+    /// let config = GenConfig {
+    ///     max_depth: 0,
+    ///     ..GenConfig::default()
+    /// };
+    /// let value = config.quickcheck(&mut Gen::new(10));
+    /// assert!(!matches!(value, RESP::Array(_)));
+    /// ```
+    pub fn quickcheck(&self, g: &mut quickcheck::Gen) -> RESPType {
+        self.quickcheck_at(g, 0)
+    }
+
+    fn quickcheck_at(&self, g: &mut quickcheck::Gen, depth: usize) -> RESPType {
+        use quickcheck::Arbitrary;
+
+        let kind = self.pick_kind(depth, u32::arbitrary(g));
+        // uniform in 0..=max
+        let mut up_to = |max: usize| usize::arbitrary(g) % max.saturating_add(1);
+        match kind {
+            RESPKind::SimpleString => {
+                RESPType::SimpleString(self.line(String::arbitrary(g).chars().collect()))
+            }
+            RESPKind::Error => RESPType::Error(self.line(String::arbitrary(g).chars().collect())),
+            RESPKind::Integer => RESPType::Integer(i64::arbitrary(g)),
+            RESPKind::BulkString => RESPType::BulkString(if up_to(7) == 0 {
+                None
+            } else {
+                let len = up_to(self.max_bulk_len);
+                Some((0..len).map(|_| u8::arbitrary(g)).collect())
+            }),
+            RESPKind::Array => RESPType::Array(if up_to(7) == 0 {
+                None
+            } else {
+                let len = up_to(self.max_array_len);
+                Some((0..len).map(|_| self.quickcheck_at(g, depth + 1)).collect())
+            }),
+            RESPKind::Push => {
+                let len = up_to(self.max_array_len);
+                RESPType::Push((0..len).map(|_| self.quickcheck_at(g, depth + 1)).collect())
+            }
+            RESPKind::Double => RESPType::Double(f64::arbitrary(g)),
+            RESPKind::Boolean => RESPType::Boolean(bool::arbitrary(g)),
+            RESPKind::BigNumber => RESPType::BigNumber(i128::arbitrary(g).to_string()),
+            RESPKind::Verbatim => {
+                let len = up_to(self.max_bulk_len);
+                let format = *g.choose(&VERBATIM_FORMATS).expect("not empty");
+                RESPType::verbatim(
+                    format,
+                    (0..len).map(|_| u8::arbitrary(g)).collect::<Vec<_>>(),
+                )
+            }
+            RESPKind::Null => RESPType::Null,
+            RESPKind::Attribute => {
+                let len = up_to(self.max_attribute_len());
+                let attributes = (0..len)
+                    .map(|_| {
+                        (
+                            self.quickcheck_at(g, depth + 1),
+                            self.quickcheck_at(g, depth + 1),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                RESPType::attribute(attributes, self.quickcheck_at(g, depth + 1))
+            }
+            RESPKind::Map => {
+                let len = up_to(self.max_map_len());
+                RESPType::Map(
+                    (0..len)
+                        .map(|_| {
+                            (
+                                self.quickcheck_at(g, depth + 1),
+                                self.quickcheck_at(g, depth + 1),
+                            )
+                        })
+                        .collect(),
+                )
+            }
+            RESPKind::Set => {
+                let len = up_to(self.max_array_len);
+                RESPType::Set((0..len).map(|_| self.quickcheck_at(g, depth + 1)).collect())
+            }
+        }
+    }
+}
+
+/// Generates values within [GenConfig::default](GenConfig::default).
+///
+/// Arrays shrink to their elements first, then to shorter arrays of shrunk elements.
+/// Attributes shrink to their value first, then to fewer attributes about a shrunk value.
+/// Maps shrink to fewer keys and values, and sets like arrays.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{de, ser, RESP};
+///
+/// fn round_trip(value: RESP) -> bool {
+///     let mut bytes = Vec::new();
+///     ser::to_writer(&value, &mut bytes).unwrap();
+///     value == de::from_reader(&mut &bytes[..]).unwrap()
+/// }
+///
+/// quickcheck::quickcheck(round_trip as fn(RESP) -> bool);
+/// ```
+#[cfg(feature = "quickcheck")]
+impl quickcheck::Arbitrary for RESPType {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        GenConfig::default().quickcheck(g)
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let valid_line = |s: &String| !s.contains(['\r', '\n']);
+        match self {
+            RESPType::SimpleString(s) => {
+                Box::new(s.shrink().filter(valid_line).map(RESPType::SimpleString))
+            }
+            RESPType::Error(s) => Box::new(s.shrink().filter(valid_line).map(RESPType::Error)),
+            RESPType::Integer(i) => Box::new(i.shrink().map(RESPType::Integer)),
+            RESPType::BulkString(b) => Box::new(b.shrink().map(RESPType::BulkString)),
+            RESPType::Array(a) => {
+                let elements = a.clone().unwrap_or_default();
+                Box::new(elements.into_iter().chain(a.shrink().map(RESPType::Array)))
+            }
+            RESPType::Double(v) => Box::new(v.shrink().map(RESPType::Double)),
+            RESPType::Boolean(v) => Box::new(v.shrink().map(RESPType::Boolean)),
+            RESPType::BigNumber(s) => Box::new(
+                s.parse::<i128>()
+                    .into_iter()
+                    .flat_map(|n| n.shrink())
+                    .map(|n| RESPType::BigNumber(n.to_string())),
+            ),
+            RESPType::Verbatim { format, data } => {
+                let format = format.clone();
+                Box::new(
+                    data.shrink()
+                        .map(move |data| RESPType::verbatim(format.clone(), data)),
+                )
+            }
+            RESPType::Null => quickcheck::empty_shrinker(),
+            RESPType::Push(arr) => Box::new(
+                arr.clone()
+                    .into_iter()
+                    .chain(arr.shrink().map(RESPType::Push)),
+            ),
+            RESPType::Attribute { attributes, value } => Box::new(
+                std::iter::once((**value).clone()).chain(
+                    (attributes.clone(), (**value).clone())
+                        .shrink()
+                        .map(|(attributes, value)| RESPType::attribute(attributes, value)),
+                ),
+            ),
+            RESPType::Map(pairs) => Box::new(pairs.shrink().map(RESPType::Map)),
+            RESPType::Set(arr) => Box::new(
+                arr.clone()
+                    .into_iter()
+                    .chain(arr.shrink().map(RESPType::Set)),
+            ),
+        }
+    }
+}
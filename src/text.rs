@@ -0,0 +1,107 @@
+//! Escaped text format for fixtures, snapshots and golden files.
+//!
+//! The text of a value is its wire format with bytes escaped as in Rust byte strings:
+//! `\r`, `\n`, `\t` and `\\`, and `\xHH` for any other byte outside printable ASCII.
+//! It always fits on a single line, diffs well and converts back to exactly the same bytes.
+//!
+//! # Examples
+//! ```
+//! use serde_resp::{array, bulk, simple, text, RESP};
+//!
+//! let value = array![simple!("OK".to_owned()), bulk!(vec![0xff, b'\n'])];
+//! let escaped = text::to_text(&value).unwrap();
+//! assert_eq!(r"*2\r\n+OK\r\n$2\r\n\xff\n\r\n", escaped);
+//! assert_eq!(value, text::from_text::<RESP>(&escaped).unwrap());
+//! ```
+
+use crate::{de, ser, Error, Result};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Write;
+
+/// Escape bytes into a single line of printable ASCII.
+pub fn escape(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len());
+    for &b in bytes {
+        match b {
+            b'\r' => text.push_str("\\r"),
+            b'\n' => text.push_str("\\n"),
+            b'\t' => text.push_str("\\t"),
+            b'\\' => text.push_str("\\\\"),
+            b' '..=b'~' => text.push(b as char),
+            _ => write!(text, "\\x{:02x}", b).expect("writing to a String never fails"),
+        }
+    }
+    text
+}
+
+/// Get back the bytes escaped by [escape](escape).
+///
+/// Characters other than escapes stand for their utf-8 bytes, so hand-written text may contain them.
+/// Hex digits may be in either case.
+pub fn unescape(text: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        let b = match chars.next().map(|(_, c)| c) {
+            Some('r') => b'\r',
+            Some('n') => b'\n',
+            Some('t') => b'\t',
+            Some('\\') => b'\\',
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).map(|(_, c)| c).collect();
+                match u8::from_str_radix(&hex, 16) {
+                    Ok(b) if hex.len() == 2 => b,
+                    _ => return Err(invalid_escape(i)),
+                }
+            }
+            _ => return Err(invalid_escape(i)),
+        };
+        bytes.push(b);
+    }
+    Ok(bytes)
+}
+
+fn invalid_escape(index: usize) -> Error {
+    Error::Message(format!("invalid escape at index {}", index))
+}
+
+/// Serialize a value into its escaped text.
+pub fn to_text<T>(value: &T) -> Result<String>
+where
+    T: Serialize,
+{
+    Ok(escape(&ser::to_vec(value)?))
+}
+
+/// Deserialize a value from its escaped text. Fails if the text contains anything after the value.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{int, text, RESP};
+///
+/// assert_eq!(int!(42), text::from_text::<RESP>(r":42\r\n").unwrap());
+/// assert!(text::from_text::<RESP>(r":42\r\n:43\r\n").is_err());
+/// assert!(text::from_text::<RESP>(r":42\r\q").is_err());
+/// ```
+pub fn from_text<T>(text: &str) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let bytes = unescape(text)?;
+    let mut reader = &bytes[..];
+    let value = de::from_buf_reader(&mut reader)?;
+    if !reader.is_empty() {
+        return Err(Error::Message(format!(
+            "{} bytes after the value",
+            reader.len()
+        )));
+    }
+    Ok(value)
+}
@@ -0,0 +1,105 @@
+use crate::{Error, RESPType, Result};
+
+/// The RESP3 type a RESP2 reply is upgraded to by [RESPType::upgrade_hint](RESPType::upgrade_hint).
+///
+/// RESP2 replies do not tell a hash from a flat array or a score from a string, so the hint,
+/// which depends on the command, does.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum UpgradeHint {
+    /// Keep the value as it is, as for `GET`.
+    Keep,
+    /// A flat array of alternating keys and values becomes a [map](RESPType::Map), as for `HGETALL`.
+    Map,
+    /// An array becomes a [set](RESPType::Set), as for `SMEMBERS`.
+    Set,
+    /// A bulk string of a number, or an integer, becomes a [double](RESPType::Double), as for `ZSCORE`.
+    Double,
+    /// The integers `0` and `1` become [booleans](RESPType::Boolean).
+    Boolean,
+    /// The elements of an array are upgraded with the hint, such as `ArrayOf(Double)` for `ZMSCORE`.
+    ArrayOf(Box<UpgradeHint>),
+    /// A flat array of alternating keys and values becomes a map, whose values are upgraded with the hint.
+    MapOf(Box<UpgradeHint>),
+}
+
+impl RESPType {
+    /// Convert this RESP2 reply to RESP3 as `hint` says, such as for a server answering RESP3 clients
+    /// with replies built for RESP2. Reverse of [downgrade_to_resp2](RESPType::downgrade_to_resp2).
+    ///
+    /// RESP3 has a single null, so null bulk strings and null arrays become [nulls](RESPType::Null)
+    /// whatever the hint. Errors are kept as they are, since any command can reply one.
+    ///
+    /// # Errors
+    /// Fails if the value does not have the shape `hint` expects, such as an array of an odd number of
+    /// elements for a map.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{array, bulk, bulk_null, UpgradeHint, RESP};
+    ///
+    /// let reply = array![bulk!(b"name".to_vec()), bulk!(b"Alice".to_vec())];
+    /// assert_eq!(
+    ///     RESP::map(vec![(bulk!(b"name".to_vec()), bulk!(b"Alice".to_vec()))]),
+    ///     reply.upgrade_hint(&UpgradeHint::Map).unwrap()
+    /// );
+    ///
+    /// let reply = array![bulk!(b"1.5".to_vec()), bulk_null!()];
+    /// let hint = UpgradeHint::ArrayOf(Box::new(UpgradeHint::Double));
+    /// assert_eq!(
+    ///     RESP::array(vec![RESP::double(1.5), RESP::Null]),
+    ///     reply.upgrade_hint(&hint).unwrap()
+    /// );
+    /// ```
+    pub fn upgrade_hint(self, hint: &UpgradeHint) -> Result<Self> {
+        match (self, hint) {
+            (RESPType::BulkString(None), _) | (RESPType::Array(None), _) => Ok(RESPType::Null),
+            (value @ RESPType::Error(_), _) | (value, UpgradeHint::Keep) => Ok(value),
+            (RESPType::Array(Some(elements)), UpgradeHint::Map) => {
+                pairs(elements, &UpgradeHint::Keep)
+            }
+            (RESPType::Array(Some(elements)), UpgradeHint::MapOf(values)) => {
+                pairs(elements, values)
+            }
+            (RESPType::Array(Some(elements)), UpgradeHint::Set) => Ok(RESPType::Set(elements)),
+            (RESPType::Array(Some(elements)), UpgradeHint::ArrayOf(hint)) => elements
+                .into_iter()
+                .map(|element| element.upgrade_hint(hint))
+                .collect::<Result<_>>()
+                .map(|elements| RESPType::Array(Some(elements))),
+            (RESPType::BulkString(Some(b)), UpgradeHint::Double) => std::str::from_utf8(&b)
+                .ok()
+                .and_then(|text| text.parse().ok())
+                .map(RESPType::Double)
+                .ok_or_else(|| cannot_upgrade(&RESPType::BulkString(Some(b)), hint)),
+            (RESPType::Integer(i), UpgradeHint::Double) => Ok(RESPType::Double(i as f64)),
+            (RESPType::Integer(i @ 0..=1), UpgradeHint::Boolean) => Ok(RESPType::Boolean(i == 1)),
+            (value, hint) => Err(cannot_upgrade(&value, hint)),
+        }
+    }
+}
+
+// A map of the alternating keys and values in `elements`, the values upgraded with `hint`.
+fn pairs(elements: Vec<RESPType>, hint: &UpgradeHint) -> Result<RESPType> {
+    let len = elements.len();
+    let mut pairs = Vec::with_capacity(len / 2);
+    let mut elements = elements.into_iter();
+    while let Some(k) = elements.next() {
+        let v = elements.next().ok_or_else(|| {
+            Error::Message(format!(
+                "cannot upgrade an array of {} elements to a map",
+                len
+            ))
+        })?;
+        pairs.push((k, v.upgrade_hint(hint)?));
+    }
+    Ok(RESPType::Map(pairs))
+}
+
+fn cannot_upgrade(value: &RESPType, hint: &UpgradeHint) -> Error {
+    Error::Message(format!(
+        "cannot upgrade {:?} with hint {:?}",
+        value.truncated(4, 32),
+        hint
+    ))
+}
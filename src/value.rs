@@ -0,0 +1,1092 @@
+//! Convert between Rust types and [RESPType](crate::RESPType) values in memory, without any IO.
+//!
+//! Use [to_value](to_value) to build a value from any `Serialize` type and [from_value](from_value)
+//! to get any `Deserialize` type out of a value. Rust types map to RESP types like this:
+//!
+//! - `bool` and integers: integer (`true` is `1`)
+//! - floats, `char` and strings: bulk string
+//! - byte buffers (e.g. `serde_bytes`): bulk string. Note that `Vec<u8>` itself is a sequence.
+//! - `None`: null bulk string, `Some` is transparent. For sequences, [none_as_null_array](none_as_null_array)
+//!   and [none_as_empty_array](none_as_empty_array) choose how `None` is written.
+//! - `()`: null array
+//! - sequences and tuples: array
+//! - maps and structs: flat array of alternating keys and values, like the reply of `HGETALL`
+//! - enums: bulk string of the variant name for unit variants, otherwise a 2-element array of the
+//!   variant name and its content
+//! - [RESPType](crate::RESPType): itself
+//!
+//! Deserializing is more lenient: integers and floats can also be parsed from strings,
+//! which is how Redis often returns them. Maps and structs can also be read from RESP3 maps,
+//! and sequences from RESP3 sets and pushes. RESP3 attributes are skipped, giving the value they are about,
+//! unless deserializing a [RESPType](crate::RESPType), which keeps them.
+//!
+//! # Examples
+//! ```
+//! use serde::{Deserialize, Serialize};
+//! use serde_resp::{array, bulk, int, value, RESP};
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct User {
+//!     name: String,
+//!     age: u8,
+//! }
+//!
+//! let user = User {
+//!     name: "Alice".to_owned(),
+//!     age: 42,
+//! };
+//! let resp = value::to_value(&user).unwrap();
+//! assert_eq!(
+//!     array![
+//!         bulk!(b"name".to_vec()),
+//!         bulk!(b"Alice".to_vec()),
+//!         bulk!(b"age".to_vec()),
+//!         int!(42),
+//!     ],
+//!     resp
+//! );
+//!
+//! // HGETALL replies numbers as bulk strings
+//! let reply = array![
+//!     bulk!(b"name".to_vec()),
+//!     bulk!(b"Alice".to_vec()),
+//!     bulk!(b"age".to_vec()),
+//!     bulk!(b"42".to_vec()),
+//! ];
+//! let user2: User = value::from_value(reply).unwrap();
+//! assert_eq!(user, user2);
+//! ```
+
+use crate::ser::{
+    ATTRIBUTE_TOKEN, BIG_NUMBER_TOKEN, BOOLEAN_TOKEN, DOUBLE_TOKEN, ENCODED_TOKEN, ERROR_TOKEN,
+    MAP_TOKEN, NULL_TOKEN, PUSH_TOKEN, SET_TOKEN, SIMPLE_STRING_TOKEN, VERBATIM_TOKEN,
+};
+use crate::{Error, RESPType, Result};
+
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde::{ser, Serialize};
+
+use std::str::FromStr;
+use std::vec;
+
+/// Convert `value` into a [RESPType](RESPType).
+///
+/// # Errors
+/// Fails if `value` fails to serialize, or contains an unsigned integer too large for a RESP integer.
+pub fn to_value<T>(value: &T) -> Result<RESPType>
+where
+    T: ?Sized + Serialize,
+{
+    value.serialize(ValueSerializer)
+}
+
+/// Convert a [RESPType](RESPType) into `T`.
+///
+/// # Errors
+/// Fails if the shape of `value` does not match `T`. Tuples and fixed-size arrays such as `[T; N]`
+/// need exactly as many elements as they have.
+///
+/// # Examples
+/// ```
+/// use serde_resp::{array, bulk, int, simple, value, RESP};
+///
+/// // Reply to GEOPOS for one member
+/// let position = array![bulk!(b"13.36".to_vec()), bulk!(b"38.11".to_vec())];
+/// assert_eq!([13.36, 38.11], value::from_value::<[f64; 2]>(position).unwrap());
+///
+/// let err = value::from_value::<[i64; 2]>(array![int!(1)]).unwrap_err();
+/// assert_eq!("invalid length 1, expected an array of length 2", err.to_string());
+///
+/// // Attributes are skipped
+/// let attribute = RESP::Attribute {
+///     attributes: vec![(simple!("ttl".to_owned()), int!(3600))],
+///     value: Box::new(int!(42)),
+/// };
+/// assert_eq!(42, value::from_value::<i64>(attribute.clone()).unwrap());
+/// assert_eq!(attribute.clone(), value::from_value::<RESP>(attribute).unwrap());
+/// ```
+pub fn from_value<T>(value: RESPType) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(value)
+}
+
+fn bulk(bytes: impl Into<Vec<u8>>) -> RESPType {
+    RESPType::BulkString(Some(bytes.into()))
+}
+
+// Serializer building a RESPType.
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = RESPType;
+    type Error = Error;
+    type SerializeSeq = SerializeVec;
+    type SerializeTuple = SerializeVec;
+    type SerializeTupleStruct = SerializeVec;
+    type SerializeTupleVariant = SerializeVec;
+    type SerializeMap = SerializeVec;
+    type SerializeStruct = SerializeVec;
+    type SerializeStructVariant = SerializeVec;
+
+    fn serialize_bool(self, v: bool) -> Result<RESPType> {
+        Ok(RESPType::Integer(v as i64))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<RESPType> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<RESPType> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<RESPType> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<RESPType> {
+        Ok(RESPType::Integer(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<RESPType> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<RESPType> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<RESPType> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<RESPType> {
+        if v > i64::MAX as u64 {
+            return Err(ser::Error::custom(format!(
+                "{} is too large for a RESP integer",
+                v
+            )));
+        }
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<RESPType> {
+        Ok(bulk(v.to_string()))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<RESPType> {
+        Ok(bulk(v.to_string()))
+    }
+
+    fn serialize_char(self, v: char) -> Result<RESPType> {
+        Ok(bulk(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<RESPType> {
+        Ok(bulk(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<RESPType> {
+        Ok(bulk(v))
+    }
+
+    fn serialize_none(self) -> Result<RESPType> {
+        Ok(RESPType::BulkString(None))
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<RESPType>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<RESPType> {
+        Ok(RESPType::Array(None))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<RESPType> {
+        match name {
+            NULL_TOKEN => Ok(RESPType::Null),
+            _ => self.serialize_unit(),
+        }
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<RESPType> {
+        Ok(bulk(variant))
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<RESPType>
+    where
+        T: ?Sized + Serialize,
+    {
+        let value = value.serialize(self)?;
+        let line = |value| -> Result<String> {
+            match value {
+                RESPType::BulkString(Some(bytes)) => Ok(String::from_utf8(bytes)?),
+                _ => Err(ser::Error::custom("expected a string")),
+            }
+        };
+        match name {
+            SIMPLE_STRING_TOKEN => Ok(RESPType::SimpleString(line(value)?)),
+            ERROR_TOKEN => Ok(RESPType::Error(line(value)?)),
+            BIG_NUMBER_TOKEN => Ok(RESPType::BigNumber(line(value)?)),
+            // Floats serialize as their text, which parses back to the same number.
+            DOUBLE_TOKEN => match line(value)?.parse() {
+                Ok(v) => Ok(RESPType::Double(v)),
+                Err(_) => Err(ser::Error::custom("expected a double")),
+            },
+            VERBATIM_TOKEN => match value {
+                RESPType::BulkString(Some(payload)) if crate::de::is_verbatim(&payload) => {
+                    Ok(crate::de::split_verbatim(payload))
+                }
+                _ => Err(ser::Error::custom("expected a verbatim string")),
+            },
+            PUSH_TOKEN => match value {
+                RESPType::Array(Some(elements)) => Ok(RESPType::Push(elements)),
+                _ => Err(ser::Error::custom("expected a sequence")),
+            },
+            ATTRIBUTE_TOKEN => match value {
+                RESPType::Array(Some(elements)) => {
+                    crate::de::join_attribute(elements).ok_or_else(|| {
+                        ser::Error::custom("expected keys and values followed by a value")
+                    })
+                }
+                _ => Err(ser::Error::custom("expected a sequence")),
+            },
+            MAP_TOKEN => match value {
+                RESPType::Array(Some(elements)) => crate::de::join_map(elements)
+                    .ok_or_else(|| ser::Error::custom("expected keys and values")),
+                _ => Err(ser::Error::custom("expected a sequence")),
+            },
+            SET_TOKEN => match value {
+                RESPType::Array(Some(elements)) => Ok(RESPType::Set(elements)),
+                _ => Err(ser::Error::custom("expected a sequence")),
+            },
+            BOOLEAN_TOKEN => match value {
+                RESPType::Integer(i) => Ok(RESPType::Boolean(i != 0)),
+                _ => Err(ser::Error::custom("expected a boolean")),
+            },
+            ENCODED_TOKEN => match value {
+                RESPType::BulkString(Some(bytes)) => crate::de::from_buf_reader(&bytes[..]),
+                _ => Err(ser::Error::custom("expected encoded bytes")),
+            },
+            _ => Ok(value),
+        }
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<RESPType>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(RESPType::Array(Some(vec![
+            bulk(variant),
+            value.serialize(self)?,
+        ])))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeVec> {
+        Ok(SerializeVec::new(len.unwrap_or_default(), None))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeVec> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeVec> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVec> {
+        Ok(SerializeVec::new(len, Some(variant)))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeVec> {
+        Ok(SerializeVec::new(len.unwrap_or_default() * 2, None))
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeVec> {
+        Ok(SerializeVec::new(len * 2, None))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVec> {
+        Ok(SerializeVec::new(len * 2, Some(variant)))
+    }
+}
+
+// Collects the elements of a compound type into an array.
+// For enum variants, the array is wrapped in another array with the variant name.
+struct SerializeVec {
+    vec: Vec<RESPType>,
+    variant: Option<&'static str>,
+}
+
+impl SerializeVec {
+    fn new(capacity: usize, variant: Option<&'static str>) -> Self {
+        SerializeVec {
+            vec: Vec::with_capacity(capacity),
+            variant,
+        }
+    }
+
+    fn push<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.vec.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<RESPType> {
+        let arr = RESPType::Array(Some(self.vec));
+        Ok(match self.variant {
+            None => arr,
+            Some(variant) => RESPType::Array(Some(vec![bulk(variant), arr])),
+        })
+    }
+}
+
+impl ser::SerializeSeq for SerializeVec {
+    type Ok = RESPType;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RESPType> {
+        SerializeVec::end(self)
+    }
+}
+
+impl ser::SerializeTuple for SerializeVec {
+    type Ok = RESPType;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RESPType> {
+        SerializeVec::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeVec {
+    type Ok = RESPType;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RESPType> {
+        SerializeVec::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeVec {
+    type Ok = RESPType;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RESPType> {
+        SerializeVec::end(self)
+    }
+}
+
+impl ser::SerializeMap for SerializeVec {
+    type Ok = RESPType;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(key)
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RESPType> {
+        SerializeVec::end(self)
+    }
+}
+
+impl ser::SerializeStruct for SerializeVec {
+    type Ok = RESPType;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.vec.push(bulk(key));
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RESPType> {
+        SerializeVec::end(self)
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVec {
+    type Ok = RESPType;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.vec.push(bulk(key));
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RESPType> {
+        SerializeVec::end(self)
+    }
+}
+
+impl RESPType {
+    // Text of a simple string, error, big number or bulk string.
+    fn text(&self) -> Option<&[u8]> {
+        match self {
+            RESPType::SimpleString(s) | RESPType::Error(s) | RESPType::BigNumber(s) => {
+                Some(s.as_bytes())
+            }
+            RESPType::BulkString(Some(b)) => Some(b),
+            _ => None,
+        }
+    }
+
+    fn invalid_type<E: de::Error>(&self, exp: &dyn de::Expected) -> E {
+        let unexp = match self {
+            RESPType::SimpleString(s) => de::Unexpected::Str(s),
+            RESPType::Error(s) => de::Unexpected::Other(s),
+            RESPType::Integer(i) => de::Unexpected::Signed(*i),
+            RESPType::BulkString(Some(b)) => de::Unexpected::Bytes(b),
+            RESPType::BulkString(None) => de::Unexpected::Other("null bulk string"),
+            RESPType::Array(None) => de::Unexpected::Other("null array"),
+            RESPType::Array(Some(_)) | RESPType::Push(_) | RESPType::Set(_) => de::Unexpected::Seq,
+            RESPType::Double(v) => de::Unexpected::Float(*v),
+            RESPType::Boolean(v) => de::Unexpected::Bool(*v),
+            RESPType::BigNumber(_) => de::Unexpected::Other("big number"),
+            RESPType::Verbatim { data, .. } => de::Unexpected::Bytes(data),
+            RESPType::Null => de::Unexpected::Other("null"),
+            RESPType::Attribute { .. } => de::Unexpected::Other("attribute"),
+            RESPType::Map(_) => de::Unexpected::Map,
+        };
+        de::Error::invalid_type(unexp, exp)
+    }
+
+    // The value an attribute is about, looking through nested attributes, or this value if it is not one.
+    fn without_attributes(self) -> RESPType {
+        let mut value = self;
+        while let RESPType::Attribute { value: inner, .. } = value {
+            value = *inner;
+        }
+        value
+    }
+
+    // Integer, or text parsed as a number.
+    fn parse<T: FromStr>(&self, exp: &dyn de::Expected) -> Result<T> {
+        self.text()
+            .and_then(|text| std::str::from_utf8(text).ok())
+            .and_then(|text| text.parse().ok())
+            .ok_or_else(|| self.invalid_type(exp))
+    }
+}
+
+macro_rules! deserialize_number {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                match self.without_attributes() {
+                    RESPType::Integer(i) => visitor.visit_i64(i),
+                    RESPType::Double(v) => visitor.visit_f64(v),
+                    value => {
+                        let v = value.parse(&visitor)?;
+                        visitor.$visit(v)
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for RESPType {
+    type Error = Error;
+
+    // Mirrors the visitor calls of the wire format Deserializer.
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            RESPType::SimpleString(s) => visitor.visit_str(&s),
+            RESPType::Error(s) => visitor.visit_string(s),
+            RESPType::Integer(i) => visitor.visit_i64(i),
+            RESPType::BulkString(Some(b)) => visitor.visit_byte_buf(b),
+            RESPType::BulkString(None) => visitor.visit_none(),
+            RESPType::Array(Some(arr)) => visitor.visit_seq(SeqDeserializer::new(arr)),
+            RESPType::Array(None) => visitor.visit_unit(),
+            RESPType::Double(v) => visitor.visit_f64(v),
+            RESPType::Boolean(v) => visitor.visit_bool(v),
+            // Tagged like the wire format does, with the content as a string.
+            RESPType::BigNumber(digits) => visitor.visit_enum(EnumDeserializer {
+                variant: bulk(BIG_NUMBER_TOKEN),
+                content: Some(RESPType::SimpleString(digits)),
+            }),
+            // Same, with the payload `format:data` as a bulk string.
+            RESPType::Verbatim { format, mut data } => {
+                data.splice(0..0, format.bytes().chain(Some(b':')));
+                visitor.visit_enum(EnumDeserializer {
+                    variant: bulk(VERBATIM_TOKEN),
+                    content: Some(RESPType::BulkString(Some(data))),
+                })
+            }
+            RESPType::Null => visitor.visit_enum(EnumDeserializer {
+                variant: bulk(NULL_TOKEN),
+                content: None,
+            }),
+            RESPType::Push(elements) => visitor.visit_enum(EnumDeserializer {
+                variant: bulk(PUSH_TOKEN),
+                content: Some(RESPType::Array(Some(elements))),
+            }),
+            // Same, with the keys and values followed by the value as an array, as on the wire.
+            RESPType::Attribute { attributes, value } => {
+                let mut elements = flatten(attributes);
+                elements.push(*value);
+                visitor.visit_enum(EnumDeserializer {
+                    variant: bulk(ATTRIBUTE_TOKEN),
+                    content: Some(RESPType::Array(Some(elements))),
+                })
+            }
+            RESPType::Map(pairs) => visitor.visit_enum(EnumDeserializer {
+                variant: bulk(MAP_TOKEN),
+                content: Some(RESPType::Array(Some(flatten(pairs)))),
+            }),
+            RESPType::Set(elements) => visitor.visit_enum(EnumDeserializer {
+                variant: bulk(SET_TOKEN),
+                content: Some(RESPType::Array(Some(elements))),
+            }),
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.without_attributes() {
+            RESPType::Integer(i) => visitor.visit_bool(i != 0),
+            RESPType::Boolean(v) => visitor.visit_bool(v),
+            value => Err(value.invalid_type(&visitor)),
+        }
+    }
+
+    deserialize_number! {
+        deserialize_i8 => visit_i64,
+        deserialize_i16 => visit_i64,
+        deserialize_i32 => visit_i64,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u64,
+        deserialize_u16 => visit_u64,
+        deserialize_u32 => visit_u64,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f64,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.without_attributes() {
+            RESPType::SimpleString(s) | RESPType::Error(s) | RESPType::BigNumber(s) => {
+                visitor.visit_string(s)
+            }
+            RESPType::BulkString(Some(b)) | RESPType::Verbatim { data: b, .. } => {
+                match String::from_utf8(b) {
+                    Ok(s) => visitor.visit_string(s),
+                    Err(e) => visitor.visit_byte_buf(e.into_bytes()),
+                }
+            }
+            value => Err(value.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.without_attributes() {
+            RESPType::SimpleString(s) | RESPType::Error(s) => visitor.visit_byte_buf(s.into()),
+            RESPType::BulkString(Some(b)) | RESPType::Verbatim { data: b, .. } => {
+                visitor.visit_byte_buf(b)
+            }
+            value => Err(value.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.without_attributes() {
+            RESPType::BulkString(None) | RESPType::Array(None) | RESPType::Null => {
+                visitor.visit_none()
+            }
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.without_attributes() {
+            RESPType::BulkString(None) | RESPType::Array(None) | RESPType::Null => {
+                visitor.visit_unit()
+            }
+            value => Err(value.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.without_attributes() {
+            RESPType::Array(Some(arr)) | RESPType::Push(arr) | RESPType::Set(arr) => {
+                visitor.visit_seq(SeqDeserializer::new(arr))
+            }
+            // Vec<u8> and friends
+            RESPType::BulkString(Some(b)) => {
+                visitor.visit_seq(de::value::SeqDeserializer::<_, Error>::new(b.into_iter()))
+            }
+            value => Err(value.invalid_type(&visitor)),
+        }
+    }
+
+    // Tuples and fixed-size arrays, which need exactly `len` elements.
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let value = self.without_attributes();
+        let actual = match &value {
+            RESPType::Array(Some(arr)) | RESPType::Push(arr) | RESPType::Set(arr) => arr.len(),
+            RESPType::BulkString(Some(b)) => b.len(),
+            _ => len,
+        };
+        if actual != len {
+            return Err(de::Error::invalid_length(actual, &visitor));
+        }
+        value.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.without_attributes() {
+            RESPType::Array(Some(arr)) => {
+                if arr.len() % 2 != 0 {
+                    return Err(de::Error::invalid_length(
+                        arr.len(),
+                        &"an even number of keys and values",
+                    ));
+                }
+                visitor.visit_map(SeqDeserializer::new(arr))
+            }
+            RESPType::Map(pairs) => visitor.visit_map(SeqDeserializer::new(flatten(pairs))),
+            value => Err(value.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.without_attributes() {
+            RESPType::Array(Some(mut arr)) => {
+                if arr.len() != 2 {
+                    return Err(de::Error::invalid_length(
+                        arr.len(),
+                        &"a variant name and its content",
+                    ));
+                }
+                let content = arr.pop();
+                let variant = arr.pop().unwrap_or(RESPType::Array(None));
+                visitor.visit_enum(EnumDeserializer { variant, content })
+            }
+            variant => visitor.visit_enum(EnumDeserializer {
+                variant,
+                content: None,
+            }),
+        }
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for RESPType {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+// The keys and values of a map or an attribute, as written on the wire.
+pub(crate) fn flatten<T>(pairs: Vec<(T, T)>) -> Vec<T> {
+    let mut elements = Vec::with_capacity(pairs.len() * 2 + 1);
+    for (k, v) in pairs {
+        elements.push(k);
+        elements.push(v);
+    }
+    elements
+}
+
+// Gives out the elements of an array, either one by one or as key-value pairs.
+// Also used for those of a RESPRef.
+pub(crate) struct SeqDeserializer<D> {
+    iter: vec::IntoIter<D>,
+}
+
+impl<D> SeqDeserializer<D> {
+    pub(crate) fn new(arr: Vec<D>) -> Self {
+        SeqDeserializer {
+            iter: arr.into_iter(),
+        }
+    }
+}
+
+impl<'de, D: de::Deserializer<'de, Error = Error>> SeqAccess<'de> for SeqDeserializer<D> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.iter.next().map(|v| seed.deserialize(v)).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+impl<'de, D: de::Deserializer<'de, Error = Error>> MapAccess<'de> for SeqDeserializer<D> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        self.next_element_seed(seed)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some(v) => seed.deserialize(v),
+            None => Err(de::Error::custom("missing value after key")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len() / 2)
+    }
+}
+
+pub(crate) struct EnumDeserializer<D> {
+    pub(crate) variant: D,
+    pub(crate) content: Option<D>,
+}
+
+impl<'de, D: de::Deserializer<'de, Error = Error>> EnumAccess<'de> for EnumDeserializer<D> {
+    type Error = Error;
+    type Variant = VariantDeserializer<D>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, VariantDeserializer<D>)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(self.variant)?;
+        Ok((
+            variant,
+            VariantDeserializer {
+                content: self.content,
+            },
+        ))
+    }
+}
+
+pub(crate) struct VariantDeserializer<D> {
+    content: Option<D>,
+}
+
+impl<D> VariantDeserializer<D> {
+    fn content(self, exp: &dyn de::Expected) -> Result<D> {
+        self.content
+            .ok_or_else(|| de::Error::invalid_type(de::Unexpected::UnitVariant, exp))
+    }
+}
+
+impl<'de, D: de::Deserializer<'de, Error = Error>> VariantAccess<'de> for VariantDeserializer<D> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        match self.content {
+            None => Ok(()),
+            Some(content) => de::Deserialize::deserialize(content),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.content(&"newtype variant")?)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_seq(self.content(&visitor)?, visitor)
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_map(self.content(&visitor)?, visitor)
+    }
+}
+
+/// Serialize `None` of an `Option<Vec<T>>` field as a null array `*-1`, for use with `#[serde(with = "...")]`.
+///
+/// A plain `None` is a null bulk string, since serializers cannot know the type it stands for.
+/// Some commands and servers use null arrays for missing lists, others use empty arrays
+/// (see [none_as_empty_array](none_as_empty_array)). Deserializing accepts any null.
+///
+/// # Examples
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use serde_resp::{array, array_null, bulk, value, RESP};
+///
+/// #[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// struct Reply {
+///     #[serde(with = "value::none_as_null_array")]
+///     tags: Option<Vec<String>>,
+/// }
+///
+/// let reply = Reply { tags: None };
+/// let resp = value::to_value(&reply).unwrap();
+/// assert_eq!(array![bulk!(b"tags".to_vec()), array_null!()], resp);
+/// assert_eq!(reply, value::from_value(resp).unwrap());
+/// ```
+pub mod none_as_null_array {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serialize `None` as a null array, and `Some` as its content.
+    pub fn serialize<S, T>(value: &Option<Vec<T>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        match value {
+            Some(elements) => elements.serialize(serializer),
+            None => serializer.serialize_unit(),
+        }
+    }
+
+    /// Deserialize nulls as `None`, and arrays, even empty, as `Some`.
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        Option::deserialize(deserializer)
+    }
+}
+
+/// Serialize `None` of an `Option<Vec<T>>` field as an empty array `*0`, for use with `#[serde(with = "...")]`.
+///
+/// Refer to [none_as_null_array](none_as_null_array). Deserializing gives `None` for nulls and empty arrays.
+///
+/// # Examples
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use serde_resp::{array, bulk, value, RESP};
+///
+/// #[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// struct Reply {
+///     #[serde(with = "value::none_as_empty_array")]
+///     tags: Option<Vec<String>>,
+/// }
+///
+/// let reply = Reply { tags: None };
+/// let resp = value::to_value(&reply).unwrap();
+/// assert_eq!(array![bulk!(b"tags".to_vec()), array![]], resp);
+/// assert_eq!(reply, value::from_value(resp).unwrap());
+/// ```
+pub mod none_as_empty_array {
+    use serde::ser::SerializeSeq;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serialize `None` as an empty array, and `Some` as its content.
+    pub fn serialize<S, T>(value: &Option<Vec<T>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        match value {
+            Some(elements) => elements.serialize(serializer),
+            None => serializer.serialize_seq(Some(0))?.end(),
+        }
+    }
+
+    /// Deserialize nulls and empty arrays as `None`, and other arrays as `Some`.
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        let value: Option<Vec<T>> = Option::deserialize(deserializer)?;
+        Ok(value.filter(|elements| !elements.is_empty()))
+    }
+}
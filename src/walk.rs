@@ -0,0 +1,148 @@
+use crate::RESPType;
+
+use std::fmt::{self, Display};
+use std::iter;
+
+/// Position of a value nested in arrays, given to the callbacks of [RESPType::walk](RESPType::walk).
+///
+/// Displays as the indices in brackets, e.g. `[1][0]`, or `root` for the outermost value.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Path {
+    indices: Vec<usize>,
+}
+
+impl Path {
+    /// Indices into the nested aggregates, such as arrays, leading to the value. Empty for the outermost value.
+    ///
+    /// The values nested in a map or an attribute are numbered in the order they are written: its keys and values
+    /// alternate, followed by the value an attribute is about.
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Number of aggregates, such as arrays, the value is nested in.
+    pub fn depth(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub(crate) fn push_front(&mut self, index: usize) {
+        self.indices.insert(0, index);
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.indices.is_empty() {
+            return formatter.write_str("root");
+        }
+        for i in &self.indices {
+            write!(formatter, "[{}]", i)?;
+        }
+        Ok(())
+    }
+}
+
+impl RESPType {
+    // Values nested directly in this one, in the order they are written, see `Path::indices`.
+    pub(crate) fn children(&self) -> impl Iterator<Item = &RESPType> + '_ {
+        let (elements, pairs, value): (&[RESPType], &[(RESPType, RESPType)], _) = match self {
+            RESPType::Array(Some(arr)) | RESPType::Push(arr) | RESPType::Set(arr) => {
+                (arr, &[], None)
+            }
+            RESPType::Attribute { attributes, value } => (&[], attributes, Some(&**value)),
+            RESPType::Map(pairs) => (&[], pairs, None),
+            _ => (&[], &[], None),
+        };
+        let pairs = pairs
+            .iter()
+            .flat_map(|(k, v)| iter::once(k).chain(iter::once(v)));
+        elements.iter().chain(pairs).chain(value)
+    }
+
+    pub(crate) fn children_mut(&mut self) -> impl Iterator<Item = &mut RESPType> + '_ {
+        let (elements, pairs, value): (&mut [RESPType], &mut [(RESPType, RESPType)], _) = match self
+        {
+            RESPType::Array(Some(arr)) | RESPType::Push(arr) | RESPType::Set(arr) => {
+                (arr, &mut [], None)
+            }
+            RESPType::Attribute { attributes, value } => (&mut [], attributes, Some(&mut **value)),
+            RESPType::Map(pairs) => (&mut [], pairs, None),
+            _ => (&mut [], &mut [], None),
+        };
+        let pairs = pairs
+            .iter_mut()
+            .flat_map(|(k, v)| iter::once(k).chain(iter::once(v)));
+        elements.iter_mut().chain(pairs).chain(value)
+    }
+
+    /// Call `f` on this value and every value nested in it, depth-first, parents before their elements.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{array, bulk, int, RESP};
+    ///
+    /// let reply = array![int!(1), array![bulk!(vec![0; 2048]), bulk!(b"small".to_vec())]];
+    /// let mut large = Vec::new();
+    /// reply.walk(|path, value| {
+    ///     if let RESP::BulkString(Some(b)) = value {
+    ///         if b.len() > 1024 {
+    ///             large.push(path.to_string());
+    ///         }
+    ///     }
+    /// });
+    /// assert_eq!(vec!["[1][0]"], large);
+    /// ```
+    pub fn walk<F>(&self, mut f: F)
+    where
+        F: FnMut(&Path, &RESPType),
+    {
+        self.walk_at(&mut Path::default(), &mut f);
+    }
+
+    fn walk_at<F>(&self, path: &mut Path, f: &mut F)
+    where
+        F: FnMut(&Path, &RESPType),
+    {
+        f(path, self);
+        for (i, child) in self.children().enumerate() {
+            path.indices.push(i);
+            child.walk_at(path, f);
+            path.indices.pop();
+        }
+    }
+
+    /// Call `f` on this value and every value nested in it, depth-first, allowing it to change them.
+    ///
+    /// Values nested in another are visited after `f` was called on it, so they are the values it left.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_resp::{array, bulk, err_str, int, RESP};
+    ///
+    /// let mut reply = array![int!(1), array![err_str!("ERR secret".to_owned())]];
+    /// reply.walk_mut(|_, value| {
+    ///     if let RESP::Error(_) = value {
+    ///         *value = err_str!("ERR redacted".to_owned());
+    ///     }
+    /// });
+    /// assert_eq!(array![int!(1), array![err_str!("ERR redacted".to_owned())]], reply);
+    /// ```
+    pub fn walk_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Path, &mut RESPType),
+    {
+        self.walk_mut_at(&mut Path::default(), &mut f);
+    }
+
+    fn walk_mut_at<F>(&mut self, path: &mut Path, f: &mut F)
+    where
+        F: FnMut(&Path, &mut RESPType),
+    {
+        f(path, self);
+        for (i, child) in self.children_mut().enumerate() {
+            path.indices.push(i);
+            child.walk_mut_at(path, f);
+            path.indices.pop();
+        }
+    }
+}
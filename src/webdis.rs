@@ -0,0 +1,164 @@
+//! The JSON mapping of [Webdis](https://webd.is), an HTTP interface to Redis. Requires feature `json`.
+//!
+//! Webdis takes commands as URL paths such as `/SET/key/value`, and answers with a JSON object holding
+//! the reply under the name of the command:
+//!
+//! - simple strings: `[true, "OK"]`
+//! - errors: `[false, "ERR message"]`
+//! - integers: numbers
+//! - bulk strings: strings, `null` for null bulk strings
+//! - arrays: arrays of the above, `null` for null arrays
+//! - RESP3 doubles: numbers, `null` for infinities and NaN
+//! - RESP3 booleans: `true` and `false`
+//! - RESP3 big numbers: strings
+//! - RESP3 verbatim strings: strings of their data, without the format
+//! - RESP3 nulls: `null`
+//! - RESP3 pushes: arrays
+//! - RESP3 attributes: their value, without the attributes
+//! - RESP3 maps: arrays of their keys and values, like the RESP2 reply of `HGETALL`
+//! - RESP3 sets: arrays
+//!
+//! Gateways translate paths with [command_from_path](command_from_path) and replies with
+//! [reply_to_json](reply_to_json). Clients of Webdis get the replies back with [reply_from_json](reply_from_json).
+//!
+//! # Examples
+//! ```
+//! use serde_json::json;
+//! use serde_resp::{array, bulk, simple, webdis, RESP};
+//!
+//! let command = webdis::command_from_path("/SET/greeting/hello%20world").unwrap();
+//! assert_eq!(
+//!     array![bulk!(b"SET".to_vec()), bulk!(b"greeting".to_vec()), bulk!(b"hello world".to_vec())],
+//!     command
+//! );
+//!
+//! let json = webdis::reply_to_json("SET", &simple!("OK".to_owned()));
+//! assert_eq!(json!({ "SET": [true, "OK"] }), json);
+//! assert_eq!(("SET".to_owned(), simple!("OK".to_owned())), webdis::reply_from_json(json).unwrap());
+//! ```
+
+use crate::{Error, RESPType, Result};
+
+use serde_json::{json, Value};
+
+/// Get the command of a Webdis URL path, such as `/GET/key`. Each segment of the path is an argument,
+/// percent-decoded.
+///
+/// # Errors
+/// Fails if the path has no command, or if a segment has an invalid percent escape.
+pub fn command_from_path(path: &str) -> Result<RESPType> {
+    let path = path.strip_prefix('/').unwrap_or(path);
+    if path.is_empty() {
+        return Err(Error::Message("no command in path".to_owned()));
+    }
+    let args = path
+        .split('/')
+        .map(percent_decode)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(RESPType::array(args.into_iter().map(RESPType::bulk)))
+}
+
+/// Convert the reply to `command` into the JSON Webdis answers with.
+///
+/// Bulk strings that are not valid UTF-8 are converted lossily, as JSON strings must be text.
+///
+/// # Examples
+/// ```
+/// use serde_json::json;
+/// use serde_resp::{array, bulk, bulk_null, err_str, int, webdis, RESP};
+///
+/// let reply = array![bulk!(b"a".to_vec()), bulk_null!(), int!(3)];
+/// assert_eq!(json!({ "MGET": ["a", null, 3] }), webdis::reply_to_json("MGET", &reply));
+///
+/// let reply = err_str!("ERR unknown command".to_owned());
+/// assert_eq!(json!({ "FOO": [false, "ERR unknown command"] }), webdis::reply_to_json("FOO", &reply));
+/// ```
+pub fn reply_to_json(command: &str, reply: &RESPType) -> Value {
+    let mut object = serde_json::Map::new();
+    object.insert(command.to_owned(), to_json(reply));
+    Value::Object(object)
+}
+
+/// Get the command name and the reply back from the JSON of Webdis. Reverse of [reply_to_json](reply_to_json).
+///
+/// # Errors
+/// Fails if the JSON is not an object with a single member holding a reply.
+pub fn reply_from_json(json: Value) -> Result<(String, RESPType)> {
+    let mut object = match json {
+        Value::Object(object) if object.len() == 1 => object,
+        other => return Err(not_webdis(&other)),
+    };
+    let command = object.keys().next().expect("length checked").clone();
+    let reply = object.remove(&command).expect("key exists");
+    Ok((command, from_json(reply)?))
+}
+
+fn to_json(value: &RESPType) -> Value {
+    match value {
+        RESPType::SimpleString(s) => json!([true, s]),
+        RESPType::Error(s) => json!([false, s]),
+        RESPType::Integer(i) => json!(i),
+        RESPType::BulkString(Some(b)) => json!(String::from_utf8_lossy(b)),
+        RESPType::BulkString(None) | RESPType::Array(None) | RESPType::Null => Value::Null,
+        RESPType::Array(Some(arr)) | RESPType::Push(arr) | RESPType::Set(arr) => {
+            Value::Array(arr.iter().map(to_json).collect())
+        }
+        RESPType::Map(pairs) => Value::Array(
+            pairs
+                .iter()
+                .flat_map(|(k, v)| vec![to_json(k), to_json(v)])
+                .collect(),
+        ),
+        RESPType::Double(v) => json!(v),
+        RESPType::Boolean(v) => json!(v),
+        RESPType::BigNumber(s) => json!(s),
+        RESPType::Verbatim { data, .. } => json!(String::from_utf8_lossy(data)),
+        RESPType::Attribute { value, .. } => to_json(value),
+    }
+}
+
+fn from_json(value: Value) -> Result<RESPType> {
+    match value {
+        Value::Null => Ok(RESPType::bulk_null()),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Ok(RESPType::int(i)),
+            None if n.is_f64() => Ok(RESPType::double(n.as_f64().expect("checked is_f64"))),
+            None => Err(not_webdis(&Value::Number(n))),
+        },
+        Value::String(s) => Ok(RESPType::bulk(s)),
+        Value::Bool(v) => Ok(RESPType::boolean(v)),
+        Value::Array(arr) => match &arr[..] {
+            [Value::Bool(true), Value::String(s)] => Ok(RESPType::simple(s.as_str())),
+            [Value::Bool(false), Value::String(s)] => Ok(RESPType::err_str(s.as_str())),
+            _ => Ok(RESPType::array(
+                arr.into_iter().map(from_json).collect::<Result<Vec<_>>>()?,
+            )),
+        },
+        other => Err(not_webdis(&other)),
+    }
+}
+
+fn not_webdis(value: &Value) -> Error {
+    Error::Message(format!("not a Webdis reply: {}", value))
+}
+
+// Decode `%XX` escapes.
+fn percent_decode(segment: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(segment.len());
+    let mut rest = segment.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        if b != b'%' {
+            bytes.push(b);
+            rest = tail;
+            continue;
+        }
+        let escaped = tail
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| Error::Message(format!("invalid percent escape in {:?}", segment)))?;
+        bytes.push(escaped);
+        rest = &tail[2..];
+    }
+    Ok(bytes)
+}
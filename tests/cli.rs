@@ -0,0 +1,94 @@
+//! Runs the `resp-cli` binary. Requires feature `cli`.
+#![cfg(feature = "cli")]
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+// Run resp-cli with `args`, giving it `input` on stdin.
+fn resp_cli(args: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_resp-cli"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn decode_pretty() {
+    let output = resp_cli(&["decode"], b"*2\r\n$3\r\nGET\r\n:1\r\n+OK\r\n");
+    assert!(output.status.success());
+    assert_eq!(
+        "1) \"GET\"\n2) (integer) 1\nOK\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn decode_prints_values_before_an_error() {
+    let output = resp_cli(&["decode", "--json"], b"+OK\r\n:1\r\n?\r\n:2\r\n");
+    assert!(!output.status.success());
+    assert_eq!(
+        "{\"simple\":\"OK\"}\n1\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+    assert!(!output.stderr.is_empty());
+}
+
+#[test]
+fn decode_literal() {
+    let output = resp_cli(&["decode", "--literal"], b"*1\r\n$4\r\nPING\r\n:1\r\n");
+    assert!(output.status.success());
+    assert_eq!(
+        "*1\\r\\n$4\\r\\nPING\\r\\n\n:1\\r\\n\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn encode_json() {
+    let output = resp_cli(&["encode"], br#"["SET", "a", 1] {"simple": "OK"} null"#);
+    assert!(output.status.success());
+    assert_eq!(
+        &b"*3\r\n$3\r\nSET\r\n$1\r\na\r\n:1\r\n+OK\r\n$-1\r\n"[..],
+        &output.stdout[..]
+    );
+}
+
+#[test]
+fn encode_literal() {
+    let output = resp_cli(
+        &["encode", "--literal"],
+        b"*1\\r\\n$4\\r\\nPING\\r\\n\n\n$2\\r\\n\\xff\\n\\r\\n\n",
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        &b"*1\r\n$4\r\nPING\r\n$2\r\n\xff\n\r\n"[..],
+        &output.stdout[..]
+    );
+
+    let output = resp_cli(&["encode", "--literal"], b":1\\r\\n\n:2\n");
+    assert!(!output.status.success());
+    assert_eq!(&b":1\r\n"[..], &output.stdout[..]);
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("line 2: "));
+}
+
+#[test]
+fn usage() {
+    for args in [
+        &["encode", "--json"][..],
+        &["decode", "--json", "--literal"],
+        &["lint"],
+    ] {
+        let output = resp_cli(args, b"");
+        assert!(!output.status.success());
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .starts_with("usage: "));
+    }
+}